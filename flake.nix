@@ -67,7 +67,7 @@
             checkPhase = ''
               runHook preCheck
               echo "Running tests..."
-              cargo test --all --release
+              cargo test --all --all-features --release
               echo "Running clippy..."
               cargo clippy --all-targets --all-features -- -D warnings
               echo "Checking formatting..."
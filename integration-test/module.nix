@@ -1,19 +1,41 @@
 # Auto-generated NixOS module for test-service
 # Generated from Rust types using serde-nixos
 
-{ config
-, lib
-, pkgs
-, ...
-}:
+{ config, lib, pkgs, utils, ... }:
 
 with lib;
 
 let
   cfg = config.services.test-service;
 
-  # Type definitions
-  # NixOS type definition for ServerConfig
+  databaseConfigType = types.submodule {
+    options = {
+      host = lib.mkOption {
+        type = types.str;
+        description = "Database host";
+        default = "localhost";
+      };
+
+      port = lib.mkOption {
+        type = types.port;
+        description = "Database port";
+        default = 5432;
+      };
+
+      database = lib.mkOption {
+        type = types.str;
+        description = "Database name";
+      };
+
+      ssl = lib.mkOption {
+        type = types.bool;
+        description = "Enable SSL";
+        default = false;
+      };
+
+    };
+  };
+
   serverConfigType = types.submodule {
     options = {
       enable = lib.mkOption {
@@ -23,7 +45,7 @@ let
       };
 
       port = lib.mkOption {
-        type = types.int;
+        type = types.port;
         description = "Server port";
         default = 8080;
       };
@@ -43,104 +65,112 @@ let
     };
   };
 
-  # NixOS type definition for DatabaseConfig
-  databaseConfigType = types.submodule {
-    options = {
-      host = lib.mkOption {
-        type = types.str;
-        description = "Database host";
-        default = "localhost";
-      };
-
-      port = lib.mkOption {
-        type = types.int;
-        description = "Database port";
-        default = 5432;
-      };
-
-      database = lib.mkOption {
-        type = types.str;
-        description = "Database name";
-      };
-
-      ssl = lib.mkOption {
-        type = types.bool;
-        description = "Enable SSL";
-        default = false;
-      };
-
-    };
+  databaseConfigToSettings = cfg: {
+    host = cfg.host;
+    port = cfg.port;
+    database = cfg.database;
+    ssl = cfg.ssl;
+  };
+  serverConfigToSettings = cfg: {
+    enable = cfg.enable;
+    port = cfg.port;
+    bind_address = cfg.bind_address;
+    max_connections = cfg.max_connections;
+  };
+  testServiceConfigToSettings = cfg: {
+    service_name = cfg.service_name;
+    server = serverConfigToSettings cfg.server;
+    database = databaseConfigToSettings cfg.database;
+    debug = cfg.debug;
+    log_level = cfg.log_level;
   };
 
-  # Generate config file
-  configFile = pkgs.writeText "test-service-config.json" (
-    builtins.toJSON {
-      service_name = cfg.service_name;
-      server = {
-        enable = cfg.server.enable;
-        port = cfg.server.port;
-        bind_address = cfg.server.bind_address;
-        max_connections = cfg.server.max_connections;
-      };
-      database = {
-        host = cfg.database.host;
-        port = cfg.database.port;
-        database = cfg.database.database;
-        ssl = cfg.database.ssl;
-      };
-      debug = cfg.debug;
-      log_level = cfg.log_level;
-    }
-  );
+  settings = testServiceConfigToSettings cfg;
+  configFile = pkgs.writeText "test-service.json" (builtins.toJSON settings);
 in
 {
   options.services.test-service = {
-    enable = mkEnableOption "test service";
+    enable = mkEnableOption "Test Service for serde-nixos integration testing";
 
     package = mkOption {
       type = types.package;
       default = pkgs.serde-nixos-test-service or pkgs.hello;
-      description = "The test-service package to use";
+      defaultText = literalExpression "pkgs.serde-nixos-test-service or pkgs.hello";
+      description = "The test-service package to use.";
     };
 
-    service_name = mkOption {
+    openFirewall = mkOption {
+      type = types.bool;
+      default = false;
+      description = "Whether to open the ports of test-service in the firewall.";
+    };
+
+    service_name = lib.mkOption {
       type = types.str;
-      default = "test-service";
       description = "Service name";
+      default = "test-service";
     };
 
-    server = mkOption {
+    server = lib.mkOption {
       type = serverConfigType;
       description = "Server configuration";
     };
 
-    database = mkOption {
+    database = lib.mkOption {
       type = databaseConfigType;
       description = "Database configuration";
     };
 
-    debug = mkOption {
+    debug = lib.mkOption {
       type = types.bool;
-      default = false;
       description = "Enable debug logging";
+      default = false;
     };
 
-    log_level = mkOption {
+    log_level = lib.mkOption {
       type = types.str;
-      default = "info";
       description = "Log level";
+      default = "info";
     };
+
   };
 
   config = mkIf cfg.enable {
+    networking.firewall = mkIf cfg.openFirewall {
+      allowedTCPPorts = [ cfg.server.port ];
+    };
+
     systemd.services.test-service = {
       description = "Test Service for serde-nixos integration testing";
       wantedBy = [ "multi-user.target" ];
       after = [ "network.target" ];
 
       serviceConfig = {
+        ExecStart = "${cfg.package}/bin/test-service ${utils.escapeSystemdExecArgs [ "${configFile}" ]}";
+        DynamicUser = true;
+        NoNewPrivileges = true;
+        PrivateTmp = true;
+        PrivateDevices = true;
+        ProtectSystem = "strict";
+        ProtectHome = true;
+        ProtectProc = "invisible";
+        ProtectKernelTunables = true;
+        ProtectKernelModules = true;
+        ProtectKernelLogs = true;
+        ProtectControlGroups = true;
+        ProtectClock = true;
+        ProtectHostname = true;
+        RestrictNamespaces = true;
+        RestrictRealtime = true;
+        RestrictSUIDSGID = true;
+        RestrictAddressFamilies = [ "AF_UNIX" "AF_INET" "AF_INET6" ];
+        LockPersonality = true;
+        MemoryDenyWriteExecute = true;
+        SystemCallArchitectures = "native";
+        SystemCallFilter = [ "@system-service" "~@privileged" "~@resources" ];
+        CapabilityBoundingSet = "";
+        UMask = "0077";
         Type = "oneshot";
-        ExecStart = "${cfg.package}/bin/test-service ${configFile}";
         RemainAfterExit = true;
       };
     };
@@ -67,6 +67,7 @@ pkgs.nixosTest {
       services.test-service = {
         enable = true;
         package = pkgs.serde-nixos-test-service;
+        openFirewall = true;
         service_name = "integration-test";
 
         server = {
@@ -121,5 +122,8 @@ pkgs.nixosTest {
     machine.succeed("journalctl -u test-service.service | grep 'Debug: true'")
     machine.succeed("journalctl -u test-service.service | grep 'Level: debug'")
     machine.succeed("journalctl -u test-service.service | grep 'Configuration loaded successfully'")
+
+    # `openFirewall` opens the server port
+    machine.succeed("iptables -S nixos-fw | grep -- '--dport 3000'")
   '';
 }
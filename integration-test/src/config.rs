@@ -11,7 +11,7 @@ pub struct ServerConfig {
     pub enable: bool,
 
     /// Server port
-    #[nixos(default = "8080")]
+    #[nixos(port, open_firewall, default = "8080")]
     pub port: u16,
 
     /// Server bind address
@@ -30,7 +30,7 @@ pub struct DatabaseConfig {
     pub host: String,
 
     /// Database port
-    #[nixos(default = "5432")]
+    #[nixos(port, default = "5432")]
     pub port: u16,
 
     /// Database name
@@ -44,6 +44,7 @@ pub struct DatabaseConfig {
 #[derive(Debug, Serialize, Deserialize, NixosType, PartialEq)]
 pub struct TestServiceConfig {
     /// Service name
+    #[nixos(default = "\"test-service\"")]
     pub service_name: String,
 
     /// Server configuration
@@ -0,0 +1,223 @@
+//! Wire types for fields whose serde representation differs from the Rust type.
+//!
+//! A field can change its serialized shape with `#[serde(with = "...")]` or
+//! with `serde_with`'s `#[serde_as(as = "...")]`. The NixOS option has to
+//! describe that shape, so the adapter is looked up here before falling back
+//! to the regular Rust type mapping.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{GenericArgument, Path, PathArguments, Type};
+
+use crate::attributes::EffectiveAttributes;
+use crate::ecosystem::with_adapter_type;
+use crate::type_mapping::{get_generic_inner_type, get_map_value_type, unwrap_option_type};
+
+/// How a field's NixOS type relates to its Rust type.
+pub enum WireType {
+    /// No adapter; the Rust type describes the wire format.
+    Native,
+    /// A known adapter; the expression evaluates to the NixOS type.
+    Adapted(TokenStream),
+    /// An adapter this crate cannot see through.
+    Unknown(String),
+}
+
+/// Resolve the wire type of a field.
+///
+/// `field_type` is the field type with any `Option` already removed, and
+/// `map` maps a Rust type to its regular NixOS type expression (used for `_`
+/// placeholders in `serde_as` types).
+pub fn wire_type(
+    attrs: &EffectiveAttributes,
+    field_type: &Type,
+    map: &dyn Fn(&Type) -> TokenStream,
+) -> WireType {
+    let as_type = attrs
+        .serde_as
+        .clone()
+        .or_else(|| attrs.with.as_deref().and_then(as_type_from_with));
+
+    if let Some(as_type) = as_type {
+        // The caller adds `types.nullOr` for `Option` fields itself
+        let inner_as_type = match &as_type {
+            Type::Path(type_path) if type_name(&as_type).as_deref() == Some("Option") => {
+                generic_types(&type_path.path).first().copied()
+            }
+            _ => None,
+        };
+        return match serde_as_type(inner_as_type.unwrap_or(&as_type), field_type, map) {
+            Some(nixos_type) => WireType::Adapted(nixos_type),
+            None => WireType::Unknown(format!(
+                "#[serde_as(as = \"{}\")]",
+                quote!(#as_type).to_string().replace(' ', "")
+            )),
+        };
+    }
+
+    match attrs.with.as_deref() {
+        Some(with) => match with_adapter_type(with, field_type) {
+            Some(nixos_type) => WireType::Adapted(quote! { #nixos_type }),
+            None => WireType::Unknown(format!("#[serde(with = \"{}\")]", with)),
+        },
+        None => WireType::Native,
+    }
+}
+
+/// Whether a `serde_as` type maps part of the field through its Rust type.
+pub fn uses_field_type(attrs: &EffectiveAttributes) -> bool {
+    attrs.serde_as.as_ref().is_some_and(|as_type| {
+        quote!(#as_type)
+            .to_string()
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .any(|token| token == "_" || token == "Same")
+    })
+}
+
+/// `serde_as` expands `as = "T"` into `with = "::serde_with::As::<T>"`.
+fn as_type_from_with(with: &str) -> Option<Type> {
+    let path: Path = syn::parse_str(with).ok()?;
+    let last = path.segments.last()?;
+    if last.ident != "As" {
+        return None;
+    }
+    match &last.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty.clone()),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+fn generic_types(path: &Path) -> Vec<&Type> {
+    match path.segments.last().map(|s| &s.arguments) {
+        Some(PathArguments::AngleBracketed(args)) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn type_path(ty: &Type) -> Option<&Path> {
+    match ty {
+        Type::Path(type_path) => Some(&type_path.path),
+        _ => None,
+    }
+}
+
+fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// NixOS type of a `serde_with` numeric format parameter such as `u64` or `f64`.
+fn format_type(format: Option<&Type>, fallback: &'static str) -> &'static str {
+    match format.and_then(type_name).as_deref() {
+        Some("u8" | "u16" | "u32" | "u64" | "u128" | "usize") => "types.ints.unsigned",
+        Some("i8" | "i16" | "i32" | "i64" | "i128" | "isize") => "types.int",
+        Some("f32" | "f64") => "types.float",
+        Some("String") => "types.str",
+        _ => fallback,
+    }
+}
+
+/// Wrap a NixOS type expression in a type function, parenthesizing as needed.
+fn apply_type_function(function: &str, inner: TokenStream) -> TokenStream {
+    quote! {
+        {
+            let inner = #inner;
+            if inner.contains(' ') {
+                format!("{} ({})", #function, inner)
+            } else {
+                format!("{} {}", #function, inner)
+            }
+        }
+    }
+}
+
+/// Map a `serde_with` adapter type to the NixOS type of its wire format.
+fn serde_as_type(
+    as_type: &Type,
+    field_type: &Type,
+    map: &dyn Fn(&Type) -> TokenStream,
+) -> Option<TokenStream> {
+    let path = match as_type {
+        Type::Infer(_) => return Some(map(field_type)),
+        Type::Path(type_path) => &type_path.path,
+        _ => return None,
+    };
+    let name = path.segments.last()?.ident.to_string();
+    let args = generic_types(path);
+
+    let nixos_type = match name.as_str() {
+        "Same" => return Some(map(field_type)),
+        "DisplayFromStr" => "types.str",
+        "DurationSeconds"
+        | "DurationMilliSeconds"
+        | "DurationMicroSeconds"
+        | "DurationNanoSeconds" => format_type(args.first().copied(), "types.ints.unsigned"),
+        "DurationSecondsWithFrac"
+        | "DurationMilliSecondsWithFrac"
+        | "DurationMicroSecondsWithFrac"
+        | "DurationNanoSecondsWithFrac" => format_type(args.first().copied(), "types.float"),
+        "TimestampSeconds"
+        | "TimestampMilliSeconds"
+        | "TimestampMicroSeconds"
+        | "TimestampNanoSeconds" => format_type(args.first().copied(), "types.int"),
+        "TimestampSecondsWithFrac"
+        | "TimestampMilliSecondsWithFrac"
+        | "TimestampMicroSecondsWithFrac"
+        | "TimestampNanoSecondsWithFrac" => format_type(args.first().copied(), "types.float"),
+        "StringWithSeparator" => match args.first().and_then(|s| type_name(s)).as_deref() {
+            Some("CommaSeparator") => "types.commas",
+            _ => "types.str",
+        },
+        "OneOrMany" => {
+            let element_type = type_path(field_type)
+                .and_then(get_generic_inner_type)
+                .unwrap_or(field_type);
+            let element = serde_as_type(args.first()?, element_type, map)?;
+            let list = apply_type_function("types.listOf", element.clone());
+            return Some(quote! {
+                {
+                    let element = #element;
+                    let element = if element.contains(' ') {
+                        format!("({})", element)
+                    } else {
+                        element.to_string()
+                    };
+                    format!("types.either {} ({})", element, #list)
+                }
+            });
+        }
+        "Option" => {
+            let inner = serde_as_type(args.first()?, unwrap_option_type(field_type), map)?;
+            return Some(apply_type_function("types.nullOr", inner));
+        }
+        "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashSet" | "BTreeSet" => {
+            let element_type = type_path(field_type)
+                .and_then(get_generic_inner_type)
+                .unwrap_or(field_type);
+            let element = serde_as_type(args.first()?, element_type, map)?;
+            return Some(apply_type_function("types.listOf", element));
+        }
+        "HashMap" | "BTreeMap" => {
+            let value_type = type_path(field_type)
+                .and_then(get_map_value_type)
+                .unwrap_or(field_type);
+            let value = serde_as_type(args.get(1)?, value_type, map)?;
+            return Some(apply_type_function("types.attrsOf", value));
+        }
+        _ => return None,
+    };
+
+    Some(quote! { #nixos_type })
+}
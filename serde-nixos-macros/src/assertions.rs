@@ -0,0 +1,130 @@
+//! NixOS `assertions` from `#[nixos(assert(...))]`.
+//!
+//! Each assertion's Nix expression is evaluated with the struct's value
+//! bound to `self`, so it refers to sibling options as `self.<name>`. A
+//! `let` binding rather than `with` keeps names in scope, such as `lib` or
+//! `cfg`, from shadowing options of the same name. Assertions of nested struct
+//! fields are collected too, guarded by a null check when the field is
+//! nullable.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, Fields, Result};
+
+use crate::adapters::{wire_type, WireType};
+use crate::attributes::{
+    apply_rename_rule_to_field, combine_attributes, parse_nixos_attributes, parse_serde_attributes,
+    Assertion, RenameRule,
+};
+use crate::type_mapping::{get_custom_type_name, is_optional_type, unwrap_option_type};
+
+/// Generate the body of `nixos_assertions_at(value, path)`, which returns
+/// `(assertion, message)` pairs for the struct whose Nix value is `value`.
+pub fn generate_assertions_body(
+    input: &syn::DeriveInput,
+    assertions: &[Assertion],
+    rename_all: Option<RenameRule>,
+) -> Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => Some(fields),
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some(fields) = fields else {
+        if !assertions.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`assert` only applies to structs with named fields",
+            ));
+        }
+        return Ok(quote! { Vec::new() });
+    };
+
+    let own = assertions.iter().map(|assertion| {
+        let expr = &assertion.expr;
+        let message = &assertion.message;
+        quote! {
+            assertions.push((
+                format!("let self = {}; in {}", value, #expr),
+                if path.is_empty() {
+                    #message.to_string()
+                } else {
+                    format!("{}: {}", path, #message)
+                },
+            ));
+        }
+    });
+
+    let mut nested = Vec::new();
+    for field in &fields.named {
+        let field_name = field.ident.as_ref().unwrap();
+        let nixos_attrs = parse_nixos_attributes(&field.attrs)?;
+        let serde_attrs = parse_serde_attributes(&field.attrs)?;
+        let effective_attrs = combine_attributes(nixos_attrs, serde_attrs, None, false);
+        if effective_attrs.skip
+            || effective_attrs.flatten
+            || effective_attrs.type_override.is_some()
+        {
+            continue;
+        }
+        let value_type = unwrap_option_type(&field.ty);
+        if !matches!(
+            wire_type(&effective_attrs, value_type, &|_| quote! {}),
+            WireType::Native
+        ) || get_custom_type_name(value_type).is_none()
+        {
+            continue;
+        }
+
+        let nix_field_name = effective_attrs
+            .name
+            .clone()
+            .unwrap_or_else(|| match rename_all {
+                Some(rule) => apply_rename_rule_to_field(&field_name.to_string(), rule),
+                None => field_name.to_string(),
+            });
+        // Same condition as the `types.nullOr` wrapping of the option
+        let nullable = is_optional_type(&field.ty)
+            || (effective_attrs.optional && !effective_attrs.has_nixos_default());
+        let guard = if nullable {
+            quote! { format!("{} == null || ({})", field_value, assertion) }
+        } else {
+            quote! { assertion }
+        };
+        nested.push(quote! {
+            {
+                let field_value = ::serde_nixos::validation::join_path(value, #nix_field_name);
+                let field_path = ::serde_nixos::validation::join_path(path, #nix_field_name);
+                for (assertion, message) in
+                    <#value_type>::nixos_assertions_at(&field_value, &field_path)
+                {
+                    assertions.push((#guard, message));
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
+        let mut assertions: Vec<(String, String)> = Vec::new();
+        #(#own)*
+        #(#nested)*
+        assertions
+    })
+}
+
+/// Rust-side checks for assertions with a `rust = "..."` expression, for
+/// `nixos_validate_into(&self, path, errors)`.
+pub fn generate_assertion_checks(assertions: &[Assertion]) -> TokenStream {
+    let checks = assertions.iter().filter_map(|assertion| {
+        let rust = assertion.rust.as_ref()?;
+        let message = &assertion.message;
+        Some(quote! {
+            if !(#rust) {
+                errors.push(::serde_nixos::NixosValidationError::new(path, #message));
+            }
+        })
+    });
+    quote! { #(#checks)* }
+}
@@ -1,5 +1,12 @@
 use syn::{token, Attribute, DeriveInput, Token};
 
+use proc_macro2::Span;
+
+use crate::constraints::is_string_type;
+use crate::nix_syntax::check_syntax;
+use crate::patterns::check_pattern;
+use crate::validators::{parse_validator_attributes, ImportedRules};
+
 /// Serde rename strategies for fields and enum variants.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RenameRule {
@@ -46,7 +53,16 @@ fn from_str_or_error(value: &str, span: proc_macro2::Span) -> syn::Result<Self>
 /// Serde attributes parsed at the struct / enum level.
 #[derive(Debug, Default, Clone)]
 pub struct SerdeContainerAttributes {
+    /// `#[serde(rename = "...")]`: the name serde gives the type, e.g. as
+    /// the value of its `tag`
+    pub rename: Option<String>,
     pub rename_all: Option<RenameRule>,
+    /// `#[serde(tag = "...")]`: internally (or, with `content`, adjacently) tagged
+    pub tag: Option<String>,
+    /// `#[serde(content = "...")]`
+    pub content: Option<String>,
+    /// `#[serde(untagged)]`
+    pub untagged: bool,
 }
 
 /// Attributes that can be applied at the struct level with #[nixos(...)]
@@ -54,6 +70,40 @@ pub struct SerdeContainerAttributes {
 pub struct NixosStructAttributes {
     /// Automatically use doc comments as descriptions for all fields
     pub auto_doc: bool,
+    /// Description of the type itself (`description = "..."`)
+    pub description: Option<String>,
+    /// Rename rule for option names only (`rename_all = "..."`); serde
+    /// keys keep their own names
+    pub rename_all: Option<RenameRule>,
+    /// The enum is serialized as its discriminants (`serde_repr`)
+    pub repr: bool,
+    /// Generate a public `nixos_validate()` method
+    pub validate: bool,
+    /// Cross-field checks from `#[nixos(assert(...))]`
+    pub assertions: Vec<Assertion>,
+    /// Option quality problems reported as errors
+    pub deny: DeniedLints,
+}
+
+/// Lints enabled by `#[nixos(deny(...))]`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DeniedLints {
+    /// Fields without a doc comment or `description`
+    pub missing_description: bool,
+    /// Mandatory options, i.e. fields without any default
+    pub missing_default: bool,
+    /// Fields whose NixOS type is a guess (`types.attrs`, unknown adapters)
+    pub untyped_fallback: bool,
+}
+
+/// A cross-field check: `#[nixos(assert(expr = "...", message = "...", rust = "..."))]`.
+#[derive(Debug, Clone)]
+pub struct Assertion {
+    /// Nix expression over the sibling options, with the struct's value bound to `self`
+    pub expr: String,
+    pub message: String,
+    /// Equivalent Rust expression over `self`, checked by `nixos_validate()`
+    pub rust: Option<syn::Expr>,
 }
 
 /// Parse #[nixos(...)] attributes from a struct
@@ -68,6 +118,43 @@ pub fn parse_nixos_struct_attributes(input: &DeriveInput) -> syn::Result<NixosSt
         attr.parse_nested_meta(|meta| {
             if meta.path.is_ident("auto_doc") {
                 struct_attrs.auto_doc = true;
+            } else if meta.path.is_ident("description") {
+                let value = meta.value()?;
+                let s: syn::LitStr = value.parse()?;
+                struct_attrs.description = Some(s.value());
+            } else if meta.path.is_ident("rename_all") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                if matches!(input.data, syn::Data::Enum(_)) {
+                    return Err(syn::Error::new(
+                        s.span(),
+                        "`rename_all` renames struct options; enum values are the serialized \
+                         variant names, use `#[serde(rename_all = \"...\")]` instead",
+                    ));
+                }
+                struct_attrs.rename_all =
+                    Some(RenameRule::from_str_or_error(&s.value(), s.span())?);
+            } else if meta.path.is_ident("repr") {
+                struct_attrs.repr = true;
+            } else if meta.path.is_ident("validate") {
+                struct_attrs.validate = true;
+            } else if meta.path.is_ident("assert") {
+                struct_attrs.assertions.push(parse_assertion(&meta)?);
+            } else if meta.path.is_ident("deny") {
+                meta.parse_nested_meta(|lint| {
+                    if lint.path.is_ident("missing_description") {
+                        struct_attrs.deny.missing_description = true;
+                    } else if lint.path.is_ident("missing_default") {
+                        struct_attrs.deny.missing_default = true;
+                    } else if lint.path.is_ident("untyped_fallback") {
+                        struct_attrs.deny.untyped_fallback = true;
+                    } else {
+                        return Err(lint.error(
+                            "unknown lint; expected `missing_description`, `missing_default` \
+                             or `untyped_fallback`",
+                        ));
+                    }
+                    Ok(())
+                })?;
             } else {
                 return Err(meta.error("unsupported nixos struct attribute"));
             }
@@ -78,6 +165,59 @@ pub fn parse_nixos_struct_attributes(input: &DeriveInput) -> syn::Result<NixosSt
     Ok(struct_attrs)
 }
 
+fn parse_assertion(meta: &syn::meta::ParseNestedMeta<'_>) -> syn::Result<Assertion> {
+    let (mut expr, mut message, mut rust) = (None, None, None);
+    meta.parse_nested_meta(|inner| {
+        let s: syn::LitStr = inner.value()?.parse()?;
+        if inner.path.is_ident("expr") {
+            check_syntax(&s, "assert")?;
+            expr = Some(s.value());
+        } else if inner.path.is_ident("message") {
+            message = Some(s.value());
+        } else if inner.path.is_ident("rust") {
+            rust = Some(s.parse::<syn::Expr>()?);
+        } else {
+            return Err(inner.error("expected `expr`, `message` or `rust`"));
+        }
+        Ok(())
+    })?;
+
+    match (expr, message) {
+        (Some(expr), Some(message)) => Ok(Assertion {
+            expr,
+            message,
+            rust,
+        }),
+        _ => Err(meta.error("`assert` needs `expr = \"...\"` and `message = \"...\"`")),
+    }
+}
+
+/// Get the integer type named by `#[repr(...)]`, if any.
+pub fn parse_repr_int(attrs: &[Attribute]) -> syn::Result<Option<syn::Ident>> {
+    const INT_TYPES: &[&str] = &[
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+    ];
+    let mut repr_int = None;
+
+    for attr in attrs {
+        if !attr.path().is_ident("repr") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            match meta.path.get_ident() {
+                Some(ident) if INT_TYPES.iter().any(|int| ident == int) => {
+                    repr_int = Some(ident.clone());
+                }
+                _ => consume_meta_input(&meta)?,
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(repr_int)
+}
+
 /// Parse serde attributes from a container (struct/enum).
 pub fn parse_serde_container_attributes(
     attrs: &[Attribute],
@@ -90,7 +230,30 @@ pub fn parse_serde_container_attributes(
         }
 
         attr.parse_nested_meta(|meta| {
-            if meta.path.is_ident("rename_all") {
+            if meta.path.is_ident("rename") {
+                if meta.input.peek(Token![=]) {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    serde_attrs.rename = Some(s.value());
+                } else if meta.input.peek(token::Paren) {
+                    let mut serialize_name: Option<String> = None;
+                    let mut deserialize_name: Option<String> = None;
+
+                    meta.parse_nested_meta(|nested| {
+                        if nested.path.is_ident("serialize") {
+                            let s: syn::LitStr = nested.value()?.parse()?;
+                            serialize_name = Some(s.value());
+                        } else if nested.path.is_ident("deserialize") {
+                            let s: syn::LitStr = nested.value()?.parse()?;
+                            deserialize_name = Some(s.value());
+                        } else {
+                            consume_meta_input(&nested)?;
+                        }
+                        Ok(())
+                    })?;
+
+                    serde_attrs.rename = deserialize_name.or(serialize_name);
+                }
+            } else if meta.path.is_ident("rename_all") {
                 if meta.input.peek(Token![=]) {
                     let value = meta.value()?;
                     let s: syn::LitStr = value.parse()?;
@@ -119,6 +282,14 @@ pub fn parse_serde_container_attributes(
 
                     serde_attrs.rename_all = deserialize_rule.or(serialize_rule);
                 }
+            } else if meta.path.is_ident("tag") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                serde_attrs.tag = Some(s.value());
+            } else if meta.path.is_ident("content") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                serde_attrs.content = Some(s.value());
+            } else if meta.path.is_ident("untagged") {
+                serde_attrs.untagged = true;
             } else {
                 // Consume unknown serde container meta to avoid parser errors on value-bearing attributes.
                 consume_meta_input(&meta)?;
@@ -150,8 +321,16 @@ fn consume_meta_input(meta: &syn::meta::ParseNestedMeta<'_>) -> syn::Result<()>
 pub struct NixosFieldAttributes {
     pub description: Option<String>,
     pub default: Option<String>,
+    pub default_span: Option<Span>,
+    /// Rust expression serialized to Nix at runtime (`default_value = "..."`)
+    pub default_value: Option<syn::Expr>,
     pub default_text: Option<String>,
+    /// `default` is the submodule's attribute name (`default_from_name`)
+    pub default_from_name: bool,
     pub example: Option<String>,
+    pub example_span: Option<Span>,
+    /// Rust expression serialized to Nix at runtime (`example_value = "..."`)
+    pub example_value: Option<syn::Expr>,
     pub apply: Option<String>,
     pub internal: bool,
     pub visible: Option<String>,
@@ -159,7 +338,91 @@ pub struct NixosFieldAttributes {
     pub related_packages: Option<String>,
     pub optional: bool,
     pub rename: Option<String>,
+    /// Nested option path (`path = "tls.cert"`) replacing the option name
+    pub path: Option<String>,
+    /// Declare a `<name>File` option instead of the value (`secret`)
+    pub secret: bool,
+    /// The service may write to the path (`writable`)
+    pub writable: bool,
+    /// The field holds ports (`port` / `port = "udp"`)
+    pub port: Option<PortProtocol>,
+    /// The ports are opened by the service's `openFirewall` (`open_firewall`)
+    pub open_firewall: bool,
     pub skip: bool,
+    pub type_override: Option<String>,
+    pub constraints: Constraints,
+    /// Rules from `#[validate(...)]` / `#[garde(...)]`
+    pub imported: ImportedRules,
+}
+
+/// The protocol of a `#[nixos(port)]` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortProtocol {
+    Tcp,
+    Udp,
+}
+
+/// Value constraints from `#[nixos(min, max, pattern, non_empty, unique)]`
+/// or translated `validator` / `garde` rules.
+///
+/// `min`, `max` and `pattern` apply to the value, or to each item of a list
+/// or map; `non_empty`, `unique` and the length bounds apply to the string
+/// or collection itself.
+#[derive(Debug, Default, Clone)]
+pub struct Constraints {
+    /// Inclusive lower bound, as a numeric literal
+    pub min: Option<String>,
+    /// Inclusive upper bound, as a numeric literal
+    pub max: Option<String>,
+    /// POSIX extended regular expression the whole string must match
+    pub pattern: Option<String>,
+    pub non_empty: bool,
+    pub unique: bool,
+    /// Inclusive bounds on the length of a string (in bytes) or collection
+    pub min_length: Option<u64>,
+    pub max_length: Option<u64>,
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        self.min.is_none()
+            && self.max.is_none()
+            && self.pattern.is_none()
+            && !self.non_empty
+            && !self.unique
+            && self.min_length.is_none()
+            && self.max_length.is_none()
+    }
+
+    /// Fill in what `self` leaves unset from `other`.
+    fn or(self, other: Constraints) -> Constraints {
+        Constraints {
+            min: self.min.or(other.min),
+            max: self.max.or(other.max),
+            pattern: self.pattern.or(other.pattern),
+            non_empty: self.non_empty || other.non_empty,
+            unique: self.unique || other.unique,
+            min_length: self.min_length.or(other.min_length),
+            max_length: self.max_length.or(other.max_length),
+        }
+    }
+}
+
+/// Parse a numeric literal such as `1`, `-5` or `0.5`.
+fn parse_number(meta: &syn::meta::ParseNestedMeta<'_>) -> syn::Result<String> {
+    let value = meta.value()?;
+    let negative = value.parse::<Option<Token![-]>>()?.is_some();
+    let lit: syn::Lit = value.parse()?;
+    let digits = match &lit {
+        syn::Lit::Int(int) => int.base10_digits().to_string(),
+        syn::Lit::Float(float) => float.base10_digits().to_string(),
+        _ => return Err(syn::Error::new_spanned(lit, "expected a number")),
+    };
+    Ok(if negative {
+        format!("-{}", digits)
+    } else {
+        digits
+    })
 }
 
 /// Parse #[nixos(...)] attributes from a field
@@ -177,26 +440,66 @@ pub fn parse_nixos_attributes(attrs: &[Attribute]) -> syn::Result<NixosFieldAttr
                 let s: syn::LitStr = value.parse()?;
                 nixos_attrs.description = Some(s.value());
             } else if meta.path.is_ident("default") {
+                if nixos_attrs.default_value.is_some() || nixos_attrs.default_from_name {
+                    return Err(meta.error(
+                        "use only one of `default`, `default_value` and `default_from_name`",
+                    ));
+                }
                 let value = meta.value()?;
                 let s: syn::LitStr = value.parse()?;
+                check_syntax(&s, "default")?;
+                nixos_attrs.default_span = Some(s.span());
                 nixos_attrs.default = Some(s.value());
+            } else if meta.path.is_ident("default_value") {
+                if nixos_attrs.default.is_some() {
+                    return Err(meta.error(
+                        "use only one of `default`, `default_value` and `default_from_name`",
+                    ));
+                }
+                let value = meta.value()?;
+                let s: syn::LitStr = value.parse()?;
+                nixos_attrs.default_value = Some(s.parse()?);
+            } else if meta.path.is_ident("default_from_name") {
+                if nixos_attrs.default.is_some() || nixos_attrs.default_value.is_some() {
+                    return Err(meta.error(
+                        "use only one of `default`, `default_value` and `default_from_name`",
+                    ));
+                }
+                // The attribute name of the enclosing submodule
+                nixos_attrs.default = Some("name".to_string());
+                nixos_attrs.default_from_name = true;
             } else if meta.path.is_ident("default_text") || meta.path.is_ident("defaultText") {
                 let value = meta.value()?;
                 let s: syn::LitStr = value.parse()?;
+                check_syntax(&s, "defaultText")?;
                 nixos_attrs.default_text = Some(s.value());
             } else if meta.path.is_ident("example") {
+                if nixos_attrs.example_value.is_some() {
+                    return Err(meta.error("use either `example` or `example_value`"));
+                }
                 let value = meta.value()?;
                 let s: syn::LitStr = value.parse()?;
+                check_syntax(&s, "example")?;
+                nixos_attrs.example_span = Some(s.span());
                 nixos_attrs.example = Some(s.value());
+            } else if meta.path.is_ident("example_value") {
+                if nixos_attrs.example.is_some() {
+                    return Err(meta.error("use either `example` or `example_value`"));
+                }
+                let value = meta.value()?;
+                let s: syn::LitStr = value.parse()?;
+                nixos_attrs.example_value = Some(s.parse()?);
             } else if meta.path.is_ident("apply") {
                 let value = meta.value()?;
                 let s: syn::LitStr = value.parse()?;
+                check_syntax(&s, "apply")?;
                 nixos_attrs.apply = Some(s.value());
             } else if meta.path.is_ident("internal") {
                 nixos_attrs.internal = true;
             } else if meta.path.is_ident("visible") {
                 let value = meta.value()?;
                 let s: syn::LitStr = value.parse()?;
+                check_syntax(&s, "visible")?;
                 nixos_attrs.visible = Some(s.value());
             } else if meta.path.is_ident("read_only") || meta.path.is_ident("readOnly") {
                 nixos_attrs.read_only = true;
@@ -205,21 +508,88 @@ pub fn parse_nixos_attributes(attrs: &[Attribute]) -> syn::Result<NixosFieldAttr
             {
                 let value = meta.value()?;
                 let s: syn::LitStr = value.parse()?;
+                check_syntax(&s, "relatedPackages")?;
                 nixos_attrs.related_packages = Some(s.value());
             } else if meta.path.is_ident("optional") {
                 nixos_attrs.optional = true;
             } else if meta.path.is_ident("rename") {
+                if nixos_attrs.path.is_some() {
+                    return Err(meta.error("use either `rename` or `path`"));
+                }
                 let value = meta.value()?;
                 let s: syn::LitStr = value.parse()?;
                 nixos_attrs.rename = Some(s.value());
+            } else if meta.path.is_ident("path") {
+                if nixos_attrs.rename.is_some() {
+                    return Err(meta.error("use either `rename` or `path`"));
+                }
+                let s: syn::LitStr = meta.value()?.parse()?;
+                let valid = s.value().split('.').all(|segment| {
+                    segment
+                        .chars()
+                        .next()
+                        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                        && segment
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\''))
+                });
+                if !valid {
+                    return Err(syn::Error::new(
+                        s.span(),
+                        "`path` must be dot-separated Nix identifiers, e.g. \"tls.cert\"",
+                    ));
+                }
+                nixos_attrs.path = Some(s.value());
+            } else if meta.path.is_ident("secret") {
+                nixos_attrs.secret = true;
+            } else if meta.path.is_ident("writable") {
+                nixos_attrs.writable = true;
+            } else if meta.path.is_ident("port") {
+                nixos_attrs.port = Some(if meta.input.peek(Token![=]) {
+                    let value = meta.value()?;
+                    let s: syn::LitStr = value.parse()?;
+                    match s.value().as_str() {
+                        "tcp" => PortProtocol::Tcp,
+                        "udp" => PortProtocol::Udp,
+                        _ => {
+                            return Err(syn::Error::new(
+                                s.span(),
+                                "unknown port protocol; expected \"tcp\" or \"udp\"",
+                            ))
+                        }
+                    }
+                } else {
+                    PortProtocol::Tcp
+                });
+            } else if meta.path.is_ident("open_firewall") {
+                nixos_attrs.open_firewall = true;
             } else if meta.path.is_ident("skip") {
                 nixos_attrs.skip = true;
+            } else if meta.path.is_ident("type") {
+                let value = meta.value()?;
+                let s: syn::LitStr = value.parse()?;
+                check_syntax(&s, "type")?;
+                nixos_attrs.type_override = Some(s.value());
+            } else if meta.path.is_ident("min") {
+                nixos_attrs.constraints.min = Some(parse_number(&meta)?);
+            } else if meta.path.is_ident("max") {
+                nixos_attrs.constraints.max = Some(parse_number(&meta)?);
+            } else if meta.path.is_ident("pattern") {
+                let value = meta.value()?;
+                let s: syn::LitStr = value.parse()?;
+                check_pattern(&s.value()).map_err(|message| syn::Error::new(s.span(), message))?;
+                nixos_attrs.constraints.pattern = Some(s.value());
+            } else if meta.path.is_ident("non_empty") {
+                nixos_attrs.constraints.non_empty = true;
+            } else if meta.path.is_ident("unique") {
+                nixos_attrs.constraints.unique = true;
             } else {
                 return Err(meta.error("unsupported nixos attribute"));
             }
             Ok(())
         })?;
     }
+    nixos_attrs.imported = parse_validator_attributes(attrs)?;
 
     Ok(nixos_attrs)
 }
@@ -278,12 +648,23 @@ pub fn parse_serde_attributes(attrs: &[Attribute]) -> syn::Result<SerdeAttribute
                 // Ignore this serde-only behavior, but consume its value to avoid parse errors.
                 let value = meta.value()?;
                 let _: syn::LitStr = value.parse()?;
-            } else if meta.path.is_ident("alias")
-                || meta.path.is_ident("serialize_with")
-                || meta.path.is_ident("deserialize_with")
-                || meta.path.is_ident("with")
-                || meta.path.is_ident("getter")
+            } else if meta.path.is_ident("with") {
+                let value = meta.value()?;
+                let s: syn::LitStr = value.parse()?;
+                serde_attrs.with = Some(s.value());
+            } else if meta.path.is_ident("serialize_with") || meta.path.is_ident("deserialize_with")
             {
+                // Keep the adapter module so known adapters still map to their wire type
+                let value = meta.value()?;
+                let s: syn::LitStr = value.parse()?;
+                if serde_attrs.with.is_none() {
+                    let function = s.value();
+                    serde_attrs.with = Some(match function.rsplit_once("::") {
+                        Some((module, _)) => module.to_string(),
+                        None => function,
+                    });
+                }
+            } else if meta.path.is_ident("alias") || meta.path.is_ident("getter") {
                 let value = meta.value()?;
                 let _: syn::LitStr = value.parse()?;
             } else if meta.path.is_ident("borrow") {
@@ -319,6 +700,29 @@ pub fn parse_serde_attributes(attrs: &[Attribute]) -> syn::Result<SerdeAttribute
         })?;
     }
 
+    // `serde_with` adapters, unless `#[serde_as]` already rewrote them to `with`
+    for attr in attrs {
+        if !attr.path().is_ident("serde_as") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("as")
+                || meta.path.is_ident("deserialize_as")
+                || meta.path.is_ident("serialize_as")
+            {
+                let value = meta.value()?;
+                let s: syn::LitStr = value.parse()?;
+                if serde_attrs.serde_as.is_none() {
+                    serde_attrs.serde_as = Some(s.parse()?);
+                }
+            } else {
+                consume_meta_input(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+
     Ok(serde_attrs)
 }
 
@@ -331,6 +735,8 @@ pub struct SerdeAttributes {
     pub skip_deserializing: bool,
     pub has_default: bool,
     pub flatten: bool,
+    pub with: Option<String>,
+    pub serde_as: Option<syn::Type>,
 }
 
 /// Combine nixos and serde attributes to get the effective attributes
@@ -349,11 +755,21 @@ pub fn combine_attributes(
     };
 
     EffectiveAttributes {
-        name: nixos.rename.or(serde.rename),
+        name: nixos.path.clone().or(nixos.rename).or(serde.rename.clone()),
+        path: nixos.path.is_some(),
+        secret: nixos.secret,
+        writable: nixos.writable,
+        port: nixos.port,
+        open_firewall: nixos.open_firewall,
+        serde_name: serde.rename,
         description,
         default: nixos.default,
+        default_span: nixos.default_span,
+        default_value: nixos.default_value,
         default_text: nixos.default_text,
         example: nixos.example,
+        example_span: nixos.example_span,
+        example_value: nixos.example_value,
         apply: nixos.apply,
         internal: nixos.internal,
         visible: nixos.visible,
@@ -362,27 +778,74 @@ pub fn combine_attributes(
         optional: nixos.optional || serde.has_default,
         skip: nixos.skip || serde.skip,
         flatten: serde.flatten,
+        with: serde.with,
+        serde_as: serde.serde_as,
+        type_override: nixos.type_override,
+        has_nixos_constraints: !nixos.constraints.is_empty(),
+        constraints: nixos.constraints.or(nixos.imported.constraints.clone()),
+        imported: nixos.imported,
     }
 }
 
 /// The effective attributes after combining nixos and serde attributes
 #[derive(Debug, Clone)]
 pub struct EffectiveAttributes {
+    /// The option name, from `#[nixos(rename)]` or `#[serde(rename)]`
     pub name: Option<String>,
+    /// The serialized name, from `#[serde(rename)]`
+    pub serde_name: Option<String>,
+    /// Whether `name` is a nested option path from `#[nixos(path)]`
+    pub path: bool,
+    /// The option takes a file containing the value (`#[nixos(secret)]`)
+    pub secret: bool,
+    /// The service may write to the path (`#[nixos(writable)]`)
+    pub writable: bool,
+    /// The field holds ports of this protocol (`#[nixos(port)]`)
+    pub port: Option<PortProtocol>,
+    /// The service's `openFirewall` opens the ports (`#[nixos(open_firewall)]`)
+    pub open_firewall: bool,
     pub description: Option<String>,
     pub default: Option<String>,
+    pub default_span: Option<Span>,
+    /// Rust expression serialized to Nix at runtime (`default_value = "..."`)
+    pub default_value: Option<syn::Expr>,
     pub default_text: Option<String>,
     pub example: Option<String>,
+    pub example_span: Option<Span>,
+    /// Rust expression serialized to Nix at runtime (`example_value = "..."`)
+    pub example_value: Option<syn::Expr>,
     pub apply: Option<String>,
     pub internal: bool,
     pub visible: Option<String>,
     pub read_only: bool,
     pub related_packages: Option<String>,
-    #[allow(dead_code)]
     pub optional: bool,
     pub skip: bool,
-    #[allow(dead_code)]
     pub flatten: bool,
+    pub with: Option<String>,
+    pub serde_as: Option<syn::Type>,
+    pub type_override: Option<String>,
+    /// `#[nixos]` constraints, filled in from the imported rules
+    pub constraints: Constraints,
+    /// Whether any constraints come from `#[nixos(...)]` itself
+    pub has_nixos_constraints: bool,
+    pub imported: ImportedRules,
+}
+
+impl EffectiveAttributes {
+    /// Whether the option gets a Nix default from `default` or `default_value`
+    pub fn has_nixos_default(&self) -> bool {
+        self.default.is_some() || self.default_value.is_some()
+    }
+
+    /// Fit the imported rules to the field's type, with any `Option`
+    /// removed: `validator` `length` bounds on strings stay untranslated.
+    pub fn fit_imported_rules(&mut self, ty: &syn::Type) {
+        if is_string_type(ty) && self.imported.untranslate_char_length() {
+            self.constraints.min_length = None;
+            self.constraints.max_length = None;
+        }
+    }
 }
 
 /// Apply serde rename rule to a struct field name (assumed snake_case input).
@@ -0,0 +1,263 @@
+//! NixOS types for `#[nixos(min, max, pattern, non_empty, unique)]`.
+//!
+//! Constraints refine the regular type mapping: `min` / `max` and `pattern`
+//! restrict a number or string (or each item of a list or map), while
+//! `non_empty`, `unique` and the length bounds imported from `validator` /
+//! `garde` restrict the string or collection itself. An `Option` around the
+//! field is handled by the caller.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Path, Result, Type};
+
+use crate::attributes::Constraints;
+use crate::ecosystem::collection_alias;
+use crate::nix_strings::escape_nix_string;
+use crate::type_mapping::{get_generic_inner_type, get_map_value_type, map_to_nixos_type};
+
+pub(crate) const INT_TYPES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+pub(crate) const FLOAT_TYPES: &[&str] = &["f32", "f64"];
+pub(crate) const STRING_TYPES: &[&str] = &["String", "str", "OsString", "OsStr"];
+
+/// Build the constrained NixOS type of `ty` and a sentence for its description.
+pub fn constrained_type(
+    ty: &Type,
+    constraints: &Constraints,
+    map: &dyn Fn(&Type) -> TokenStream,
+) -> Result<(TokenStream, String)> {
+    let path = type_path(ty);
+    let name = path
+        .and_then(|path| path.segments.last())
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+
+    match collection_alias(&name) {
+        "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashSet" | "BTreeSet" => {
+            let element_type = path.and_then(get_generic_inner_type).unwrap_or(ty);
+            let element = scalar_type(element_type, constraints, map)?;
+            let list_function = if constraints.non_empty {
+                "types.nonEmptyListOf"
+            } else {
+                "types.listOf"
+            };
+            let list = quote! {
+                {
+                    let element = #element;
+                    if element.contains(' ') {
+                        format!("{} ({})", #list_function, element)
+                    } else {
+                        format!("{} {}", #list_function, element)
+                    }
+                }
+            };
+            let list = match length_check(constraints, "builtins.length l") {
+                Some(check) => quote! { format!("types.addCheck ({}) (l: {})", #list, #check) },
+                None => list,
+            };
+            let list = if constraints.unique {
+                quote! {
+                    format!(
+                        "types.addCheck ({}) (l: builtins.length (lib.unique l) == builtins.length l)",
+                        #list
+                    )
+                }
+            } else {
+                list
+            };
+            Ok((list, describe(constraints, true)))
+        }
+        "HashMap" | "BTreeMap" => {
+            if constraints.unique {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "`unique` only applies to lists",
+                ));
+            }
+            let value_type = path.and_then(get_map_value_type).unwrap_or(ty);
+            let value = scalar_type(value_type, constraints, map)?;
+            let attrs = map_to_nixos_type(path.and_then(get_generic_inner_type), value);
+            let attrs = if constraints.non_empty {
+                quote! { format!("types.addCheck ({}) (attrs: attrs != {{ }})", #attrs) }
+            } else {
+                attrs
+            };
+            let attrs =
+                match length_check(constraints, "builtins.length (builtins.attrNames attrs)") {
+                    Some(check) => {
+                        quote! { format!("types.addCheck ({}) (attrs: {})", #attrs, #check) }
+                    }
+                    None => attrs,
+                };
+            Ok((attrs, describe(constraints, true)))
+        }
+        _ => {
+            if constraints.unique {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "`unique` only applies to lists",
+                ));
+            }
+            let scalar = scalar_type(ty, constraints, map)?;
+            let non_empty = constraints.non_empty;
+            if non_empty && !STRING_TYPES.contains(&name.as_str()) {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "`non_empty` only applies to strings, lists and maps",
+                ));
+            }
+            let length = length_check(constraints, "builtins.stringLength s");
+            if length.is_some() && !STRING_TYPES.contains(&name.as_str()) {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "`length` only applies to strings, lists and maps",
+                ));
+            }
+            let scalar = match (non_empty, &constraints.pattern) {
+                (true, Some(_)) => {
+                    quote! { format!("types.addCheck ({}) (s: s != \"\")", #scalar) }
+                }
+                (true, None) => quote! { "types.nonEmptyStr" },
+                (false, _) => scalar,
+            };
+            let scalar = match length {
+                Some(check) => quote! {
+                    {
+                        let scalar = #scalar;
+                        if scalar.contains(' ') {
+                            format!("types.addCheck ({}) (s: {})", scalar, #check)
+                        } else {
+                            format!("types.addCheck {} (s: {})", scalar, #check)
+                        }
+                    }
+                },
+                None => scalar,
+            };
+            Ok((scalar, describe(constraints, false)))
+        }
+    }
+}
+
+/// Apply `min`, `max` and `pattern` to a number or string type.
+fn scalar_type(
+    ty: &Type,
+    constraints: &Constraints,
+    map: &dyn Fn(&Type) -> TokenStream,
+) -> Result<TokenStream> {
+    let name = type_path(ty)
+        .and_then(|path| path.segments.last())
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+    let name = name.as_str();
+
+    if constraints.min.is_some() || constraints.max.is_some() {
+        let base = if INT_TYPES.contains(&name) {
+            "types.int"
+        } else if FLOAT_TYPES.contains(&name) {
+            "types.float"
+        } else {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "`min` and `max` only apply to numbers",
+            ));
+        };
+        let min = constraints.min.as_deref().map(nix_number);
+        let max = constraints.max.as_deref().map(nix_number);
+        let nixos_type = match (min, max) {
+            (Some(min), Some(max)) if base == "types.int" => {
+                format!("types.ints.between {} {}", min, max)
+            }
+            (Some(min), Some(max)) => {
+                format!("types.addCheck {} (x: x >= {} && x <= {})", base, min, max)
+            }
+            (Some(min), None) => format!("types.addCheck {} (x: x >= {})", base, min),
+            (None, Some(max)) => format!("types.addCheck {} (x: x <= {})", base, max),
+            (None, None) => unreachable!(),
+        };
+        return Ok(quote! { #nixos_type });
+    }
+
+    if let Some(pattern) = &constraints.pattern {
+        if !STRING_TYPES.contains(&name) {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "`pattern` only applies to strings",
+            ));
+        }
+        let nixos_type = format!("types.strMatching \"{}\"", escape_nix_string(pattern));
+        return Ok(quote! { #nixos_type });
+    }
+
+    Ok(map(ty))
+}
+
+/// Describe the constraints for the option's documentation.
+fn describe(constraints: &Constraints, collection: bool) -> String {
+    let mut value_parts = Vec::new();
+    match (&constraints.min, &constraints.max) {
+        (Some(min), Some(max)) => value_parts.push(format!("between {} and {}", min, max)),
+        (Some(min), None) => value_parts.push(format!("at least {}", min)),
+        (None, Some(max)) => value_parts.push(format!("at most {}", max)),
+        (None, None) => {}
+    }
+    if let Some(pattern) = &constraints.pattern {
+        value_parts.push(format!("matching `{}`", pattern));
+    }
+
+    let mut parts = Vec::new();
+    if constraints.non_empty {
+        parts.push("must not be empty".to_string());
+    }
+    if constraints.unique {
+        parts.push("items must be unique".to_string());
+    }
+    match (constraints.min_length, constraints.max_length) {
+        (Some(min), Some(max)) if min == max => parts.push(format!("length {}", min)),
+        (Some(min), Some(max)) => parts.push(format!("length between {} and {}", min, max)),
+        (Some(min), None) => parts.push(format!("length at least {}", min)),
+        (None, Some(max)) => parts.push(format!("length at most {}", max)),
+        (None, None) => {}
+    }
+    if !value_parts.is_empty() {
+        let subject = if collection { "each item" } else { "value" };
+        parts.push(format!("{} {}", subject, value_parts.join(", ")));
+    }
+
+    format!("Constraints: {}.", parts.join("; "))
+}
+
+/// A Nix condition bounding `measure`, e.g. `builtins.length l >= 1`.
+fn length_check(constraints: &Constraints, measure: &str) -> Option<String> {
+    match (constraints.min_length, constraints.max_length) {
+        (Some(min), Some(max)) if min == max => Some(format!("{} == {}", measure, min)),
+        (Some(min), Some(max)) => Some(format!("{} >= {} && {} <= {}", measure, min, measure, max)),
+        (Some(min), None) => Some(format!("{} >= {}", measure, min)),
+        (None, Some(max)) => Some(format!("{} <= {}", measure, max)),
+        (None, None) => None,
+    }
+}
+
+/// Negative numbers need parentheses as function arguments.
+fn nix_number(number: &str) -> String {
+    if number.starts_with('-') {
+        format!("({})", number)
+    } else {
+        number.to_string()
+    }
+}
+
+/// Whether `ty` is one of [`STRING_TYPES`], or a reference to one
+pub(crate) fn is_string_type(ty: &Type) -> bool {
+    type_path(ty)
+        .and_then(|path| path.segments.last())
+        .is_some_and(|segment| STRING_TYPES.iter().any(|name| segment.ident == name))
+}
+
+fn type_path(ty: &Type) -> Option<&Path> {
+    match ty {
+        Type::Path(type_path) => Some(&type_path.path),
+        Type::Reference(reference) => type_path(&reference.elem),
+        _ => None,
+    }
+}
@@ -0,0 +1,485 @@
+//! Doc comments to NixOS manual markdown.
+//!
+//! Rustdoc and the NixOS manual both use CommonMark, but differ in the
+//! extras: rustdoc-only sections (`# Examples`, `# Panics`, ...) and hidden
+//! `#` lines of Rust code blocks are dropped, headings (not allowed in
+//! option docs) become bold paragraphs, and intra-doc links to fields
+//! become `{option}` references to the field's NixOS option.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::collections::HashMap;
+use syn::Type;
+
+use crate::ecosystem::collection_alias;
+use crate::type_mapping::{get_custom_type_name, get_generic_inner_type, get_map_value_type};
+
+/// Rustdoc sections that only make sense for Rust API docs
+const RUSTDOC_SECTIONS: &[&str] = &["example", "examples", "panics", "errors", "safety"];
+
+/// A field that intra-doc links can refer to.
+pub struct LinkTarget {
+    pub rust_name: String,
+    pub nix_name: String,
+    /// `#[serde(flatten)]` fields have no option of their own
+    pub flatten: bool,
+    pub ty: Type,
+}
+
+/// The fields of the struct being documented.
+pub struct LinkContext<'a> {
+    pub struct_name: String,
+    pub fields: &'a [LinkTarget],
+}
+
+/// A piece of converted documentation.
+#[derive(Debug, PartialEq)]
+pub enum DocPart {
+    Text(String),
+    /// `{option}` reference to the option at this path, relative to the
+    /// struct being documented. Labelled links keep their text in front;
+    /// the text alone is used if the path can't be resolved.
+    OptionRef {
+        path: Vec<PathSegment>,
+        text: String,
+        labelled: bool,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PathSegment {
+    Name(String),
+    /// The NixOS name of a field of another derived type
+    FieldOf(String, String),
+}
+
+/// Convert a doc comment. Without a context, links are reduced to their text.
+pub fn convert_doc(doc: &str, context: Option<&LinkContext>) -> Vec<DocPart> {
+    let definitions = link_definitions(doc);
+    let mut parts = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    let mut rust_block = false;
+    let mut skip_level: Option<usize> = None;
+
+    for line in doc.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some((fence_char, fence_len)) = fence {
+            if is_closing_fence(trimmed, fence_char, fence_len) {
+                fence = None;
+            } else if rust_block && is_hidden_line(trimmed) {
+                continue;
+            }
+            if skip_level.is_none() {
+                let line = match trimmed.strip_prefix("##") {
+                    Some(rest) if rust_block => format!("#{}", rest),
+                    _ => line.to_string(),
+                };
+                push_text(&mut parts, &line);
+                push_text(&mut parts, "\n");
+            }
+            continue;
+        }
+
+        if let Some((fence_char, fence_len, info)) = opening_fence(trimmed) {
+            fence = Some((fence_char, fence_len));
+            rust_block = is_rust_info(info);
+            if skip_level.is_none() {
+                let fence_text = fence_char.to_string().repeat(fence_len);
+                if rust_block {
+                    push_text(&mut parts, &format!("{}rust\n", fence_text));
+                } else {
+                    push_text(&mut parts, &format!("{}\n", line));
+                }
+            }
+            continue;
+        }
+
+        if let Some((level, title)) = heading(trimmed) {
+            if skip_level.is_some_and(|skip| level > skip) {
+                continue;
+            }
+            skip_level = None;
+            if RUSTDOC_SECTIONS.contains(&title.to_lowercase().as_str()) {
+                skip_level = Some(level);
+            } else {
+                push_text(&mut parts, &format!("**{}**\n\n", title));
+            }
+            continue;
+        }
+
+        if skip_level.is_some() || parse_definition(trimmed).is_some_and(|(_, t)| is_rust_path(t)) {
+            continue;
+        }
+
+        convert_links(line, &definitions, context, &mut parts);
+        push_text(&mut parts, "\n");
+    }
+
+    tidy(parts)
+}
+
+/// The converted documentation as text, with links reduced to their text.
+pub fn convert_doc_text(doc: &str) -> String {
+    convert_doc(doc, None)
+        .into_iter()
+        .map(|part| match part {
+            DocPart::Text(text) => text,
+            DocPart::OptionRef { .. } => unreachable!("links are only resolved with a context"),
+        })
+        .collect()
+}
+
+/// An expression evaluating to the documentation as a `String`, with
+/// `option_prefix: &str` in scope.
+pub fn doc_tokens(parts: &[DocPart]) -> TokenStream {
+    let pushes = parts.iter().map(|part| match part {
+        DocPart::Text(text) => quote! { doc.push_str(#text); },
+        DocPart::OptionRef {
+            path,
+            text,
+            labelled,
+        } => {
+            let segments = path.iter().map(|segment| match segment {
+                PathSegment::Name(name) => quote! { Some(#name) },
+                PathSegment::FieldOf(ty, field) => {
+                    let ty: TokenStream = ty.parse().unwrap_or_default();
+                    quote! { <#ty>::nixos_field_name(#field) }
+                }
+            });
+            let label = if *labelled {
+                format!("{} (", text)
+            } else {
+                String::new()
+            };
+            let close = if *labelled { ")" } else { "" };
+            quote! {
+                let path: Option<Vec<&str>> =
+                    [Some(option_prefix), #(#segments),*].into_iter().collect();
+                match path {
+                    Some(path) => {
+                        let path: Vec<&str> = path.into_iter().filter(|s| !s.is_empty()).collect();
+                        doc.push_str(#label);
+                        doc.push_str("{option}`");
+                        doc.push_str(&path.join("."));
+                        doc.push('`');
+                        doc.push_str(#close);
+                    }
+                    None => doc.push_str(#text),
+                }
+            }
+        }
+    });
+    quote! {
+        {
+            let mut doc = String::new();
+            #(#pushes)*
+            doc
+        }
+    }
+}
+
+fn push_text(parts: &mut Vec<DocPart>, text: &str) {
+    match parts.last_mut() {
+        Some(DocPart::Text(last)) => last.push_str(text),
+        _ => parts.push(DocPart::Text(text.to_string())),
+    }
+}
+
+/// Trim the ends and collapse the blank lines left by removed sections.
+fn tidy(parts: Vec<DocPart>) -> Vec<DocPart> {
+    let count = parts.len();
+    let mut tidied = Vec::new();
+    for (index, part) in parts.into_iter().enumerate() {
+        match part {
+            DocPart::Text(mut text) => {
+                while text.contains("\n\n\n") {
+                    text = text.replace("\n\n\n", "\n\n");
+                }
+                if index == 0 {
+                    text = text.trim_start_matches('\n').to_string();
+                }
+                if index + 1 == count {
+                    text = text.trim_end().to_string();
+                }
+                if !text.is_empty() {
+                    tidied.push(DocPart::Text(text));
+                }
+            }
+            part => tidied.push(part),
+        }
+    }
+    tidied
+}
+
+fn opening_fence(line: &str) -> Option<(char, usize, &str)> {
+    let fence_char = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.chars().take_while(|c| *c == fence_char).count();
+    let info = &line[len..];
+    (len >= 3 && !(fence_char == '`' && info.contains('`'))).then_some((
+        fence_char,
+        len,
+        info.trim(),
+    ))
+}
+
+fn is_closing_fence(line: &str, fence_char: char, fence_len: usize) -> bool {
+    let len = line.chars().take_while(|c| *c == fence_char).count();
+    len >= fence_len && line[len..].trim().is_empty()
+}
+
+/// Rustdoc treats unlabelled code blocks and its test attributes as Rust.
+fn is_rust_info(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .all(|token| {
+            matches!(
+                token,
+                "rust"
+                    | "ignore"
+                    | "no_run"
+                    | "should_panic"
+                    | "compile_fail"
+                    | "test_harness"
+                    | "allow_fail"
+            ) || token.starts_with("edition")
+        })
+}
+
+fn is_hidden_line(line: &str) -> bool {
+    line == "#" || line.starts_with("# ")
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+/// `[label]: target`
+fn parse_definition(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix('[')?;
+    let (label, rest) = rest.split_once("]:")?;
+    let target = rest.split_whitespace().next()?;
+    Some((label, target))
+}
+
+fn link_definitions(doc: &str) -> HashMap<String, String> {
+    doc.lines()
+        .filter_map(|line| parse_definition(line.trim_start()))
+        .map(|(label, target)| (label.to_lowercase(), target.to_string()))
+        .collect()
+}
+
+/// Strip rustdoc's disambiguators and backticks from a link target.
+fn clean_target(target: &str) -> &str {
+    let target = target.trim().trim_matches('`');
+    let target = match target.split_once('@') {
+        Some((_, rest)) => rest,
+        None => target,
+    };
+    target.trim_end_matches("()").trim_end_matches('!')
+}
+
+fn is_rust_path(target: &str) -> bool {
+    let target = clean_target(target);
+    !target.is_empty()
+        && target.split("::").all(|segment| {
+            segment
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_')
+                && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+        })
+}
+
+/// Rewrite the links in one line of text outside code blocks.
+fn convert_links(
+    line: &str,
+    definitions: &HashMap<String, String>,
+    context: Option<&LinkContext>,
+    parts: &mut Vec<DocPart>,
+) {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            // Inline code is copied verbatim
+            '`' => {
+                let run = chars[i..].iter().take_while(|c| **c == '`').count();
+                let close = (i + run..chars.len()).find(|&j| {
+                    chars[j..].iter().take_while(|c| **c == '`').count() == run
+                        && (j == 0 || chars[j - 1] != '`')
+                });
+                let end = close.map_or(chars.len(), |j| j + run);
+                push_text(parts, &chars[i..end].iter().collect::<String>());
+                i = end;
+            }
+            '[' => match parse_link(&chars, i, definitions) {
+                Some((text, target, end)) if is_rust_path(&target) => {
+                    let resolved = context.and_then(|context| resolve(&target, context));
+                    // Links whose text just names the target need no label
+                    let target_path = clean_target(&target);
+                    let name = target_path.rsplit("::").next().unwrap_or_default();
+                    let labelled = ![target_path, name].contains(&text.trim_matches('`'));
+                    match resolved {
+                        Some(path) => parts.push(DocPart::OptionRef {
+                            path,
+                            text,
+                            labelled,
+                        }),
+                        // Bare `[word]` is more likely prose than a broken link
+                        None if !labelled && !text.starts_with('`') && !text.contains("::") => {
+                            push_text(parts, &format!("[{}]", text));
+                        }
+                        None => push_text(parts, &text),
+                    }
+                    i = end;
+                }
+                _ => {
+                    push_text(parts, "[");
+                    i += 1;
+                }
+            },
+            c => {
+                push_text(parts, &c.to_string());
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Parse `[text](target)`, `[text][label]` or `[text]` at `start`,
+/// returning the text, the target and the index after the link.
+fn parse_link(
+    chars: &[char],
+    start: usize,
+    definitions: &HashMap<String, String>,
+) -> Option<(String, String, usize)> {
+    let close = (start + 1..chars.len()).find(|&j| chars[j] == ']')?;
+    let text: String = chars[start + 1..close].iter().collect();
+    if text.is_empty() || text.contains('[') {
+        return None;
+    }
+    let after = close + 1;
+    match chars.get(after) {
+        Some('(') => {
+            let end = (after..chars.len()).find(|&j| chars[j] == ')')?;
+            let target: String = chars[after + 1..end].iter().collect();
+            Some((text, target.trim_matches(['<', '>']).to_string(), end + 1))
+        }
+        Some('[') => {
+            let end = (after..chars.len()).find(|&j| chars[j] == ']')?;
+            let label: String = chars[after + 1..end].iter().collect();
+            let label = if label.is_empty() { &text } else { &label };
+            // Rustdoc also accepts a path in place of the label
+            let target = definitions
+                .get(&label.to_lowercase())
+                .cloned()
+                .unwrap_or_else(|| label.clone());
+            Some((text, target, end + 1))
+        }
+        // Shortcut links point at their own text unless defined elsewhere
+        _ => {
+            let target = definitions
+                .get(&text.to_lowercase())
+                .cloned()
+                .unwrap_or_else(|| text.clone());
+            Some((text, target, after))
+        }
+    }
+}
+
+/// Resolve a link to a field of the struct, or of the type of one of its
+/// fields, into an option path.
+fn resolve(target: &str, context: &LinkContext) -> Option<Vec<PathSegment>> {
+    let segments: Vec<&str> = clean_target(target)
+        .split("::")
+        .skip_while(|segment| matches!(*segment, "crate" | "self" | "super"))
+        .collect();
+    let segments = match segments.as_slice() {
+        ["Self", rest @ ..] => rest,
+        [name, rest @ ..] if *name == context.struct_name => rest,
+        all => all,
+    };
+
+    match segments {
+        [name] => {
+            if let Some(field) = context.fields.iter().find(|f| f.rust_name == *name) {
+                return (!field.flatten).then(|| vec![PathSegment::Name(field.nix_name.clone())]);
+            }
+            let field = field_of_type(context, name)?;
+            (!field.flatten).then(|| field_path(field).0)
+        }
+        [.., type_name, field_name] => {
+            let field = field_of_type(context, type_name)?;
+            let (mut path, value_type) = field_path(field);
+            path.push(PathSegment::FieldOf(value_type, field_name.to_string()));
+            Some(path)
+        }
+        [] => None,
+    }
+}
+
+/// The only field whose value type is `type_name`.
+fn field_of_type<'a>(context: &'a LinkContext, type_name: &str) -> Option<&'a LinkTarget> {
+    let mut matches = context.fields.iter().filter(|field| {
+        get_custom_type_name(value_type(&field.ty).0).as_deref() == Some(type_name)
+    });
+    let field = matches.next()?;
+    matches.next().is_none().then_some(field)
+}
+
+/// The option path of `field` down to its custom value type, and that type.
+fn field_path(field: &LinkTarget) -> (Vec<PathSegment>, String) {
+    let (ty, wildcards) = value_type(&field.ty);
+    let mut path = Vec::new();
+    if !field.flatten {
+        path.push(PathSegment::Name(field.nix_name.clone()));
+    }
+    path.extend(
+        wildcards
+            .into_iter()
+            .map(|w| PathSegment::Name(w.to_string())),
+    );
+    let ty = quote!(#ty).to_string();
+    (path, ty)
+}
+
+/// Unwrap options, pointers and collections, collecting the path segments
+/// NixOS uses for collection items (`*` for lists, `<name>` for attribute sets).
+fn value_type(ty: &Type) -> (&Type, Vec<&'static str>) {
+    let Type::Path(type_path) = ty else {
+        return (ty, Vec::new());
+    };
+    let name = type_path
+        .path
+        .segments
+        .last()
+        .map(|s| s.ident.to_string())
+        .unwrap_or_default();
+    let (inner, wildcard) = match collection_alias(&name) {
+        "Option" | "Box" | "Rc" | "Arc" => (get_generic_inner_type(&type_path.path), None),
+        "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashSet" | "BTreeSet" => {
+            (get_generic_inner_type(&type_path.path), Some("*"))
+        }
+        "HashMap" | "BTreeMap" => (get_map_value_type(&type_path.path), Some("<name>")),
+        _ => (None, None),
+    };
+    match inner {
+        Some(inner) => {
+            let (ty, mut wildcards) = value_type(inner);
+            if let Some(wildcard) = wildcard {
+                wildcards.insert(0, wildcard);
+            }
+            (ty, wildcards)
+        }
+        None => (ty, Vec::new()),
+    }
+}
@@ -0,0 +1,192 @@
+//! Feature-gated NixOS type mappings for popular ecosystem crates.
+//!
+//! Each mapping is enabled by the cargo feature of the same name on
+//! `serde-nixos`, which forwards it to this crate. Types are matched by
+//! name; a qualified path must also start with the expected crate, so
+//! `semver::Version` matches while `my_crate::Version` does not.
+
+use syn::{Path, Type};
+
+use crate::type_mapping::{get_generic_inner_type, IPV4_PATTERN, IPV6_PATTERN};
+
+const DATE_PATTERN: &str = "[0-9]{4}-[0-9]{2}-[0-9]{2}";
+const TIME_PATTERN: &str = "[0-9]{1,2}:[0-9]{2}:[0-9]{2}([.][0-9]+)?";
+const OFFSET_PATTERN: &str = "([Zz]|[+-][0-9]{2}:?[0-9]{2})";
+const HUMANTIME_PATTERN: &str = "([0-9]+ *[a-zA-Z]+ *)+";
+/// `time`'s own (`serde-human-readable`) format for `OffsetDateTime`, e.g.
+/// `2023-01-01 00:00:00.0 +00:00:00`
+const TIME_OFFSET_DATE_TIME_PATTERN: &str = "[+-]?[0-9]{4,6}-[0-9]{2}-[0-9]{2} \
+     [0-9]{1,2}:[0-9]{2}:[0-9]{2}[.][0-9]{1,9} [+-][0-9]{2}:[0-9]{2}:[0-9]{2}";
+/// RFC 2822, e.g. `Tue, 01 Jul 2003 10:52:37 +0200`, also with the obsolete
+/// zone names `time` parses
+const RFC2822_PATTERN: &str = "([a-zA-Z]{3}, *)?[0-9]{1,2} [a-zA-Z]{3} [0-9]{4} \
+     [0-9]{2}:[0-9]{2}(:[0-9]{2})? ([+-][0-9]{4}|[a-zA-Z]+)";
+
+fn str_matching(pattern: &str) -> String {
+    format!("types.strMatching \"{}\"", pattern)
+}
+
+fn timestamp_type(with_offset: bool) -> String {
+    let offset = if with_offset { OFFSET_PATTERN } else { "" };
+    str_matching(&format!("{}[Tt ]{}{}", DATE_PATTERN, TIME_PATTERN, offset))
+}
+
+fn humantime_type(inner: Option<&Type>) -> String {
+    match inner.and_then(type_name) {
+        Some(name) if name == "SystemTime" => timestamp_type(true),
+        _ => str_matching(HUMANTIME_PATTERN),
+    }
+}
+
+fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// Whether `path` names one of `names`, either unqualified or under `krate`.
+pub fn is_type(path: &Path, krate: &str, names: &[&str]) -> bool {
+    let Some(last) = path.segments.last() else {
+        return false;
+    };
+    if !names.iter().any(|name| last.ident == name) {
+        return false;
+    }
+
+    path.segments.len() == 1 || path.segments[0].ident == krate
+}
+
+/// Get the NixOS type for an ecosystem type, if its feature is enabled.
+pub fn ecosystem_type(path: &Path) -> Option<String> {
+    if cfg!(feature = "url") && is_type(path, "url", &["Url"]) {
+        return Some(str_matching("[a-zA-Z][a-zA-Z0-9+.-]*:.+"));
+    }
+    if cfg!(feature = "uuid") && is_type(path, "uuid", &["Uuid"]) {
+        return Some(str_matching(
+            "[0-9a-fA-F]{8}-?[0-9a-fA-F]{4}-?[0-9a-fA-F]{4}-?[0-9a-fA-F]{4}-?[0-9a-fA-F]{12}",
+        ));
+    }
+    if cfg!(feature = "chrono") {
+        if is_type(path, "chrono", &["DateTime"]) {
+            return Some(timestamp_type(true));
+        }
+        if is_type(path, "chrono", &["NaiveDateTime"]) {
+            return Some(timestamp_type(false));
+        }
+        if is_type(path, "chrono", &["NaiveDate"]) {
+            return Some(str_matching(DATE_PATTERN));
+        }
+        if is_type(path, "chrono", &["NaiveTime"]) {
+            return Some(str_matching(TIME_PATTERN));
+        }
+    }
+    if cfg!(feature = "time") {
+        if is_type(path, "time", &["OffsetDateTime"]) {
+            return Some(str_matching(TIME_OFFSET_DATE_TIME_PATTERN));
+        }
+        if is_type(path, "time", &["PrimitiveDateTime"]) {
+            return Some(timestamp_type(false));
+        }
+        // A bare `Date` is too common a name to claim without the crate prefix
+        if is_type(path, "time", &["Date"]) && path.segments.len() > 1 {
+            return Some(str_matching(DATE_PATTERN));
+        }
+    }
+    if cfg!(feature = "humantime") && is_type(path, "humantime_serde", &["Serde"]) {
+        return Some(humantime_type(get_generic_inner_type(path)));
+    }
+    if cfg!(feature = "ipnet") {
+        let v4 = format!("({})/[0-9]{{1,2}}", IPV4_PATTERN);
+        let v6 = format!("({})/[0-9]{{1,3}}", IPV6_PATTERN);
+        if is_type(path, "ipnet", &["Ipv4Net"]) {
+            return Some(str_matching(&v4));
+        }
+        if is_type(path, "ipnet", &["Ipv6Net"]) {
+            return Some(str_matching(&v6));
+        }
+        if is_type(path, "ipnet", &["IpNet"]) {
+            return Some(str_matching(&format!("{}|{}", v4, v6)));
+        }
+    }
+    if cfg!(feature = "bytesize") && is_type(path, "bytesize", &["ByteSize"]) {
+        return Some(
+            "types.either types.ints.unsigned (types.strMatching \"[0-9]+([.][0-9]+)? *[a-zA-Z]*\")"
+                .to_string(),
+        );
+    }
+    if cfg!(feature = "semver") {
+        if is_type(path, "semver", &["Version"]) {
+            return Some(str_matching(
+                "(0|[1-9][0-9]*)[.](0|[1-9][0-9]*)[.](0|[1-9][0-9]*)\
+                 (-[0-9A-Za-z.-]+)?([+][0-9A-Za-z.-]+)?",
+            ));
+        }
+        if is_type(path, "semver", &["VersionReq"]) {
+            return Some("types.str".to_string());
+        }
+    }
+    if cfg!(feature = "http") && is_type(path, "http", &["Uri"]) {
+        return Some(str_matching("[^[:space:]]+"));
+    }
+    if cfg!(feature = "regex") && is_type(path, "regex", &["Regex"]) {
+        return Some("types.str".to_string());
+    }
+
+    None
+}
+
+/// Map ecosystem collections onto the std collection they serialize like.
+///
+/// `IndexMap` / `IndexSet` become `HashMap` / `HashSet`; Nix attribute sets
+/// are always sorted, so insertion order is not preserved.
+pub fn collection_alias(type_name: &str) -> &str {
+    match type_name {
+        "IndexMap" if cfg!(feature = "indexmap") => "HashMap",
+        "IndexSet" if cfg!(feature = "indexmap") => "HashSet",
+        _ => type_name,
+    }
+}
+
+/// Get the NixOS wire type for a field using `#[serde(with = "...")]`.
+///
+/// `ty` is the field type with any `Option` already removed; `with` is the
+/// adapter module path. Returns `None` for adapters this crate does not know.
+pub fn with_adapter_type(with: &str, ty: &Type) -> Option<String> {
+    let module = with.strip_suffix("::option").unwrap_or(with);
+
+    if cfg!(feature = "humantime") && module == "humantime_serde" {
+        return Some(humantime_type(Some(ty)));
+    }
+    if cfg!(feature = "time") {
+        match module {
+            "time::serde::rfc3339" => return Some(timestamp_type(true)),
+            "time::serde::rfc2822" => return Some(str_matching(RFC2822_PATTERN)),
+            // Parsing accepts the many ISO 8601 forms, not just the
+            // `+002023-01-01T00:00:00.000000000Z` that is written
+            "time::serde::iso8601" => return Some("types.str".to_string()),
+            "time::serde::timestamp" => return Some("types.int".to_string()),
+            _ => {}
+        }
+    }
+    if cfg!(feature = "chrono") {
+        let module = module.strip_suffix("_option").unwrap_or(module);
+        if matches!(
+            module,
+            "chrono::serde::ts_seconds"
+                | "chrono::serde::ts_milliseconds"
+                | "chrono::serde::ts_microseconds"
+                | "chrono::serde::ts_nanoseconds"
+        ) {
+            return Some("types.int".to_string());
+        }
+    }
+    if cfg!(feature = "http") && module == "http_serde::uri" {
+        return Some(str_matching("[^[:space:]]+"));
+    }
+    if cfg!(feature = "regex") && module == "serde_regex" {
+        return Some("types.str".to_string());
+    }
+
+    None
+}
@@ -2,9 +2,24 @@
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
+mod adapters;
+mod assertions;
 mod attributes;
+mod constraints;
+mod docs;
+mod ecosystem;
+mod lints;
+mod nix_strings;
+mod nix_syntax;
 mod nixos_type;
+mod patterns;
+mod ports;
+mod secrets;
+mod settings;
 mod type_mapping;
+mod validation;
+mod validators;
+mod writable;
 
 /// Derive macro for generating NixOS type definitions from Rust structures.
 ///
@@ -33,7 +48,7 @@
 /// // Generate NixOS type definition
 /// let nixos_module = Config::nixos_type_definition();
 /// ```
-#[proc_macro_derive(NixosType, attributes(nixos))]
+#[proc_macro_derive(NixosType, attributes(nixos, serde_as, validate, garde))]
 pub fn derive_nixos_type(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -0,0 +1,138 @@
+//! Option quality lints enabled by `#[nixos(deny(...))]`.
+//!
+//! Most problems are visible in the derive input and become errors right
+//! away. Whether a custom field type has a default is only known to that
+//! type's own derive, so `missing_default` checks those fields with a
+//! constant evaluated at compile time.
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, Result};
+
+use crate::adapters::{wire_type, WireType};
+use crate::attributes::{
+    combine_attributes, extract_doc_comments, parse_nixos_attributes, parse_serde_attributes,
+    DeniedLints,
+};
+use crate::type_mapping::{
+    get_custom_type_name, is_optional_type, is_untyped_fallback, unwrap_option_type,
+};
+
+/// Check the denied lints, returning compile-time checks for what can't be
+/// decided here.
+pub fn generate_lint_checks(
+    input: &DeriveInput,
+    deny: DeniedLints,
+    auto_doc: bool,
+) -> Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => fields,
+            Fields::Unnamed(_) if deny.untyped_fallback => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    format!(
+                        "`{}` is a tuple struct, which falls back to `types.attrs` \
+                         (denied by `#[nixos(deny(untyped_fallback))]`)",
+                        input.ident
+                    ),
+                ));
+            }
+            _ => return Ok(quote! {}),
+        },
+        _ => return Ok(quote! {}),
+    };
+
+    let mut errors: Option<syn::Error> = None;
+    let mut report = |error: syn::Error| match &mut errors {
+        Some(errors) => errors.combine(error),
+        None => errors = Some(error),
+    };
+    let mut checks = Vec::new();
+
+    for field in &fields.named {
+        let field_name = field.ident.as_ref().unwrap();
+        let nixos_attrs = parse_nixos_attributes(&field.attrs)?;
+        let serde_attrs = parse_serde_attributes(&field.attrs)?;
+        let doc_comment = extract_doc_comments(&field.attrs);
+        let effective_attrs = combine_attributes(nixos_attrs, serde_attrs, doc_comment, auto_doc);
+        // Flattened fields contribute their type's options, not one of their own
+        if effective_attrs.skip || effective_attrs.flatten {
+            continue;
+        }
+        let value_type = unwrap_option_type(&field.ty);
+        let wire = wire_type(&effective_attrs, value_type, &|_| quote! {});
+
+        if deny.missing_description && effective_attrs.description.is_none() {
+            report(syn::Error::new_spanned(
+                field_name,
+                format!(
+                    "field `{}` has no description; add a doc comment or \
+                     `#[nixos(description = \"...\")]` \
+                     (denied by `#[nixos(deny(missing_description))]`)",
+                    field_name
+                ),
+            ));
+        }
+
+        let mandatory = !effective_attrs.optional
+            && !effective_attrs.has_nixos_default()
+            && !is_optional_type(&field.ty);
+        if deny.missing_default && mandatory {
+            let message = format!(
+                "option `{}` is mandatory because it has no default; add \
+                 `#[nixos(default = \"...\")]`, `#[serde(default)]` or make it an `Option` \
+                 (denied by `#[nixos(deny(missing_default))]`)",
+                field_name
+            );
+            let custom = effective_attrs.type_override.is_none()
+                && matches!(wire, WireType::Native)
+                && get_custom_type_name(&field.ty).is_some();
+            if !custom {
+                report(syn::Error::new_spanned(field_name, message));
+            } else if input.generics.params.is_empty() {
+                // Enums may supply their `#[default]` variant. A constant
+                // can't name the generic parameters of the container, so
+                // generic containers leave these fields unchecked.
+                let field_type = &field.ty;
+                checks.push(quote_spanned! {field_type.span()=>
+                    const _: () = assert!(<#field_type>::nixos_has_default(), #message);
+                });
+            }
+        }
+
+        if deny.untyped_fallback && effective_attrs.type_override.is_none() {
+            match &wire {
+                WireType::Native if is_untyped_fallback(value_type) => {
+                    report(syn::Error::new_spanned(
+                        &field.ty,
+                        format!(
+                            "`{}` has no NixOS type mapping and falls back to `types.attrs`; \
+                             set the NixOS type with `#[nixos(type = \"...\")]` \
+                             (denied by `#[nixos(deny(untyped_fallback))]`)",
+                            value_type.to_token_stream().to_string().replace(' ', "")
+                        ),
+                    ));
+                }
+                WireType::Unknown(adapter) => {
+                    report(syn::Error::new_spanned(
+                        field_name,
+                        format!(
+                            "serde-nixos cannot see the wire format of `{}` on field `{}`; \
+                             set the NixOS type with `#[nixos(type = \"...\")]` \
+                             (denied by `#[nixos(deny(untyped_fallback))]`)",
+                            adapter, field_name
+                        ),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    match errors {
+        Some(errors) => Err(errors),
+        None => Ok(quote! { #(#checks)* }),
+    }
+}
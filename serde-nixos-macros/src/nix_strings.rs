@@ -0,0 +1,31 @@
+//! Nix string literals and attribute names written at compile time.
+//!
+//! These match `serde_nixos::utils::escape_nix_string` and
+//! `serde_nixos::utils::nix_attr_name`, which do the same for values only
+//! known at runtime; a proc-macro crate can't share code with it.
+
+/// Escape a string for use in a double-quoted Nix string.
+pub fn escape_nix_string(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "\\${")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+}
+
+/// Quote an attribute name unless it is a valid Nix identifier.
+pub fn nix_attr_name(name: &str) -> String {
+    let is_identifier = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\''));
+    if is_identifier {
+        name.to_string()
+    } else {
+        format!("\"{}\"", escape_nix_string(name))
+    }
+}
@@ -0,0 +1,241 @@
+//! Compile-time checks of the raw Nix snippets in `#[nixos(...)]`.
+//!
+//! Every snippet (`default`, `example`, `apply`, `visible`, ...) is parsed
+//! with `rnix`, so a syntax error is reported at the attribute instead of
+//! at NixOS evaluation time. Defaults and examples that are plain literals
+//! are also compared against the field's Rust type.
+
+use proc_macro2::Span;
+use rnix::types::Wrapper;
+use rnix::{SyntaxKind, SyntaxNode};
+use syn::{LitStr, Type};
+
+use crate::ecosystem::collection_alias;
+use crate::type_mapping::{get_generic_inner_type, is_optional_type, unwrap_option_type};
+
+/// Report Nix syntax errors in the snippet `lit` given for `attribute`.
+pub fn check_syntax(lit: &LitStr, attribute: &str) -> syn::Result<()> {
+    let source = lit.value();
+    let ast = rnix::parse(&source);
+    let Some(error) = ast.errors().into_iter().next() else {
+        return Ok(());
+    };
+
+    let at = |range: rnix::TextRange| {
+        let (start, end) = (usize::from(range.start()), usize::from(range.end()));
+        let text = source.get(start..end).unwrap_or_default();
+        format!("`{}` at offset {}", text, start)
+    };
+    let problem = match error {
+        rnix::parser::ParseError::Unexpected(range)
+        | rnix::parser::ParseError::UnexpectedExtra(range)
+        | rnix::parser::ParseError::UnexpectedWanted(_, range, _)
+        | rnix::parser::ParseError::UnexpectedDoubleBind(range)
+        | rnix::parser::ParseError::DuplicatedArgs(range, _) => format!("unexpected {}", at(range)),
+        rnix::parser::ParseError::UnexpectedEOF
+        | rnix::parser::ParseError::UnexpectedEOFWanted(_) => "unexpected end of input".to_string(),
+        other => other.to_string(),
+    };
+    Err(syn::Error::new(
+        lit.span(),
+        format!("invalid Nix in `{}`: {}", attribute, problem),
+    ))
+}
+
+/// The kind of value a literal Nix expression evaluates to.
+enum Shape {
+    Int(Option<i128>),
+    Float,
+    Bool,
+    Null,
+    Str,
+    Path,
+    List,
+    AttrSet,
+}
+
+impl Shape {
+    fn describe(&self) -> &'static str {
+        match self {
+            Shape::Int(_) => "an integer",
+            Shape::Float => "a float",
+            Shape::Bool => "a boolean",
+            Shape::Null => "`null`",
+            Shape::Str => "a string",
+            Shape::Path => "a path",
+            Shape::List => "a list",
+            Shape::AttrSet => "an attribute set",
+        }
+    }
+}
+
+/// Best-effort check that the literal `snippet` for `attribute` fits the
+/// field type `ty`. Expressions other than plain literals are not checked.
+pub fn check_literal_type(
+    ty: &Type,
+    nullable: bool,
+    snippet: &str,
+    attribute: &str,
+    span: Span,
+) -> syn::Result<()> {
+    let ast = rnix::parse(snippet);
+    let Some(shape) = ast.root().inner().and_then(|node| shape(&node)) else {
+        return Ok(());
+    };
+    let nullable = nullable || is_optional_type(ty);
+    let ty = unwrap_smart_pointers(unwrap_option_type(ty));
+    let Some(name) = type_name(ty) else {
+        return Ok(());
+    };
+
+    if let Shape::Null = shape {
+        if nullable {
+            return Ok(());
+        }
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "`{}` is `null`, but a `{}` option is not nullable",
+                attribute, name
+            ),
+        ));
+    }
+
+    let (fits, wanted) = match collection_alias(&name) {
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64" | "i128"
+        | "isize" | "NonZeroU8" | "NonZeroU16" | "NonZeroU32" | "NonZeroU64" | "NonZeroU128"
+        | "NonZeroUsize" | "NonZeroI8" | "NonZeroI16" | "NonZeroI32" | "NonZeroI64"
+        | "NonZeroI128" | "NonZeroIsize" => {
+            if let Shape::Int(Some(value)) = shape {
+                return check_int_range(&name, value, attribute, span);
+            }
+            (matches!(shape, Shape::Int(_)), "an integer")
+        }
+        "f32" | "f64" => (matches!(shape, Shape::Int(_) | Shape::Float), "a number"),
+        "bool" => (matches!(shape, Shape::Bool), "a boolean"),
+        "String" | "str" | "char" | "OsString" | "OsStr" | "Cow" => {
+            (matches!(shape, Shape::Str), "a string")
+        }
+        "PathBuf" | "Path" => (
+            matches!(shape, Shape::Str | Shape::Path),
+            "a path or string",
+        ),
+        "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashSet" | "BTreeSet" => {
+            (matches!(shape, Shape::List), "a list")
+        }
+        "HashMap" | "BTreeMap" => (matches!(shape, Shape::AttrSet), "an attribute set"),
+        // Custom and ecosystem types map to too many shapes to check
+        _ => return Ok(()),
+    };
+    if fits {
+        return Ok(());
+    }
+    Err(syn::Error::new(
+        span,
+        format!(
+            "`{}` is {}, but `{}` needs {}",
+            attribute,
+            shape.describe(),
+            name,
+            wanted
+        ),
+    ))
+}
+
+fn check_int_range(name: &str, value: i128, attribute: &str, span: Span) -> syn::Result<()> {
+    if name.starts_with("NonZero") && value == 0 {
+        return Err(syn::Error::new(
+            span,
+            format!("`{}` is 0, but `{}` cannot be zero", attribute, name),
+        ));
+    }
+    let unsigned = name.starts_with('u') || name.starts_with("NonZeroU");
+    let bits = match name
+        .trim_start_matches("NonZero")
+        .trim_start_matches(['u', 'i', 'U', 'I'])
+    {
+        "8" => 8,
+        "16" => 16,
+        "32" => 32,
+        // Nix integers are signed 64-bit, which bounds the wider types too
+        _ => 64,
+    };
+    let min = if unsigned { 0 } else { -(1i128 << (bits - 1)) };
+    let max = if unsigned && bits < 64 {
+        (1i128 << bits) - 1
+    } else {
+        (1i128 << (bits - 1)) - 1
+    };
+    if value < min || value > max {
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "`{}` {} is out of range for `{}` ({} to {})",
+                attribute, value, name, min, max
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn shape(node: &SyntaxNode) -> Option<Shape> {
+    match node.kind() {
+        SyntaxKind::NODE_PAREN => node.first_child().and_then(|inner| shape(&inner)),
+        SyntaxKind::NODE_LITERAL => {
+            let token = node.first_token()?;
+            match token.kind() {
+                SyntaxKind::TOKEN_INTEGER => Some(Shape::Int(token.text().parse().ok())),
+                SyntaxKind::TOKEN_FLOAT => Some(Shape::Float),
+                SyntaxKind::TOKEN_PATH => Some(Shape::Path),
+                SyntaxKind::TOKEN_URI => Some(Shape::Str),
+                _ => None,
+            }
+        }
+        SyntaxKind::NODE_UNARY_OP => {
+            let negated = node
+                .children_with_tokens()
+                .any(|child| child.kind() == SyntaxKind::TOKEN_SUB);
+            match node.first_child().and_then(|inner| shape(&inner))? {
+                Shape::Int(value) if negated => Some(Shape::Int(value.map(|v| -v))),
+                Shape::Float if negated => Some(Shape::Float),
+                Shape::Bool if !negated => Some(Shape::Bool),
+                _ => None,
+            }
+        }
+        SyntaxKind::NODE_STRING => Some(Shape::Str),
+        SyntaxKind::NODE_IDENT => match node.text().to_string().as_str() {
+            "true" | "false" => Some(Shape::Bool),
+            "null" => Some(Shape::Null),
+            _ => None,
+        },
+        SyntaxKind::NODE_LIST => Some(Shape::List),
+        SyntaxKind::NODE_ATTR_SET => Some(Shape::AttrSet),
+        _ => None,
+    }
+}
+
+fn unwrap_smart_pointers(ty: &Type) -> &Type {
+    match type_name(ty).as_deref() {
+        Some("Box" | "Rc" | "Arc") => match ty {
+            Type::Path(type_path) => get_generic_inner_type(&type_path.path)
+                .map(unwrap_smart_pointers)
+                .unwrap_or(ty),
+            _ => ty,
+        },
+        _ => ty,
+    }
+}
+
+fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        Type::Reference(reference) => type_name(&reference.elem),
+        Type::Array(_) | Type::Slice(_) => Some("Vec".to_string()),
+        Type::Tuple(tuple) if !tuple.elems.is_empty() => Some("Vec".to_string()),
+        _ => None,
+    }
+}
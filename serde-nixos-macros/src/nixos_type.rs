@@ -1,17 +1,30 @@
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{quote, quote_spanned};
 use std::collections::BTreeSet;
 use syn::{Data, DeriveInput, Fields, FieldsNamed, Ident, Result, Type};
 
+use crate::adapters::{uses_field_type, wire_type, WireType};
+use crate::assertions::{generate_assertion_checks, generate_assertions_body};
 use crate::attributes::{
     apply_rename_rule_to_field, apply_rename_rule_to_variant, combine_attributes,
-    extract_doc_comments, parse_nixos_attributes, parse_nixos_struct_attributes,
-    parse_serde_attributes, parse_serde_container_attributes, RenameRule,
+    extract_doc_comments, parse_nixos_attributes, parse_nixos_struct_attributes, parse_repr_int,
+    parse_serde_attributes, parse_serde_container_attributes, EffectiveAttributes, RenameRule,
 };
+use crate::constraints::constrained_type;
+use crate::docs::{convert_doc, convert_doc_text, doc_tokens, LinkContext, LinkTarget};
+use crate::ecosystem::collection_alias;
+use crate::lints::generate_lint_checks;
+use crate::nix_syntax::check_literal_type;
+use crate::ports::{generate_firewall_ports, port_type};
+use crate::secrets::{generate_secrets, secret_option};
+use crate::settings::{generate_to_settings_function, to_settings_name};
 use crate::type_mapping::{
-    get_custom_type_name, get_generic_inner_type, get_map_value_type, is_optional_type,
-    rust_type_to_nixos, unwrap_option_type,
+    array_to_nixos_type, get_custom_type_name, get_generic_inner_type, get_map_value_type,
+    is_optional_type, map_key_check, map_to_nixos_type, rust_type_to_nixos, tuple_to_nixos_type,
+    unwrap_option_type,
 };
+use crate::validation::generate_validate_body;
+use crate::writable::generate_writable_paths;
 
 pub fn expand_nixos_type(input: &DeriveInput) -> Result<TokenStream> {
     let name = &input.ident;
@@ -20,17 +33,33 @@ pub fn expand_nixos_type(input: &DeriveInput) -> Result<TokenStream> {
     // Parse struct-level attributes
     let struct_attrs = parse_nixos_struct_attributes(input)?;
     let serde_container_attrs = parse_serde_container_attributes(&input.attrs)?;
+    // Option names follow `#[nixos(rename_all)]`, falling back to serde's
+    let rename_all = struct_attrs.rename_all.or(serde_container_attrs.rename_all);
 
     let type_name = generate_type_name(name);
+    // Like for fields, `auto_doc` prefers doc comments over `description`
+    let doc_comment = extract_doc_comments(&input.attrs).map(|doc| convert_doc_text(&doc));
+    let description = if struct_attrs.auto_doc {
+        doc_comment.or(struct_attrs.description.clone())
+    } else {
+        struct_attrs.description.clone().or(doc_comment)
+    };
+    let description_tokens = match &description {
+        Some(desc) => quote! { Some(#desc) },
+        None => quote! { None },
+    };
+    let discriminants = serde_repr_discriminants(input, struct_attrs.repr)?;
+    let discriminants = discriminants.as_deref();
 
     let body = match &input.data {
         Data::Struct(data_struct) => generate_struct_impl(&data_struct.fields, name, &type_name)?,
         Data::Enum(data_enum) => {
             // For enums, generate a type.enum with all variants
-            let variants =
-                generate_enum_variant_names(data_enum, serde_container_attrs.rename_all)?;
-            let variants: Vec<String> =
-                variants.into_iter().map(|v| format!("\"{}\"", v)).collect();
+            let variants = generate_enum_variant_names(data_enum, rename_all, discriminants)?;
+            let variants: Vec<String> = variants
+                .iter()
+                .map(|v| enum_value_literal(v, discriminants.is_some()))
+                .collect();
 
             let variants_str = variants.join(" ");
             quote! {
@@ -62,23 +91,76 @@ enum MyEnum {\n\
         name,
         &type_name,
         struct_attrs.auto_doc,
-        serde_container_attrs.rename_all,
-    )?;
-    let nixos_options = generate_nixos_options(
-        &input.data,
-        name,
-        false,
-        struct_attrs.auto_doc,
-        serde_container_attrs.rename_all,
-    )?;
-    let nixos_options_named = generate_nixos_options(
-        &input.data,
-        name,
-        true,
-        struct_attrs.auto_doc,
-        serde_container_attrs.rename_all,
+        rename_all,
+        discriminants,
     )?;
+    let nixos_options =
+        generate_nixos_options(&input.data, name, false, struct_attrs.auto_doc, rename_all)?;
+    let nixos_options_named =
+        generate_nixos_options(&input.data, name, true, struct_attrs.auto_doc, rename_all)?;
     let nixos_type_name_literal = type_name.clone();
+    let type_dependencies = generate_type_dependencies(&input.data)?;
+    let to_settings_function =
+        generate_to_settings_function(input, &serde_container_attrs, rename_all)?;
+    let to_settings_name = to_settings_name(&name.to_string());
+    check_option_paths(&input.data, rename_all)?;
+    let field_names = generate_field_names(&input.data, rename_all)?;
+    let (secrets_body, load_secrets_body) = generate_secrets(&input.data, rename_all)?;
+    let writable_paths_body = generate_writable_paths(&input.data, rename_all)?;
+    let firewall_ports_body = generate_firewall_ports(&input.data, rename_all)?;
+
+    let default_variant_name = match &input.data {
+        Data::Enum(data_enum) => find_default_variant(data_enum, rename_all, discriminants)?,
+        _ => None,
+    };
+    let has_default = default_variant_name.is_some();
+    let takes_name = takes_name(&input.data)?;
+    let (default_variant, default_value) = match default_variant_name {
+        Some(variant) => {
+            let literal = enum_value_literal(&variant, discriminants.is_some());
+            (quote! { Some(#variant) }, quote! { Some(#literal) })
+        }
+        None => (quote! { None }, quote! { None }),
+    };
+
+    let value_description = match (&input.data, discriminants) {
+        (Data::Enum(data_enum), Some(discriminants)) => {
+            let description = repr_value_description(data_enum, discriminants);
+            quote! { Some(#description) }
+        }
+        _ => quote! { None },
+    };
+
+    let (enum_variants, key_check) = generate_key_support(&input.data, rename_all, discriminants)?;
+
+    let adapter_hints = generate_adapter_hints(&input.data)?;
+    let lint_checks = generate_lint_checks(input, struct_attrs.deny, struct_attrs.auto_doc)?;
+
+    let assertions_body = generate_assertions_body(input, &struct_attrs.assertions, rename_all)?;
+    let assertion_checks = generate_assertion_checks(&struct_attrs.assertions);
+
+    let validate_body = generate_validate_body(&input.data, rename_all)?;
+    let has_rust_assertions = struct_attrs.assertions.iter().any(|a| a.rust.is_some());
+    let validate_method = if struct_attrs.validate || has_rust_assertions {
+        quote! {
+            /// Check the value against the constraints of the generated
+            /// NixOS option type and the `rust` side of its assertions,
+            /// reporting each violation by option path.
+            pub fn nixos_validate(
+                &self,
+            ) -> ::std::result::Result<(), Vec<::serde_nixos::NixosValidationError>> {
+                let mut errors = Vec::new();
+                self.nixos_validate_into("", &mut errors);
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
 
     // Generate the full definition with all dependent types
     let nixos_full_def = generate_nixos_full_definition(
@@ -86,10 +168,14 @@ enum MyEnum {\n\
         name,
         &type_name,
         struct_attrs.auto_doc,
-        serde_container_attrs.rename_all,
+        rename_all,
+        discriminants,
     )?;
 
     Ok(quote! {
+        #adapter_hints
+        #lint_checks
+
         impl #impl_generics #name #ty_generics #where_clause {
             /// Generate a complete NixOS module definition for this type
             pub fn nixos_type_definition() -> String {
@@ -98,6 +184,16 @@ pub fn nixos_type_definition() -> String {
 
             /// Generate just the options portion of the NixOS module
             pub fn nixos_options() -> String {
+                Self::nixos_options_at("")
+            }
+
+            /// Like [`nixos_options`], for options declared at `option_prefix`
+            /// (e.g. `"services.myapp.settings"`).
+            ///
+            /// Links to fields in doc comments become `{option}` references
+            /// to the full option path.
+            #[allow(unused_variables)]
+            pub fn nixos_options_at(option_prefix: &str) -> String {
                 #nixos_options
             }
 
@@ -110,6 +206,13 @@ pub fn nixos_options() -> String {
             /// Use this with [`NixosModuleGenerator`] to produce self-consistent
             /// `.nix` files where all types are defined in `let` bindings.
             pub fn nixos_options_named() -> String {
+                Self::nixos_options_named_at("")
+            }
+
+            /// Like [`nixos_options_named`], for options declared at
+            /// `option_prefix`.
+            #[allow(unused_variables)]
+            pub fn nixos_options_named_at(option_prefix: &str) -> String {
                 #nixos_options_named
             }
 
@@ -123,15 +226,284 @@ pub fn nixos_type_name() -> &'static str {
                 #nixos_type_name_literal
             }
 
+            /// Get the NixOS option name of the struct field `field`. Used to
+            /// resolve doc comment links to fields of other types.
+            #[doc(hidden)]
+            pub fn nixos_field_name(field: &str) -> Option<&'static str> {
+                #field_names
+            }
+
+            /// Get the serialized name of the `#[default]` enum variant, if any.
+            ///
+            /// Struct fields of this type use it as their NixOS `default`
+            /// unless they set `#[nixos(default = "...")]` themselves.
+            pub fn nixos_default_variant() -> Option<&'static str> {
+                #default_variant
+            }
+
+            /// Get the `#[default]` enum variant as a Nix literal, if any.
+            pub fn nixos_default_value() -> Option<&'static str> {
+                #default_value
+            }
+
+            /// Whether fields of this type get a NixOS default without
+            /// `#[nixos(default)]`, for `#[nixos(deny(missing_default))]`.
+            #[doc(hidden)]
+            pub const fn nixos_has_default() -> bool {
+                #has_default
+            }
+
+            /// Whether the options refer to the submodule's attribute `name`
+            /// (`#[nixos(default_from_name)]`), so the submodule is written
+            /// as a function of it.
+            #[doc(hidden)]
+            pub const fn nixos_takes_name() -> bool {
+                #takes_name
+            }
+
+            /// Get the description of this type, from its doc comment or
+            /// `#[nixos(description = "...")]`.
+            pub fn nixos_description() -> Option<&'static str> {
+                #description_tokens
+            }
+
+            /// Get a note explaining the serialized values of this type.
+            ///
+            /// `serde_repr` enums use it to map their numbers back to variant
+            /// names in the description of fields of this type.
+            pub fn nixos_value_description() -> Option<&'static str> {
+                #value_description
+            }
+
+            /// Get the serialized variant names if this is a unit-only enum.
+            ///
+            /// Maps keyed by such an enum become a submodule with one
+            /// option per variant.
+            pub fn nixos_enum_variants() -> Option<&'static [&'static str]> {
+                #enum_variants
+            }
+
+            /// Get a Nix predicate (`name: ...`) that attribute names must
+            /// satisfy when this type is used as a map key.
+            pub fn nixos_key_check() -> Option<String> {
+                #key_check
+            }
+
             /// Generate the full NixOS type definition with all dependencies
             /// Creates a `let` chain with all submodules defined first
             pub fn nixos_type_full_definition() -> String {
                 #nixos_full_def
             }
+
+            #validate_method
+
+            /// Append the constraint violations of this value to `errors`,
+            /// with option paths below `path`. Used by `nixos_validate()`.
+            #[doc(hidden)]
+            #[allow(unused_variables, clippy::ptr_arg)]
+            pub fn nixos_validate_into(
+                &self,
+                path: &str,
+                errors: &mut Vec<::serde_nixos::NixosValidationError>,
+            ) {
+                #validate_body
+                #assertion_checks
+            }
+
+            /// Get a Nix function converting the value of this type's options
+            /// into what the type deserializes from, for generating its
+            /// config file as `builtins.toJSON (toSettings cfg)`.
+            ///
+            /// The result is a `let` expression binding the functions of the
+            /// nested types, and needs `lib` in scope.
+            pub fn nixos_to_settings() -> String {
+                let mut types = Vec::new();
+                <Self as ::serde_nixos::NixosTypeGenerator>::nixos_register_types(&mut types);
+                ::serde_nixos::utils::format_to_settings(&types)
+            }
+
+            /// The settings function of this type alone, referring to the
+            /// functions of nested types by name. Used by `nixos_to_settings()`.
+            #[doc(hidden)]
+            pub fn nixos_to_settings_function() -> String {
+                #to_settings_function
+            }
+
+            /// The name the settings function of this type is bound to, e.g.
+            /// `databaseConfigToSettings`.
+            #[doc(hidden)]
+            pub fn nixos_to_settings_name() -> &'static str {
+                #to_settings_name
+            }
+
+            /// Get the credential names of the `#[nixos(secret)]` options of
+            /// this type and its nested struct fields, e.g.
+            /// `"database.password"` for the option `database.passwordFile`.
+            pub fn nixos_secrets() -> Vec<String> {
+                Self::nixos_secrets_at("")
+            }
+
+            /// Get the credential names of the secrets below `prefix`. Used
+            /// by `nixos_secrets()`.
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            pub fn nixos_secrets_at(prefix: &str) -> Vec<String> {
+                #secrets_body
+            }
+
+            /// Read the `#[nixos(secret)]` fields from the systemd credentials
+            /// passed by the generated service module. Does nothing outside
+            /// a service with credentials; secrets whose file option is unset
+            /// keep their value.
+            pub fn nixos_load_secrets(&mut self) -> ::std::io::Result<()> {
+                match ::serde_nixos::secrets::credentials_directory() {
+                    Some(dir) => self.nixos_load_secrets_from(&dir, ""),
+                    None => Ok(()),
+                }
+            }
+
+            /// Read the secrets below `prefix` from the credentials in `dir`.
+            /// Used by `nixos_load_secrets()`.
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            pub fn nixos_load_secrets_from(
+                &mut self,
+                dir: &::std::path::Path,
+                prefix: &str,
+            ) -> ::std::io::Result<()> {
+                #load_secrets_body
+            }
+
+            /// Get the option paths of the `#[nixos(writable)]` fields of this
+            /// type and its nested struct fields, e.g. `"storage.dataDir"`.
+            pub fn nixos_writable_paths() -> Vec<String> {
+                Self::nixos_writable_paths_at("")
+            }
+
+            /// Get the option paths of the writable fields below `prefix`.
+            /// Used by `nixos_writable_paths()`.
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            pub fn nixos_writable_paths_at(prefix: &str) -> Vec<String> {
+                #writable_paths_body
+            }
+
+            /// Get the ports of the `#[nixos(port, open_firewall)]` fields of
+            /// this type and its nested struct fields as Nix list expressions
+            /// by protocol, for the option value `value` (e.g. `"cfg"`).
+            pub fn nixos_firewall_ports(
+                value: &str,
+            ) -> Vec<(::serde_nixos::service::PortProtocol, String)> {
+                Self::nixos_firewall_ports_at(value, &mut Vec::new())
+            }
+
+            /// Get the ports of the value `value`, leaving out those of the
+            /// types in `visiting` so recursive types terminate. Used by
+            /// `nixos_firewall_ports()`.
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            pub fn nixos_firewall_ports_at(
+                value: &str,
+                visiting: &mut Vec<&'static str>,
+            ) -> Vec<(::serde_nixos::service::PortProtocol, String)> {
+                if visiting.contains(&Self::nixos_type_name()) {
+                    return Vec::new();
+                }
+                visiting.push(Self::nixos_type_name());
+                let ports = { #firewall_ports_body };
+                visiting.pop();
+                ports
+            }
+
+            /// Get the `#[nixos(assert(...))]` checks of this type and its
+            /// nested struct fields as a NixOS `assertions` list, for the
+            /// option at `option_path` (e.g. `"services.myapp.settings"`).
+            pub fn nixos_assertions(option_path: &str) -> String {
+                ::serde_nixos::utils::format_assertions(&Self::nixos_assertions_at(
+                    &format!("config.{}", option_path),
+                    option_path,
+                ))
+            }
+
+            /// Get `(assertion, message)` pairs for the value `value` at
+            /// `path`. Used by `nixos_assertions()`.
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            pub fn nixos_assertions_at(value: &str, path: &str) -> Vec<(String, String)> {
+                #assertions_body
+            }
+        }
+
+        impl #impl_generics ::serde_nixos::NixosTypeGenerator for #name #ty_generics #where_clause {
+            fn nixos_type_definition() -> String {
+                Self::nixos_type_definition()
+            }
+
+            fn nixos_options() -> String {
+                Self::nixos_options()
+            }
+
+            fn nixos_type() -> String {
+                Self::nixos_type()
+            }
+
+            fn nixos_options_named_at(option_prefix: &str) -> String {
+                Self::nixos_options_named_at(option_prefix)
+            }
+
+            fn nixos_assertions(option_path: &str) -> String {
+                Self::nixos_assertions(option_path)
+            }
+
+            fn nixos_secrets() -> Vec<String> {
+                Self::nixos_secrets()
+            }
+
+            fn nixos_writable_paths() -> Vec<String> {
+                Self::nixos_writable_paths()
+            }
+
+            fn nixos_firewall_ports(
+                value: &str,
+            ) -> Vec<(::serde_nixos::service::PortProtocol, String)> {
+                Self::nixos_firewall_ports(value)
+            }
+
+            fn nixos_register_types(types: &mut Vec<::serde_nixos::TypeRegistration>) {
+                if types.iter().any(|t| t.type_name == Self::nixos_type_name()) {
+                    return;
+                }
+                // Registered before its dependencies so recursive types
+                // terminate, then moved after them
+                let index = types.len();
+                types.push(::serde_nixos::type_registration!(Self));
+                #(<#type_dependencies as ::serde_nixos::NixosTypeGenerator>::nixos_register_types(types);)*
+                let this = types.remove(index);
+                types.push(this);
+            }
         }
     })
 }
 
+/// The custom types that the options of a struct refer to by name.
+fn generate_type_dependencies(data: &Data) -> Result<Vec<syn::Path>> {
+    let mut custom_types = BTreeSet::new();
+    if let Data::Struct(data_struct) = data {
+        if let Fields::Named(fields) = &data_struct.fields {
+            collect_custom_types(fields, &mut custom_types)?;
+        }
+    }
+    Ok(custom_types
+        .iter()
+        .map(|path| custom_type_path(path))
+        .collect())
+}
+
+/// Parse a path collected by `collect_custom_types`.
+fn custom_type_path(path: &str) -> syn::Path {
+    syn::parse_str(path).expect("collected type paths are valid")
+}
+
 /// Generate a camelCase type name from a struct name
 fn generate_type_name(ident: &Ident) -> String {
     to_nixos_type_name(&ident.to_string())
@@ -161,6 +533,7 @@ fn generate_nixos_type_definition(
     type_name: &str,
     auto_doc: bool,
     rename_all: Option<RenameRule>,
+    discriminants: Option<&[i128]>,
 ) -> Result<TokenStream> {
     let struct_name_str = name.to_string();
 
@@ -168,17 +541,26 @@ fn generate_nixos_type_definition(
         Data::Struct(data_struct) => match &data_struct.fields {
             Fields::Named(fields) => {
                 let options_body =
-                    generate_options_for_fields(fields, false, auto_doc, rename_all)?;
+                    generate_options_for_fields(fields, name, false, auto_doc, rename_all)?;
                 Ok(quote! {
                     {
+                        #[allow(unused_variables)]
+                        let option_prefix = "";
                         let mut result = String::new();
                         result.push_str("# NixOS type definition for ");
                         result.push_str(#struct_name_str);
                         result.push_str("\n");
+                        if let Some(desc) = Self::nixos_description() {
+                            result.push_str(&::serde_nixos::utils::nix_comment(desc, ""));
+                        }
                         result.push_str(#type_name);
-                        result.push_str(" = types.submodule {\n  options = {\n");
+                        result.push_str(" = ");
+                        result.push_str(::serde_nixos::utils::submodule_open(Self::nixos_takes_name()));
+                        result.push_str("\n  options = {\n");
                         #options_body
-                        result.push_str("  };\n};\n");
+                        result.push_str("  };\n");
+                        result.push_str(::serde_nixos::utils::submodule_close(Self::nixos_takes_name()));
+                        result.push_str(";\n");
                         result
                     }
                 })
@@ -188,15 +570,27 @@ fn generate_nixos_type_definition(
             }),
         },
         Data::Enum(data_enum) => {
-            let variants = generate_enum_variant_names(data_enum, rename_all)?;
-            let variants: Vec<String> =
-                variants.into_iter().map(|v| format!("\"{}\"", v)).collect();
+            let variants = generate_enum_variant_names(data_enum, rename_all, discriminants)?;
+            let variants: Vec<String> = variants
+                .iter()
+                .map(|v| enum_value_literal(v, discriminants.is_some()))
+                .collect();
             let variants_str = variants.join(" ");
+            let values_comment = match discriminants {
+                Some(discriminants) => {
+                    format!("# {}\n", repr_value_description(data_enum, discriminants))
+                }
+                None => String::new(),
+            };
 
             Ok(quote! {
                 format!(
-                    "# NixOS type definition for {}\n{} = types.enum [ {} ];",
+                    "# NixOS type definition for {}\n{}{}{} = types.enum [ {} ];",
                     #struct_name_str,
+                    Self::nixos_description()
+                        .map(|desc| ::serde_nixos::utils::nix_comment(desc, ""))
+                        .unwrap_or_default(),
+                    #values_comment,
                     #type_name,
                     #variants_str
                 )
@@ -211,7 +605,7 @@ fn generate_nixos_type_definition(
 
 fn generate_nixos_options(
     data: &Data,
-    _name: &Ident,
+    name: &Ident,
     use_named_types: bool,
     auto_doc: bool,
     rename_all: Option<RenameRule>,
@@ -219,8 +613,13 @@ fn generate_nixos_options(
     match data {
         Data::Struct(data_struct) => match &data_struct.fields {
             Fields::Named(fields) => {
-                let options_body =
-                    generate_options_for_fields(fields, use_named_types, auto_doc, rename_all)?;
+                let options_body = generate_options_for_fields(
+                    fields,
+                    name,
+                    use_named_types,
+                    auto_doc,
+                    rename_all,
+                )?;
                 Ok(quote! {
                     {
                         let mut result = String::new();
@@ -274,6 +673,7 @@ fn generate_nixos_full_definition(
     type_name: &str,
     auto_doc: bool,
     rename_all: Option<RenameRule>,
+    discriminants: Option<&[i128]>,
 ) -> Result<TokenStream> {
     match data {
         Data::Struct(data_struct) => {
@@ -281,10 +681,10 @@ fn generate_nixos_full_definition(
                 Fields::Named(fields) => {
                     // Collect all custom types used in fields (recursively)
                     let mut custom_types = BTreeSet::new();
-                    collect_custom_types(fields, &mut custom_types);
+                    collect_custom_types(fields, &mut custom_types)?;
 
                     let options_body =
-                        generate_options_for_fields(fields, true, auto_doc, rename_all)?;
+                        generate_options_for_fields(fields, name, true, auto_doc, rename_all)?;
 
                     // Generate let bindings for nested custom types
                     let nested_type_bindings = if custom_types.is_empty() {
@@ -292,23 +692,38 @@ fn generate_nixos_full_definition(
                     } else {
                         let mut bindings = Vec::new();
                         for custom_type in custom_types {
-                            let type_ident =
-                                syn::Ident::new(&custom_type, proc_macro2::Span::call_site());
-                            let generated_name = generate_type_name(&type_ident);
+                            let type_ident = custom_type_path(&custom_type);
+                            let generated_name =
+                                generate_type_name(&type_ident.segments.last().unwrap().ident);
                             bindings.push(quote! {
+                                if let Some(desc) = #type_ident::nixos_description() {
+                                    result.push_str(&::serde_nixos::utils::nix_comment(desc, "  "));
+                                }
                                 result.push_str("  ");
                                 result.push_str(#generated_name);
-                                result.push_str(" = types.submodule {\n    options = {\n");
-                                // Get options and indent each line by 4 spaces (matching main type indentation)
-                                let options = #type_ident::nixos_options();
-                                for line in options.lines() {
-                                    if !line.is_empty() {
-                                        result.push_str("    ");
-                                        result.push_str(line);
+                                result.push_str(" = ");
+                                let type_expr = #type_ident::nixos_type();
+                                if type_expr.trim() != #type_ident::nixos_type_name() {
+                                    // Enums are bound to their type expression,
+                                    // like `NixosModuleGenerator` does
+                                    result.push_str(type_expr.trim());
+                                    result.push_str(";\n");
+                                } else {
+                                    result.push_str(::serde_nixos::utils::submodule_open(#type_ident::nixos_takes_name()));
+                                    result.push_str("\n    options = {\n");
+                                    // Get options and indent each line by 4 spaces (matching main type indentation)
+                                    let options = #type_ident::nixos_options();
+                                    for line in options.lines() {
+                                        if !line.is_empty() {
+                                            result.push_str("    ");
+                                            result.push_str(line);
+                                        }
+                                        result.push_str("\n");
                                     }
-                                    result.push_str("\n");
+                                    result.push_str("    };\n  ");
+                                    result.push_str(::serde_nixos::utils::submodule_close(#type_ident::nixos_takes_name()));
+                                    result.push_str(";\n");
                                 }
-                                result.push_str("    };\n  };\n");
                             });
                         }
                         quote! { #(#bindings)* }
@@ -316,6 +731,8 @@ fn generate_nixos_full_definition(
 
                     Ok(quote! {
                         {
+                            #[allow(unused_variables)]
+                            let option_prefix = "";
                             let mut result = String::new();
 
                             result.push_str("let\n");
@@ -324,11 +741,18 @@ fn generate_nixos_full_definition(
                             #nested_type_bindings
 
                             // Generate the main type definition
+                            if let Some(desc) = Self::nixos_description() {
+                                result.push_str(&::serde_nixos::utils::nix_comment(desc, "  "));
+                            }
                             result.push_str("  ");
                             result.push_str(#type_name);
-                            result.push_str(" = types.submodule {\n    options = {\n");
+                            result.push_str(" = ");
+                            result.push_str(::serde_nixos::utils::submodule_open(Self::nixos_takes_name()));
+                            result.push_str("\n    options = {\n");
                             #options_body
-                            result.push_str("    };\n  };\n");
+                            result.push_str("    };\n  ");
+                            result.push_str(::serde_nixos::utils::submodule_close(Self::nixos_takes_name()));
+                            result.push_str(";\n");
                             result.push_str("in ");
                             result.push_str(#type_name);
                             result.push_str("\n");
@@ -342,9 +766,14 @@ fn generate_nixos_full_definition(
                 }),
             }
         }
-        Data::Enum(_) => {
-            generate_nixos_type_definition(data, name, type_name, auto_doc, rename_all)
-        }
+        Data::Enum(_) => generate_nixos_type_definition(
+            data,
+            name,
+            type_name,
+            auto_doc,
+            rename_all,
+            discriminants,
+        ),
         Data::Union(_) => Err(syn::Error::new_spanned(
             name,
             "Union types are not supported. Use enums instead.",
@@ -352,23 +781,195 @@ fn generate_nixos_full_definition(
     }
 }
 
+/// The NixOS option name of a field, from its rename or the container's
+/// `rename_all`. Secrets are set by the `<name>File` option.
+fn nix_field_name(
+    field_name: &Ident,
+    attrs: &EffectiveAttributes,
+    rename_all: Option<RenameRule>,
+) -> String {
+    let name = attrs.name.clone().unwrap_or_else(|| match rename_all {
+        Some(rule) => apply_rename_rule_to_field(&field_name.to_string(), rule),
+        None => field_name.to_string(),
+    });
+    if attrs.secret {
+        format!("{}File", name)
+    } else {
+        name
+    }
+}
+
+/// The fields that doc comment links can refer to.
+fn link_targets(fields: &FieldsNamed, rename_all: Option<RenameRule>) -> Result<Vec<LinkTarget>> {
+    let mut targets = Vec::new();
+    for field in &fields.named {
+        let field_name = field.ident.as_ref().unwrap();
+        let attrs = combine_attributes(
+            parse_nixos_attributes(&field.attrs)?,
+            parse_serde_attributes(&field.attrs)?,
+            None,
+            false,
+        );
+        if attrs.skip {
+            continue;
+        }
+        targets.push(LinkTarget {
+            rust_name: field_name.to_string(),
+            nix_name: nix_field_name(field_name, &attrs, rename_all),
+            flatten: attrs.flatten,
+            ty: field.ty.clone(),
+        });
+    }
+    Ok(targets)
+}
+
+/// Generate the body of `nixos_field_name()`.
+fn generate_field_names(data: &Data, rename_all: Option<RenameRule>) -> Result<TokenStream> {
+    let fields = match data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => fields,
+            _ => return Ok(quote! { None }),
+        },
+        _ => return Ok(quote! { None }),
+    };
+    let arms = link_targets(fields, rename_all)?
+        .into_iter()
+        .filter(|target| !target.flatten)
+        .map(|target| {
+            let rust_name = target.rust_name;
+            let nix_name = target.nix_name;
+            quote! { #rust_name => Some(#nix_name), }
+        });
+    Ok(quote! {
+        match field {
+            #(#arms)*
+            _ => None,
+        }
+    })
+}
+
+/// Whether any option defaults to the submodule's attribute name.
+fn takes_name(data: &Data) -> Result<bool> {
+    let Data::Struct(data_struct) = data else {
+        return Ok(false);
+    };
+    for field in &data_struct.fields {
+        let nixos_attrs = parse_nixos_attributes(&field.attrs)?;
+        let serde_attrs = parse_serde_attributes(&field.attrs)?;
+        if nixos_attrs.default_from_name && !nixos_attrs.skip && !serde_attrs.skip {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Reject `#[nixos(path)]` options that clash with other options, e.g.
+/// `tls.cert` next to a `tls` option.
+fn check_option_paths(data: &Data, rename_all: Option<RenameRule>) -> Result<()> {
+    let fields = match data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => fields,
+            _ => return Ok(()),
+        },
+        _ => return Ok(()),
+    };
+    let mut options: Vec<(String, bool)> = Vec::new();
+    for field in &fields.named {
+        let field_name = field.ident.as_ref().unwrap();
+        let attrs = combine_attributes(
+            parse_nixos_attributes(&field.attrs)?,
+            parse_serde_attributes(&field.attrs)?,
+            None,
+            false,
+        );
+        if attrs.skip {
+            continue;
+        }
+        if attrs.path && attrs.flatten {
+            return Err(syn::Error::new_spanned(
+                field_name,
+                "flattened fields have no option of their own to place with `path`",
+            ));
+        }
+        if attrs.flatten {
+            continue;
+        }
+        let name = nix_field_name(field_name, &attrs, rename_all);
+        for (other, other_path) in &options {
+            if !attrs.path && !other_path {
+                continue;
+            }
+            let (outer, inner) = if name.len() <= other.len() {
+                (&name, other)
+            } else {
+                (other, &name)
+            };
+            if inner == outer || inner.starts_with(&format!("{}.", outer)) {
+                return Err(syn::Error::new_spanned(
+                    field_name,
+                    format!("option `{}` conflicts with option `{}`", name, other),
+                ));
+            }
+        }
+        options.push((name, attrs.path));
+    }
+    Ok(())
+}
+
 /// Collect custom type names from fields (recursively handles nested types)
-fn collect_custom_types(fields: &FieldsNamed, types: &mut BTreeSet<String>) {
+///
+/// Fields whose NixOS type comes from `#[nixos(type)]` or a serde adapter do
+/// not reference their Rust type, so they contribute nothing.
+fn collect_custom_types(fields: &FieldsNamed, types: &mut BTreeSet<String>) -> Result<()> {
     for field in &fields.named {
+        let nixos_attrs = parse_nixos_attributes(&field.attrs)?;
+        let serde_attrs = parse_serde_attributes(&field.attrs)?;
+        let effective_attrs = combine_attributes(nixos_attrs, serde_attrs, None, false);
+
+        let adapted = matches!(
+            wire_type(
+                &effective_attrs,
+                unwrap_option_type(&field.ty),
+                &|_| quote! {}
+            ),
+            WireType::Adapted(_)
+        );
+        if effective_attrs.type_override.is_some()
+            || (adapted && !uses_field_type(&effective_attrs))
+        {
+            continue;
+        }
         collect_custom_types_from_type(&field.ty, types);
     }
+    Ok(())
 }
 
 /// Recursively collect custom type names from a type, including nested types
 fn collect_custom_types_from_type(ty: &Type, types: &mut BTreeSet<String>) {
+    match ty {
+        Type::Reference(reference) => {
+            return collect_custom_types_from_type(&reference.elem, types)
+        }
+        Type::Paren(paren) => return collect_custom_types_from_type(&paren.elem, types),
+        Type::Array(array) => return collect_custom_types_from_type(&array.elem, types),
+        Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                collect_custom_types_from_type(elem, types);
+            }
+            return;
+        }
+        _ => {}
+    }
+
     if let Type::Path(type_path) = ty {
         let segment = type_path.path.segments.last();
         if let Some(seg) = segment {
             let type_name = seg.ident.to_string();
 
             // Check if this is a wrapper type (Option, Vec, HashMap, etc.)
-            match type_name.as_str() {
-                "Option" | "Vec" | "Box" | "Rc" | "Arc" | "HashSet" | "BTreeSet" => {
+            match collection_alias(&type_name) {
+                "Option" | "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "Box" | "Rc"
+                | "Arc" | "Cow" | "HashSet" | "BTreeSet" => {
                     // Extract inner type from generic arguments
                     if let syn::PathArguments::AngleBracketed(args) = &seg.arguments {
                         for arg in &args.args {
@@ -389,9 +990,18 @@ fn collect_custom_types_from_type(ty: &Type, types: &mut BTreeSet<String>) {
                     }
                 }
                 _ => {
-                    // Check if this is a custom (non-built-in) type
-                    if let Some(custom_name) = get_custom_type_name(ty) {
-                        types.insert(custom_name);
+                    // Check if this is a custom (non-built-in) type, keeping
+                    // its path without generic arguments
+                    if get_custom_type_name(ty).is_some() {
+                        let path = &type_path.path;
+                        let segments: Vec<String> =
+                            path.segments.iter().map(|s| s.ident.to_string()).collect();
+                        let prefix = if path.leading_colon.is_some() {
+                            "::"
+                        } else {
+                            ""
+                        };
+                        types.insert(format!("{}{}", prefix, segments.join("::")));
                     }
                 }
             }
@@ -401,11 +1011,17 @@ fn collect_custom_types_from_type(ty: &Type, types: &mut BTreeSet<String>) {
 
 fn generate_options_for_fields(
     fields: &FieldsNamed,
+    struct_name: &Ident,
     use_named_types: bool,
     auto_doc: bool,
     rename_all: Option<RenameRule>,
 ) -> Result<TokenStream> {
     let mut field_options = Vec::new();
+    let link_targets = link_targets(fields, rename_all)?;
+    let link_context = LinkContext {
+        struct_name: struct_name.to_string(),
+        fields: &link_targets,
+    };
 
     for field in &fields.named {
         let field_name = field.ident.as_ref().unwrap();
@@ -415,29 +1031,86 @@ fn generate_options_for_fields(
         let nixos_attrs = parse_nixos_attributes(&field.attrs)?;
         let serde_attrs = parse_serde_attributes(&field.attrs)?;
         let doc_comment = extract_doc_comments(&field.attrs);
-        let effective_attrs = combine_attributes(nixos_attrs, serde_attrs, doc_comment, auto_doc);
+        let mut effective_attrs =
+            combine_attributes(nixos_attrs, serde_attrs, doc_comment.clone(), auto_doc);
+        effective_attrs.fit_imported_rules(unwrap_option_type(field_type));
 
         // Skip if marked to skip
         if effective_attrs.skip {
             continue;
         }
 
-        // Determine field name (considering rename)
-        let default_field_name = if let Some(rule) = rename_all {
-            apply_rename_rule_to_field(&field_name.to_string(), rule)
+        let nix_field_name = nix_field_name(field_name, &effective_attrs, rename_all);
+        let port_type = port_type(field, &effective_attrs)?;
+
+        if effective_attrs.secret {
+            field_options.push(secret_option(
+                field,
+                &effective_attrs,
+                &nix_field_name,
+                doc_comment.as_ref(),
+                &link_context,
+                use_named_types,
+            )?);
+            continue;
+        }
+
+        // Fields with a Rust-side default (`#[serde(default)]` or
+        // `#[nixos(optional)]`) but no Nix default become nullable, so an
+        // unset option is left out of the config and serde fills it in.
+        let falls_back_to_rust_default = effective_attrs.optional
+            && !effective_attrs.has_nixos_default()
+            && !is_optional_type(field_type);
+
+        // A known serde adapter decides the wire format; `#[nixos(type)]`
+        // overrides everything.
+        let map_type = |ty: &Type| {
+            if use_named_types {
+                rust_type_to_nixos_named(ty)
+            } else {
+                rust_type_to_nixos(ty)
+            }
+        };
+        let wire = wire_type(&effective_attrs, unwrap_option_type(field_type), &map_type);
+
+        // `#[nixos(min, max, ...)]` refine the mapping of the Rust type.
+        // Rules imported from `validator` / `garde` are only described when
+        // the NixOS type doesn't follow from the Rust type.
+        let mut unchecked_rules = effective_attrs.imported.untranslated.clone();
+        let constrained = if effective_attrs.constraints.is_empty() {
+            None
+        } else if effective_attrs.type_override.is_some()
+            || matches!(wire, WireType::Adapted(_))
+            || port_type.is_some()
+        {
+            if effective_attrs.has_nixos_constraints {
+                return Err(syn::Error::new_spanned(
+                    field_name,
+                    "value constraints cannot be combined with `#[nixos(type)]`, `port` or a \
+                     serde adapter",
+                ));
+            }
+            unchecked_rules = effective_attrs.imported.all_rules();
+            None
         } else {
-            field_name.to_string()
+            Some(constrained_type(
+                unwrap_option_type(field_type),
+                &effective_attrs.constraints,
+                &map_type,
+            )?)
+        };
+        let map_field_type = |ty: &Type| match (&wire, &constrained) {
+            _ if port_type.is_some() => quote! { #port_type },
+            (WireType::Adapted(nixos_type), _) => nixos_type.clone(),
+            (_, Some((nixos_type, _))) => nixos_type.clone(),
+            _ => map_type(ty),
         };
-        let nix_field_name = effective_attrs.name.unwrap_or(default_field_name);
 
         // Generate the type expression
-        let type_expr = if is_optional_type(field_type) {
-            let inner_type = unwrap_option_type(field_type);
-            let inner_nixos = if use_named_types {
-                rust_type_to_nixos_named(inner_type)
-            } else {
-                rust_type_to_nixos(inner_type)
-            };
+        let type_expr = if let Some(type_override) = &effective_attrs.type_override {
+            quote! { #type_override }
+        } else if is_optional_type(field_type) || falls_back_to_rust_default {
+            let inner_nixos = map_field_type(unwrap_option_type(field_type));
             quote! {
                 {
                     let inner = #inner_nixos;
@@ -448,10 +1121,8 @@ fn generate_options_for_fields(
                     }
                 }
             }
-        } else if use_named_types {
-            rust_type_to_nixos_named(field_type)
         } else {
-            rust_type_to_nixos(field_type)
+            map_field_type(field_type)
         };
 
         // Build the option definition with proper indentation
@@ -472,20 +1143,115 @@ fn generate_options_for_fields(
             result.push_str(";\n");
         });
 
-        // Add description if present
-        if let Some(desc) = &effective_attrs.description {
-            let escaped_desc = desc.replace('"', "\\\"").replace('\n', "\\n");
-            field_options.push(quote! {
-                result.push_str(#indent);
-                result.push_str("description = \"");
-                result.push_str(#escaped_desc);
-                result.push_str("\";\n");
-            });
+        // Add description if present, followed by notes on constraints and
+        // the Rust-side default
+        let mut notes = Vec::new();
+        if let Some((_, constraints_note)) = &constrained {
+            notes.push(constraints_note.clone());
+        }
+        if !unchecked_rules.is_empty() {
+            let rules: Vec<String> = unchecked_rules
+                .iter()
+                .map(|rule| format!("`{}`", rule))
+                .collect();
+            notes.push(format!(
+                "Further validated by the program: {}.",
+                rules.join(", ")
+            ));
         }
+        if falls_back_to_rust_default {
+            notes.push("If unset, the program's built-in default is used.".to_string());
+        }
+        // Doc comments are converted to NixOS manual markdown, with links
+        // to fields becoming `{option}` references below `option_prefix`
+        let own_description = match &effective_attrs.description {
+            Some(desc) if effective_attrs.description == doc_comment => {
+                let doc = doc_tokens(&convert_doc(desc, Some(&link_context)));
+                quote! { Some(#doc) }
+            }
+            Some(desc) => quote! { Some(#desc.to_string()) },
+            None => quote! { None::<String> },
+        };
+        let value_type = unwrap_option_type(field_type);
+        let custom_type = effective_attrs.type_override.is_none()
+            && matches!(wire, WireType::Native)
+            && get_custom_type_name(value_type).is_some();
+        // Without a description of its own, the field is described by its
+        // type's docs. Custom types may also explain their values, e.g.
+        // `serde_repr` enums.
+        let (type_description, value_description) = if custom_type {
+            (
+                quote! { .or_else(|| <#value_type>::nixos_description().map(String::from)) },
+                quote! { parts.extend(<#value_type>::nixos_value_description().map(String::from)); },
+            )
+        } else {
+            (quote! {}, quote! {})
+        };
+        field_options.push(quote! {
+            #[allow(unused_mut)]
+            let mut parts: Vec<String> = #own_description #type_description
+                .into_iter()
+                .collect();
+            #(parts.push(#notes.to_string());)*
+            #value_description
+            if !parts.is_empty() {
+                result.push_str(#indent);
+                result.push_str("description = ");
+                result.push_str(&::serde_nixos::utils::format_nix_string(
+                    &parts.join("\n\n"),
+                    #indent,
+                ));
+                result.push_str(";\n");
+            }
+        });
 
         // Add default if present (explicit #[nixos(default = "...")] takes priority).
-        // For Option<T> fields without an explicit default, emit `default = null;`
-        // since NixOS treats fields without defaults as mandatory.
+        // For Option<T> and Rust-defaulted fields without an explicit default,
+        // emit `default = null;` since NixOS treats fields without defaults as
+        // mandatory.
+        let serde_adapted = effective_attrs.with.is_some()
+            || effective_attrs.serde_as.is_some()
+            || !matches!(wire, WireType::Native);
+        for expr in [
+            &effective_attrs.default_value,
+            &effective_attrs.example_value,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if serde_adapted {
+                return Err(syn::Error::new_spanned(
+                    expr,
+                    "`default_value` / `example_value` are serialized with the field type's \
+                     `Serialize` impl and cannot be combined with a serde adapter; \
+                     use `default` / `example` instead",
+                ));
+            }
+        }
+
+        // Literal defaults and examples must fit the field's Rust type, or
+        // deserializing the generated config fails.
+        if !serde_adapted && effective_attrs.type_override.is_none() {
+            let nullable = falls_back_to_rust_default;
+            let snippets = [
+                (
+                    &effective_attrs.default,
+                    effective_attrs.default_span,
+                    "default",
+                ),
+                (
+                    &effective_attrs.example,
+                    effective_attrs.example_span,
+                    "example",
+                ),
+            ];
+            for (snippet, span, attribute) in snippets {
+                if let (Some(snippet), Some(span)) = (snippet, span) {
+                    check_literal_type(field_type, nullable, snippet, attribute, span)?;
+                }
+            }
+        }
+
         if let Some(default) = &effective_attrs.default {
             field_options.push(quote! {
                 result.push_str(#indent);
@@ -493,11 +1259,35 @@ fn generate_options_for_fields(
                 result.push_str(#default);
                 result.push_str(";\n");
             });
-        } else if is_optional_type(field_type) {
+        } else if let Some(default_value) = &effective_attrs.default_value {
+            // Type-checked against the field, serialized when generating
+            field_options.push(quote! {
+                {
+                    let value: #field_type = #default_value;
+                    result.push_str(#indent);
+                    result.push_str("default = ");
+                    result.push_str(&::serde_nixos::utils::to_nix_value(&value));
+                    result.push_str(";\n");
+                }
+            });
+        } else if is_optional_type(field_type) || falls_back_to_rust_default {
             field_options.push(quote! {
                 result.push_str(#indent);
                 result.push_str("default = null;\n");
             });
+        } else if effective_attrs.type_override.is_none()
+            && !matches!(wire, WireType::Adapted(_))
+            && get_custom_type_name(field_type).is_some()
+        {
+            // Enum fields fall back to the enum's `#[default]` variant.
+            field_options.push(quote! {
+                if let Some(default) = <#field_type>::nixos_default_value() {
+                    result.push_str(#indent);
+                    result.push_str("default = ");
+                    result.push_str(default);
+                    result.push_str(";\n");
+                }
+            });
         }
 
         // Add defaultText if present
@@ -518,6 +1308,16 @@ fn generate_options_for_fields(
                 result.push_str(#example);
                 result.push_str(";\n");
             });
+        } else if let Some(example_value) = &effective_attrs.example_value {
+            field_options.push(quote! {
+                {
+                    let value: #field_type = #example_value;
+                    result.push_str(#indent);
+                    result.push_str("example = ");
+                    result.push_str(&::serde_nixos::utils::to_nix_example(&value));
+                    result.push_str(";\n");
+                }
+            });
         }
 
         // Add apply if present
@@ -577,10 +1377,66 @@ fn generate_options_for_fields(
     })
 }
 
+/// Warn about fields whose serde adapter hides the wire format.
+///
+/// Stable proc macros cannot emit warnings directly, so each hint is the use
+/// of a deprecated item spanned to the field.
+fn generate_adapter_hints(data: &Data) -> Result<TokenStream> {
+    let Data::Struct(data_struct) = data else {
+        return Ok(quote! {});
+    };
+    let Fields::Named(fields) = &data_struct.fields else {
+        return Ok(quote! {});
+    };
+
+    let mut hints = Vec::new();
+    for field in &fields.named {
+        let nixos_attrs = parse_nixos_attributes(&field.attrs)?;
+        let serde_attrs = parse_serde_attributes(&field.attrs)?;
+        let effective_attrs = combine_attributes(nixos_attrs, serde_attrs, None, false);
+        if effective_attrs.skip || effective_attrs.type_override.is_some() {
+            continue;
+        }
+
+        let wire = wire_type(
+            &effective_attrs,
+            unwrap_option_type(&field.ty),
+            &|_| quote! {},
+        );
+        if let WireType::Unknown(adapter) = wire {
+            let field_name = field.ident.as_ref().unwrap();
+            let note = format!(
+                "serde-nixos cannot see the wire format of `{}` on field `{}` and uses its Rust type; \
+                 set the NixOS type with `#[nixos(type = \"...\")]`",
+                adapter, field_name
+            );
+            let hint = quote_spanned! {field_name.span()=> NixosTypeHint};
+            hints.push(quote! {
+                const _: () = {
+                    #[deprecated(note = #note)]
+                    struct NixosTypeHint;
+                    let _ = #hint;
+                };
+            });
+        }
+    }
+
+    Ok(quote! { #(#hints)* })
+}
+
+/// Get the serialized values of an enum's variants.
+///
+/// These are the (renamed) variant names, or the discriminants of a
+/// `serde_repr` enum.
 fn generate_enum_variant_names(
     data_enum: &syn::DataEnum,
     rename_all: Option<RenameRule>,
+    discriminants: Option<&[i128]>,
 ) -> Result<Vec<String>> {
+    if let Some(discriminants) = discriminants {
+        return Ok(discriminants.iter().map(|d| d.to_string()).collect());
+    }
+
     let mut variants = Vec::new();
 
     for variant in &data_enum.variants {
@@ -596,6 +1452,156 @@ fn generate_enum_variant_names(
     Ok(variants)
 }
 
+/// Generate the bodies of `nixos_enum_variants()` and `nixos_key_check()`.
+///
+/// Unit-only enums list their variants; newtype structs delegate the key
+/// check to their inner type. Everything else accepts any attribute name.
+fn generate_key_support(
+    data: &Data,
+    rename_all: Option<RenameRule>,
+    discriminants: Option<&[i128]>,
+) -> Result<(TokenStream, TokenStream)> {
+    match data {
+        Data::Enum(data_enum)
+            if data_enum
+                .variants
+                .iter()
+                .all(|variant| matches!(variant.fields, Fields::Unit)) =>
+        {
+            let variants = generate_enum_variant_names(data_enum, rename_all, discriminants)?;
+            let check = format!(
+                "name: builtins.elem name [ {} ]",
+                variants
+                    .iter()
+                    .map(|v| format!("\"{}\"", v))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+            Ok((
+                quote! { Some(&[#(#variants),*]) },
+                quote! { Some(#check.to_string()) },
+            ))
+        }
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let key_check = map_key_check(&fields.unnamed[0].ty);
+                Ok((quote! { None }, key_check))
+            }
+            _ => Ok((quote! { None }, quote! { None })),
+        },
+        _ => Ok((quote! { None }, quote! { None })),
+    }
+}
+
+/// Find the serialized name of the variant marked with `#[default]`.
+fn find_default_variant(
+    data_enum: &syn::DataEnum,
+    rename_all: Option<RenameRule>,
+    discriminants: Option<&[i128]>,
+) -> Result<Option<String>> {
+    let names = generate_enum_variant_names(data_enum, rename_all, discriminants)?;
+
+    Ok(data_enum
+        .variants
+        .iter()
+        .zip(names)
+        .find(|(variant, _)| {
+            matches!(variant.fields, Fields::Unit)
+                && variant
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("default"))
+        })
+        .map(|(_, name)| name))
+}
+
+/// Get the integer discriminants of a `#[nixos(repr)]` enum, in variant order.
+///
+/// The `serde_repr` derives themselves are stripped from the item before
+/// this derive runs, so the enum has to opt in with `#[nixos(repr)]`.
+fn serde_repr_discriminants(input: &DeriveInput, repr: bool) -> Result<Option<Vec<i128>>> {
+    let Data::Enum(data_enum) = &input.data else {
+        if repr {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`#[nixos(repr)]` is only valid on enums",
+            ));
+        }
+        return Ok(None);
+    };
+    if !repr {
+        return Ok(None);
+    }
+    if parse_repr_int(&input.attrs)?.is_none() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "enums serialized as numbers need an integer representation, e.g. `#[repr(u8)]`",
+        ));
+    }
+
+    let mut discriminants = Vec::new();
+    let mut next = 0;
+    for variant in &data_enum.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "enums serialized as numbers can only have unit variants",
+            ));
+        }
+        if let Some((_, expr)) = &variant.discriminant {
+            next = discriminant_value(expr)?;
+        }
+        discriminants.push(next);
+        next += 1;
+    }
+
+    Ok(Some(discriminants))
+}
+
+/// Evaluate an integer literal discriminant such as `5` or `-1`.
+fn discriminant_value(expr: &syn::Expr) -> Result<i128> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) => int.base10_parse(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => discriminant_value(expr).map(|value| -value),
+        syn::Expr::Paren(paren) => discriminant_value(&paren.expr),
+        syn::Expr::Group(group) => discriminant_value(&group.expr),
+        _ => Err(syn::Error::new_spanned(
+            expr,
+            "serde-nixos needs integer literal discriminants to describe this enum",
+        )),
+    }
+}
+
+/// Format a serialized enum value as a Nix literal.
+fn enum_value_literal(value: &str, numeric: bool) -> String {
+    if !numeric {
+        format!("\"{}\"", value)
+    } else if value.starts_with('-') {
+        // A bare negative number is not a valid list element in Nix
+        format!("({})", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Describe which number stands for which variant of a `serde_repr` enum.
+fn repr_value_description(data_enum: &syn::DataEnum, discriminants: &[i128]) -> String {
+    let values: Vec<String> = data_enum
+        .variants
+        .iter()
+        .zip(discriminants)
+        .map(|(variant, value)| format!("{} = {}", value, variant.ident))
+        .collect();
+    format!("Values: {}.", values.join(", "))
+}
+
 /// Convert a Rust type name to its camelCase NixOS type name (e.g. "AgentDefinition" -> "agentDefinitionType").
 fn to_nixos_type_name(type_name: &str) -> String {
     let mut chars = type_name.chars();
@@ -615,6 +1621,7 @@ fn to_nixos_type_name(type_name: &str) -> String {
 ///   - `HashMap<K, Custom>`   -> `types.attrsOf customType`
 ///   - `HashSet<Custom>`      -> `types.listOf customType`
 ///   - `Box<Custom>` / `Arc<Custom>` / `Rc<Custom>` -> `customType`
+///   - `[Custom; N]` / `(Custom, T)` -> fixed-length list checks on `customType`
 fn rust_type_to_nixos_named(ty: &Type) -> TokenStream {
     if let Some(type_name) = get_custom_type_name(ty) {
         // Direct custom type -> named reference
@@ -623,8 +1630,8 @@ fn rust_type_to_nixos_named(ty: &Type) -> TokenStream {
     } else if let Type::Path(type_path) = ty {
         if let Some(seg) = type_path.path.segments.last() {
             let ident = seg.ident.to_string();
-            match ident.as_str() {
-                "Vec" | "HashSet" | "BTreeSet" => {
+            match collection_alias(&ident) {
+                "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashSet" | "BTreeSet" => {
                     if let Some(inner) = get_generic_inner_type(&type_path.path) {
                         let inner_expr = rust_type_to_nixos_named(inner);
                         return quote! {
@@ -643,20 +1650,14 @@ fn rust_type_to_nixos_named(ty: &Type) -> TokenStream {
                 "HashMap" | "BTreeMap" => {
                     if let Some(value) = get_map_value_type(&type_path.path) {
                         let value_expr = rust_type_to_nixos_named(value);
-                        return quote! {
-                            {
-                                let inner = #value_expr;
-                                if inner.contains(' ') {
-                                    format!("types.attrsOf ({})", inner)
-                                } else {
-                                    format!("types.attrsOf {}", inner)
-                                }
-                            }
-                        };
+                        return map_to_nixos_type(
+                            get_generic_inner_type(&type_path.path),
+                            value_expr,
+                        );
                     }
                     rust_type_to_nixos(ty)
                 }
-                "Box" | "Rc" | "Arc" => {
+                "Box" | "Rc" | "Arc" | "Cow" => {
                     if let Some(inner) = get_generic_inner_type(&type_path.path) {
                         return rust_type_to_nixos_named(inner);
                     }
@@ -668,6 +1669,18 @@ fn rust_type_to_nixos_named(ty: &Type) -> TokenStream {
             rust_type_to_nixos(ty)
         }
     } else {
-        rust_type_to_nixos(ty)
+        match ty {
+            Type::Reference(reference) => rust_type_to_nixos_named(&reference.elem),
+            Type::Paren(paren) => rust_type_to_nixos_named(&paren.elem),
+            Type::Array(array) => {
+                array_to_nixos_type(rust_type_to_nixos_named(&array.elem), &array.len)
+            }
+            Type::Tuple(tuple) => {
+                let elems: Vec<TokenStream> =
+                    tuple.elems.iter().map(rust_type_to_nixos_named).collect();
+                tuple_to_nixos_type(&elems)
+            }
+            _ => rust_type_to_nixos(ty),
+        }
     }
 }
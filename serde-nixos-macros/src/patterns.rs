@@ -0,0 +1,252 @@
+//! Compile-time checks of `pattern` constraints.
+//!
+//! A pattern is used twice: by `types.strMatching` in Nix, a POSIX extended
+//! regular expression matched against bytes, and by `nixos_validate()`
+//! through `regex-lite`. Only the part of ERE that both read the same way is
+//! accepted: literals, `.`, `^` / `$`, groups, `|`, the quantifiers `*`,
+//! `+`, `?` and `{n,m}`, bracket expressions with ranges and `[:class:]`
+//! names, and backslash escapes of punctuation. Perl-style classes (`\d`),
+//! `(?...)` groups, lazy quantifiers and non-ASCII characters are rejected.
+
+const CLASSES: &[&str] = &[
+    "alnum", "alpha", "blank", "cntrl", "digit", "graph", "lower", "print", "punct", "space",
+    "upper", "xdigit",
+];
+
+/// What the previous token allows after it
+#[derive(Clone, Copy, PartialEq)]
+enum Last {
+    /// Start of the pattern, a group or an alternative
+    Start,
+    /// Something a quantifier applies to
+    Atom,
+    /// An anchor or a quantifier
+    Other,
+}
+
+/// Check that `pattern` is a POSIX ERE both Nix and `regex-lite` accept and
+/// read the same way.
+pub fn check_pattern(pattern: &str) -> Result<(), String> {
+    if let Some(c) = pattern.chars().find(|c| !c.is_ascii()) {
+        return Err(format!(
+            "`{}` in pattern: Nix matches bytes, so patterns must be ASCII",
+            c
+        ));
+    }
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+    let mut depth = 0usize;
+    let mut last = Last::Start;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' => {
+                let Some(&escaped) = chars.get(i + 1) else {
+                    return Err("pattern ends with `\\`".to_string());
+                };
+                if !"\\.[](){}*+?|^$".contains(escaped) {
+                    return Err(format!(
+                        "`\\{}` is not POSIX ERE; use a bracket expression such as \
+                         `[[:digit:]]` or `[[:space:]]` instead",
+                        escaped
+                    ));
+                }
+                i += 2;
+                last = Last::Atom;
+                continue;
+            }
+            '[' => {
+                i = check_bracket(&chars, i)?;
+                last = Last::Atom;
+                continue;
+            }
+            '(' => {
+                if chars.get(i + 1) == Some(&'?') {
+                    return Err("`(?...)` groups and flags are not POSIX ERE".to_string());
+                }
+                if chars.get(i + 1) == Some(&')') {
+                    return Err("empty group in pattern".to_string());
+                }
+                depth += 1;
+                last = Last::Start;
+            }
+            ')' => {
+                if depth == 0 {
+                    return Err("unmatched `)` in pattern".to_string());
+                }
+                if last == Last::Start {
+                    return Err("empty alternative in pattern".to_string());
+                }
+                depth -= 1;
+                last = Last::Atom;
+            }
+            '|' => {
+                if last == Last::Start {
+                    return Err("empty alternative in pattern".to_string());
+                }
+                last = Last::Start;
+            }
+            '*' | '+' | '?' | '{' => {
+                if last != Last::Atom {
+                    return Err(format!("`{}` in pattern has nothing to repeat", c));
+                }
+                if c == '{' {
+                    i = check_bound(&chars, i)?;
+                } else {
+                    i += 1;
+                }
+                if let Some(&next) = chars.get(i) {
+                    if matches!(next, '*' | '+' | '?' | '{') {
+                        return Err(
+                            "lazy, possessive and repeated quantifiers are not POSIX ERE"
+                                .to_string(),
+                        );
+                    }
+                }
+                last = Last::Other;
+                continue;
+            }
+            '}' | ']' => {
+                return Err(format!(
+                    "unmatched `{}` in pattern; escape it as `\\{}`",
+                    c, c
+                ));
+            }
+            '^' | '$' => last = Last::Other,
+            _ => last = Last::Atom,
+        }
+        i += 1;
+    }
+
+    if depth > 0 {
+        return Err("unclosed `(` in pattern".to_string());
+    }
+    if last == Last::Start && !chars.is_empty() {
+        return Err("empty alternative in pattern".to_string());
+    }
+    Ok(())
+}
+
+/// Check the bracket expression starting at `chars[start]`, returning the
+/// index after it.
+fn check_bracket(chars: &[char], start: usize) -> Result<usize, String> {
+    let mut i = start + 1;
+    if chars.get(i) == Some(&'^') {
+        i += 1;
+    }
+    let first = i;
+    // A leading `]` is a literal
+    let mut previous = None;
+    if chars.get(i) == Some(&']') {
+        previous = Some(']');
+        i += 1;
+    }
+
+    loop {
+        let Some(&c) = chars.get(i) else {
+            return Err("unclosed `[` in pattern".to_string());
+        };
+        match c {
+            ']' => return Ok(i + 1),
+            '[' if chars.get(i + 1) == Some(&':') => {
+                let rest: String = chars[i + 2..].iter().collect();
+                let Some(end) = rest.find(":]") else {
+                    return Err("unclosed `[:` in pattern".to_string());
+                };
+                let name = &rest[..end];
+                if !CLASSES.contains(&name) {
+                    return Err(format!("unknown character class `[:{}:]`", name));
+                }
+                i += end + 4;
+                previous = None;
+                continue;
+            }
+            '[' => {
+                return Err(
+                    "`[` inside a bracket expression is read differently by Nix and \
+                     regex-lite; only `[:class:]` names are supported"
+                        .to_string(),
+                )
+            }
+            '\\' => {
+                return Err(
+                    "`\\` is a literal inside POSIX bracket expressions but an escape for \
+                     regex-lite; use `[:class:]` names or move it out of the brackets"
+                        .to_string(),
+                )
+            }
+            '&' | '~' if chars.get(i + 1) == Some(&c) => {
+                return Err(format!(
+                    "`{}{}` in a bracket expression is read differently by Nix and regex-lite",
+                    c, c
+                ))
+            }
+            '-' if chars.get(i + 1) != Some(&']') => match previous {
+                Some(from) => {
+                    let Some(&to) = chars.get(i + 1) else {
+                        return Err("unclosed `[` in pattern".to_string());
+                    };
+                    if matches!(to, '[' | '\\' | '-') {
+                        return Err(format!(
+                            "range `{}-{}` in pattern has an unsupported end",
+                            from, to
+                        ));
+                    }
+                    if to < from {
+                        return Err(format!("range `{}-{}` in pattern is reversed", from, to));
+                    }
+                    i += 2;
+                    previous = None;
+                    continue;
+                }
+                // Leading `-` is a literal
+                None if i == first => {
+                    previous = Some('-');
+                }
+                None => {
+                    return Err(
+                        "`-` in a bracket expression must come first, last or in a range"
+                            .to_string(),
+                    )
+                }
+            },
+            _ => previous = Some(c),
+        }
+        i += 1;
+    }
+}
+
+/// Check the `{n}`, `{n,}` or `{n,m}` bound starting at `chars[start]`,
+/// returning the index after it.
+fn check_bound(chars: &[char], start: usize) -> Result<usize, String> {
+    let rest: String = chars[start + 1..].iter().collect();
+    let Some(end) = rest.find('}') else {
+        return Err("unclosed `{` in pattern; escape it as `\\{`".to_string());
+    };
+    let bound = &rest[..end];
+    let invalid = || format!("`{{{}}}` is not a valid repetition bound", bound);
+    let number = |s: &str| -> Result<u32, String> {
+        if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        s.parse::<u32>()
+            .ok()
+            .filter(|&n| n <= 255)
+            .ok_or_else(|| "repetition bounds in patterns must be at most 255".to_string())
+    };
+    match bound.split_once(',') {
+        None => {
+            number(bound)?;
+        }
+        Some((min, "")) => {
+            number(min)?;
+        }
+        Some((min, max)) => {
+            if number(min)? > number(max)? {
+                return Err(invalid());
+            }
+        }
+    }
+    Ok(start + end + 2)
+}
@@ -0,0 +1,214 @@
+//! `#[nixos(port, open_firewall)]` fields.
+//!
+//! Port fields are declared with `types.port`. Those marked `open_firewall`
+//! are listed by `nixos_firewall_ports(value)` as Nix list expressions, which
+//! the generated service module adds to `networking.firewall` when its
+//! `openFirewall` option is set.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, Field, Fields, Result, Type};
+
+use crate::adapters::{wire_type, WireType};
+use crate::attributes::{
+    apply_rename_rule_to_field, combine_attributes, parse_nixos_attributes, parse_serde_attributes,
+    EffectiveAttributes, PortProtocol, RenameRule,
+};
+use crate::ecosystem::collection_alias;
+use crate::type_mapping::{
+    get_custom_type_name, get_generic_inner_type, get_map_value_type, is_optional_type,
+    unwrap_option_type,
+};
+
+/// How a field holds its values, after removing `Option`
+enum Shape<'a> {
+    Single(&'a Type),
+    List(&'a Type),
+    Map(&'a Type),
+}
+
+fn shape(ty: &Type) -> Shape<'_> {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            let inner = match collection_alias(&segment.ident.to_string()) {
+                "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashSet" | "BTreeSet" => {
+                    get_generic_inner_type(&type_path.path).map(Shape::List)
+                }
+                "HashMap" | "BTreeMap" => get_map_value_type(&type_path.path).map(Shape::Map),
+                _ => None,
+            };
+            if let Some(inner) = inner {
+                return inner;
+            }
+        }
+    }
+    Shape::Single(ty)
+}
+
+fn is_u16(ty: &Type) -> bool {
+    matches!(ty, Type::Path(type_path) if type_path.path.is_ident("u16"))
+}
+
+/// The NixOS type of a `#[nixos(port)]` field without `types.nullOr`, or
+/// `None` for other fields.
+pub fn port_type(field: &Field, attrs: &EffectiveAttributes) -> Result<Option<String>> {
+    if attrs.port.is_none() {
+        if attrs.open_firewall {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`open_firewall` applies to `#[nixos(port)]` fields",
+            ));
+        }
+        return Ok(None);
+    }
+
+    let value_type = unwrap_option_type(&field.ty);
+    let invalid = if attrs.secret || attrs.flatten {
+        Some("secret and flattened fields cannot be ports")
+    } else if attrs.type_override.is_some()
+        || !matches!(
+            wire_type(attrs, value_type, &|_| quote! {}),
+            WireType::Native
+        )
+    {
+        Some("`port` cannot be combined with `#[nixos(type)]` or a serde adapter")
+    } else {
+        None
+    };
+    if let Some(message) = invalid {
+        return Err(syn::Error::new_spanned(field, message));
+    }
+
+    match shape(value_type) {
+        Shape::Single(ty) if is_u16(ty) => Ok(Some("types.port".to_string())),
+        Shape::List(ty) if is_u16(ty) => Ok(Some("types.listOf types.port".to_string())),
+        Shape::Map(ty) if is_u16(ty) => Ok(Some("types.attrsOf types.port".to_string())),
+        _ => Err(syn::Error::new_spanned(
+            field,
+            "`port` applies to `u16` fields and lists or maps of `u16`",
+        )),
+    }
+}
+
+/// Generate the body of `nixos_firewall_ports_at(value, visiting)`, listing
+/// `(protocol, ports)` pairs for the option value `value`.
+///
+/// Ports of nested struct fields are included, also inside `Option`s, lists
+/// and maps of them; those of recursive types are not.
+pub fn generate_firewall_ports(data: &Data, rename_all: Option<RenameRule>) -> Result<TokenStream> {
+    let fields = match data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => fields,
+            _ => return Ok(quote! { Vec::new() }),
+        },
+        _ => return Ok(quote! { Vec::new() }),
+    };
+
+    let mut ports = Vec::new();
+    for field in &fields.named {
+        let field_name = field.ident.as_ref().unwrap();
+        let attrs = combine_attributes(
+            parse_nixos_attributes(&field.attrs)?,
+            parse_serde_attributes(&field.attrs)?,
+            None,
+            false,
+        );
+        if attrs.skip {
+            continue;
+        }
+        let value_type = unwrap_option_type(&field.ty);
+
+        let option_name = attrs.name.clone().unwrap_or_else(|| match rename_all {
+            Some(rule) => apply_rename_rule_to_field(&field_name.to_string(), rule),
+            None => field_name.to_string(),
+        });
+        // `#[nixos(path)]` names are joined segment by segment
+        let segments: Vec<&str> = if attrs.path {
+            option_name.split('.').collect()
+        } else {
+            vec![option_name.as_str()]
+        };
+        let field_value = quote! {
+            [#(#segments),*].iter().fold(value.to_string(), |value, segment| {
+                ::serde_nixos::validation::join_path(&value, segment)
+            })
+        };
+        // Same condition as the `types.nullOr` wrapping of the option
+        let nullable =
+            is_optional_type(&field.ty) || (attrs.optional && !attrs.has_nixos_default());
+
+        if attrs.open_firewall {
+            let protocol = match attrs.port {
+                Some(PortProtocol::Udp) => quote! { ::serde_nixos::service::PortProtocol::Udp },
+                _ => quote! { ::serde_nixos::service::PortProtocol::Tcp },
+            };
+            let list = match (shape(value_type), nullable) {
+                (Shape::Single(_), false) => quote! { format!("[ {} ]", field_value) },
+                (Shape::Single(_), true) => {
+                    quote! { format!("lib.optional ({0} != null) {0}", field_value) }
+                }
+                (Shape::List(_), false) => quote! { field_value },
+                (Shape::List(_), true) => {
+                    quote! { format!("lib.optionals ({0} != null) {0}", field_value) }
+                }
+                (Shape::Map(_), false) => quote! { format!("lib.attrValues {}", field_value) },
+                (Shape::Map(_), true) => quote! {
+                    format!("lib.optionals ({0} != null) (lib.attrValues {0})", field_value)
+                },
+            };
+            ports.push(quote! {
+                {
+                    let field_value = #field_value;
+                    ports.push((#protocol, #list));
+                }
+            });
+            continue;
+        }
+
+        if attrs.type_override.is_some()
+            || !matches!(
+                wire_type(&attrs, value_type, &|_| quote! {}),
+                WireType::Native
+            )
+        {
+            continue;
+        }
+        // Nested ports refer to the value, or to each item as `v`
+        let (item_type, item_value, wrap) = match shape(value_type) {
+            Shape::Single(ty) => (ty, quote! { &field_value }, quote! { list }),
+            Shape::List(ty) => (
+                ty,
+                quote! { "v" },
+                quote! { format!("lib.concatMap (v: {}) {}", list, field_value) },
+            ),
+            Shape::Map(ty) => (
+                ty,
+                quote! { "v" },
+                quote! { format!("lib.concatMap (v: {}) (lib.attrValues {})", list, field_value) },
+            ),
+        };
+        if get_custom_type_name(item_type).is_none() {
+            continue;
+        }
+        let guard = if nullable {
+            quote! { format!("lib.optionals ({} != null) ({})", field_value, list) }
+        } else {
+            quote! { list }
+        };
+        ports.push(quote! {
+            {
+                let field_value = #field_value;
+                for (protocol, list) in <#item_type>::nixos_firewall_ports_at(#item_value, visiting) {
+                    let list = #wrap;
+                    ports.push((protocol, #guard));
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
+        let mut ports: Vec<(::serde_nixos::service::PortProtocol, String)> = Vec::new();
+        #(#ports)*
+        ports
+    })
+}
@@ -0,0 +1,180 @@
+//! `#[nixos(secret)]` fields.
+//!
+//! A secret field is declared as a `<name>File` option holding the path of
+//! a file, so the value never reaches the Nix store. The file is passed to
+//! the service as the systemd credential named after the option path, left
+//! out of the config file and read back into the field by
+//! `nixos_load_secrets()`.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, Field, Fields, Result};
+
+use crate::adapters::{wire_type, WireType};
+use crate::attributes::{
+    apply_rename_rule_to_field, combine_attributes, parse_nixos_attributes, parse_serde_attributes,
+    EffectiveAttributes, RenameRule,
+};
+use crate::docs::{convert_doc, doc_tokens, LinkContext};
+use crate::type_mapping::{get_custom_type_name, is_optional_type};
+
+/// Note added to the description of every secret option
+const SECRET_NOTE: &str = "Path of a file containing the value, read by the service at runtime. \
+     Use a string, not a Nix path, to keep the secret out of the Nix store.";
+
+/// Generate the `<name>File` option of the secret field `field`, pushed to
+/// `result` like the other options.
+pub fn secret_option(
+    field: &Field,
+    attrs: &EffectiveAttributes,
+    option_name: &str,
+    doc_comment: Option<&String>,
+    link_context: &LinkContext<'_>,
+    use_named_types: bool,
+) -> Result<TokenStream> {
+    let invalid = if attrs.has_nixos_default() {
+        Some("secrets cannot have a Nix default; it would end up in the Nix store")
+    } else if attrs.example_value.is_some() || attrs.type_override.is_some() {
+        Some("secret options take a file path; `example_value` and `type` don't apply")
+    } else if attrs.flatten {
+        Some("flattened fields cannot be secrets")
+    } else if !attrs.optional && !is_optional_type(&field.ty) {
+        Some(
+            "secrets are left out of the config file and loaded afterwards; make the field \
+             an `Option` or add `#[serde(default)]`",
+        )
+    } else {
+        None
+    };
+    if let Some(message) = invalid {
+        return Err(syn::Error::new_spanned(field, message));
+    }
+
+    let indent = if use_named_types { "      " } else { "    " };
+    let field_indent = if use_named_types { "    " } else { "  " };
+    let own_description = match &attrs.description {
+        Some(desc) if Some(desc) == doc_comment => {
+            let doc = doc_tokens(&convert_doc(desc, Some(link_context)));
+            quote! { Some(#doc) }
+        }
+        Some(desc) => quote! { Some(#desc.to_string()) },
+        None => quote! { None::<String> },
+    };
+    let example = attrs.example.iter().map(|example| {
+        quote! {
+            result.push_str(#indent);
+            result.push_str("example = ");
+            result.push_str(#example);
+            result.push_str(";\n");
+        }
+    });
+
+    Ok(quote! {
+        result.push_str(#field_indent);
+        result.push_str(#option_name);
+        result.push_str(" = lib.mkOption {\n");
+        result.push_str(#indent);
+        result.push_str("type = types.nullOr types.path;\n");
+        {
+            let mut parts: Vec<String> = #own_description.into_iter().collect();
+            parts.push(#SECRET_NOTE.to_string());
+            result.push_str(#indent);
+            result.push_str("description = ");
+            result.push_str(&::serde_nixos::utils::format_nix_string(
+                &parts.join("\n\n"),
+                #indent,
+            ));
+            result.push_str(";\n");
+        }
+        result.push_str(#indent);
+        result.push_str("default = null;\n");
+        #(#example)*
+        result.push_str(#field_indent);
+        result.push_str("};\n\n");
+    })
+}
+
+/// Generate the bodies of `nixos_secrets_at(prefix)`, listing credential
+/// names, and `nixos_load_secrets_from(&mut self, dir, prefix)`.
+///
+/// Secrets of nested struct fields are included; those inside collections
+/// or `Option`s are not, as their options don't have a single path.
+pub fn generate_secrets(
+    data: &Data,
+    rename_all: Option<RenameRule>,
+) -> Result<(TokenStream, TokenStream)> {
+    let fields = match data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => fields,
+            _ => return Ok((quote! { Vec::new() }, quote! { Ok(()) })),
+        },
+        _ => return Ok((quote! { Vec::new() }, quote! { Ok(()) })),
+    };
+
+    let mut names = Vec::new();
+    let mut loads = Vec::new();
+    for field in &fields.named {
+        let field_name = field.ident.as_ref().unwrap();
+        let attrs = combine_attributes(
+            parse_nixos_attributes(&field.attrs)?,
+            parse_serde_attributes(&field.attrs)?,
+            None,
+            false,
+        );
+        if attrs.skip || attrs.type_override.is_some() {
+            continue;
+        }
+        let option_name = attrs.name.clone().unwrap_or_else(|| match rename_all {
+            Some(rule) => apply_rename_rule_to_field(&field_name.to_string(), rule),
+            None => field_name.to_string(),
+        });
+
+        if attrs.secret {
+            names.push(quote! {
+                secrets.push(::serde_nixos::secrets::credential_name(prefix, #option_name));
+            });
+            loads.push(quote! {
+                if let Some(value) = ::serde_nixos::secrets::read_credential(
+                    dir,
+                    &::serde_nixos::secrets::credential_name(prefix, #option_name),
+                )? {
+                    self.#field_name = ::std::convert::From::from(value);
+                }
+            });
+            continue;
+        }
+
+        let nested = !is_optional_type(&field.ty)
+            && matches!(
+                wire_type(&attrs, &field.ty, &|_| quote! {}),
+                WireType::Native
+            )
+            && get_custom_type_name(&field.ty).is_some();
+        if nested {
+            let field_type = &field.ty;
+            names.push(quote! {
+                secrets.extend(<#field_type>::nixos_secrets_at(
+                    &::serde_nixos::secrets::credential_name(prefix, #option_name),
+                ));
+            });
+            loads.push(quote! {
+                self.#field_name.nixos_load_secrets_from(
+                    dir,
+                    &::serde_nixos::secrets::credential_name(prefix, #option_name),
+                )?;
+            });
+        }
+    }
+
+    Ok((
+        quote! {
+            let mut secrets: Vec<String> = Vec::new();
+            #(#names)*
+            secrets
+        },
+        quote! {
+            #(#loads)*
+            Ok(())
+        },
+    ))
+}
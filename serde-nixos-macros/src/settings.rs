@@ -0,0 +1,198 @@
+//! Nix functions converting option values into the serde shape.
+//!
+//! Each derived type gets a function `<name>ToSettings` that takes the
+//! evaluated value of its options and returns what the Rust type
+//! deserializes: option names and paths mapped back to serde names,
+//! flattened fields merged in, unset
+//! `#[serde(default)]` fields left out and enum tags added. Functions of
+//! nested types are referred to by name and bound next to it.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Result, Type};
+
+use crate::adapters::{wire_type, WireType};
+use crate::attributes::{
+    apply_rename_rule_to_field, combine_attributes, parse_nixos_attributes, parse_serde_attributes,
+    RenameRule, SerdeContainerAttributes,
+};
+use crate::ecosystem::collection_alias;
+use crate::nix_strings::{escape_nix_string, nix_attr_name};
+use crate::type_mapping::{
+    get_custom_type_name, get_generic_inner_type, get_map_value_type, is_optional_type,
+    unwrap_option_type,
+};
+
+/// Placeholder for the converted value in conversion templates
+const VALUE: &str = "{value}";
+
+/// The name of the settings function of a Rust type.
+pub fn to_settings_name(type_name: &str) -> String {
+    let mut chars = type_name.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}ToSettings", first.to_lowercase(), chars.as_str()),
+        None => "toSettings".to_string(),
+    }
+}
+
+/// Generate the body of `nixos_to_settings_function()`, which returns the
+/// settings function of this type. `rename_all` is the rule for option
+/// names, which may differ from serde's.
+pub fn generate_to_settings_function(
+    input: &DeriveInput,
+    serde_attrs: &SerdeContainerAttributes,
+    rename_all: Option<RenameRule>,
+) -> Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => fields,
+            _ => return Ok(quote! { "v: v".to_string() }),
+        },
+        // Enum options hold the serialized variant name
+        _ => {
+            let function = match (&serde_attrs.tag, serde_attrs.untagged) {
+                (_, true) => "v: null".to_string(),
+                (Some(tag), false) => format!("v: {{ {} = v; }}", nix_attr_name(tag)),
+                (None, false) => "v: v".to_string(),
+            };
+            return Ok(quote! { #function.to_string() });
+        }
+    };
+
+    let mut attrs = Vec::new();
+    let mut merges = Vec::new();
+    if let Some(tag) = &serde_attrs.tag {
+        // Serde tags a struct with its (renamed) name
+        let name = serde_attrs
+            .rename
+            .clone()
+            .unwrap_or_else(|| input.ident.to_string());
+        attrs.push(format!(
+            "{} = \"{}\";",
+            nix_attr_name(tag),
+            escape_nix_string(&name)
+        ));
+    }
+    for field in &fields.named {
+        let field_name = field.ident.as_ref().unwrap();
+        let effective_attrs = combine_attributes(
+            parse_nixos_attributes(&field.attrs)?,
+            parse_serde_attributes(&field.attrs)?,
+            None,
+            false,
+        );
+        // Secrets are loaded from credentials instead
+        if effective_attrs.skip || effective_attrs.secret {
+            continue;
+        }
+        let renamed = |name: &Option<String>, rule: Option<RenameRule>| {
+            name.clone().unwrap_or_else(|| match rule {
+                Some(rule) => apply_rename_rule_to_field(&field_name.to_string(), rule),
+                None => field_name.to_string(),
+            })
+        };
+        let option_name = renamed(&effective_attrs.name, rename_all);
+        let serde_name = renamed(&effective_attrs.serde_name, serde_attrs.rename_all);
+        // Options placed with `#[nixos(path)]` are nested attribute sets
+        let value = if effective_attrs.path {
+            format!("cfg.{}", option_name)
+        } else {
+            format!("cfg.{}", nix_attr_name(&option_name))
+        };
+        let native = effective_attrs.type_override.is_none()
+            && matches!(
+                wire_type(
+                    &effective_attrs,
+                    unwrap_option_type(&field.ty),
+                    &|_| quote! {}
+                ),
+                WireType::Native
+            );
+        let converted = match native.then(|| value_conversion(&field.ty)).flatten() {
+            Some(template) => template.replace(VALUE, &value),
+            None => value.clone(),
+        };
+
+        if effective_attrs.flatten {
+            merges.push(converted);
+        } else if effective_attrs.optional
+            && !effective_attrs.has_nixos_default()
+            && !is_optional_type(&field.ty)
+        {
+            // Left out when unset, so the Rust default applies
+            merges.push(format!(
+                "lib.optionalAttrs ({} != null) {{ {} = {}; }}",
+                value,
+                nix_attr_name(&serde_name),
+                converted
+            ));
+        } else {
+            attrs.push(format!("{} = {};", nix_attr_name(&serde_name), converted));
+        }
+    }
+
+    let mut function = String::from("cfg:");
+    if attrs.is_empty() {
+        function.push_str(" { }");
+    } else {
+        function.push_str(" {\n");
+        for attr in &attrs {
+            function.push_str("  ");
+            function.push_str(attr);
+            function.push('\n');
+        }
+        function.push('}');
+    }
+    for merge in &merges {
+        function.push_str("\n// ");
+        function.push_str(merge);
+    }
+    Ok(quote! { #function.to_string() })
+}
+
+/// A template converting a value of type `ty` (`{value}`), or `None` if
+/// the option value already has the serde shape.
+fn value_conversion(ty: &Type) -> Option<String> {
+    let inner = |ty: &Type| value_conversion(ty).map(|template| template.replace(VALUE, "v"));
+    match ty {
+        Type::Reference(reference) => value_conversion(&reference.elem),
+        Type::Paren(paren) => value_conversion(&paren.elem),
+        Type::Array(array) => inner(&array.elem).map(|f| format!("map (v: {}) {}", f, VALUE)),
+        Type::Path(type_path) => {
+            let segment = type_path.path.segments.last()?;
+            match collection_alias(&segment.ident.to_string()) {
+                "Option" => inner(get_generic_inner_type(&type_path.path)?)
+                    .map(|f| format!("lib.mapNullable (v: {}) {}", f, VALUE)),
+                "Box" | "Rc" | "Arc" => value_conversion(get_generic_inner_type(&type_path.path)?),
+                "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashSet" | "BTreeSet" => {
+                    inner(get_generic_inner_type(&type_path.path)?)
+                        .map(|f| format!("map (v: {}) {}", f, VALUE))
+                }
+                "HashMap" | "BTreeMap" => {
+                    let value_type = get_map_value_type(&type_path.path)?;
+                    // Maps keyed by a unit-only enum are submodules with a
+                    // nullable option per variant; unset variants are left
+                    // out. Other custom keys never have null values unless
+                    // the value type is an `Option`.
+                    let keys = get_generic_inner_type(&type_path.path);
+                    let drop_nulls = keys.is_some_and(|ty| get_custom_type_name(ty).is_some())
+                        && !is_optional_type(value_type);
+                    match (inner(value_type), drop_nulls) {
+                        (Some(f), true) => Some(format!(
+                            "lib.mapAttrs (_: v: {}) (lib.filterAttrs (_: v: v != null) {})",
+                            f, VALUE
+                        )),
+                        (Some(f), false) => Some(format!("lib.mapAttrs (_: v: {}) {}", f, VALUE)),
+                        (None, true) => {
+                            Some(format!("lib.filterAttrs (_: v: v != null) {}", VALUE))
+                        }
+                        (None, false) => None,
+                    }
+                }
+                _ => get_custom_type_name(ty)
+                    .map(|name| format!("{} {}", to_settings_name(&name), VALUE)),
+            }
+        }
+        _ => None,
+    }
+}
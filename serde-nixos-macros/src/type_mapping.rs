@@ -2,15 +2,142 @@
 use quote::quote;
 use syn::{GenericArgument, Path, PathArguments, Type};
 
+use crate::ecosystem::{collection_alias, ecosystem_type, is_type};
+
 /// Maps Rust types to their corresponding NixOS type expressions
 pub fn rust_type_to_nixos(ty: &Type) -> TokenStream {
     match ty {
         Type::Path(type_path) => path_to_nixos_type(&type_path.path),
         Type::Reference(reference) => rust_type_to_nixos(&reference.elem),
+        Type::Paren(paren) => rust_type_to_nixos(&paren.elem),
+        Type::Array(array) => array_to_nixos_type(rust_type_to_nixos(&array.elem), &array.len),
+        Type::Tuple(tuple) => {
+            let elems: Vec<TokenStream> = tuple.elems.iter().map(rust_type_to_nixos).collect();
+            tuple_to_nixos_type(&elems)
+        }
         _ => quote! { "types.attrs" }, // Fallback to attrs for unknown types
     }
 }
 
+/// Whether the mapping of `ty` falls back to `types.attrs` anywhere, i.e.
+/// the type has no real NixOS counterpart. Mirrors [`rust_type_to_nixos`].
+pub fn is_untyped_fallback(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => {
+            let path = &type_path.path;
+            if ecosystem_type(path).is_some() {
+                return false;
+            }
+            let type_name = path
+                .segments
+                .last()
+                .map(|s| s.ident.to_string())
+                .unwrap_or_default();
+            let inner = match collection_alias(&type_name) {
+                "HashMap" | "BTreeMap" => get_map_value_type(path),
+                "Box" | "Rc" | "Arc" | "Cow" | "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap"
+                | "Option" | "HashSet" | "BTreeSet" => get_generic_inner_type(path),
+                // Custom types must derive `NixosType` themselves
+                _ => return false,
+            };
+            match inner {
+                Some(inner) => is_untyped_fallback(inner),
+                None => true,
+            }
+        }
+        Type::Reference(reference) => is_untyped_fallback(&reference.elem),
+        Type::Paren(paren) => is_untyped_fallback(&paren.elem),
+        Type::Array(array) => is_untyped_fallback(&array.elem),
+        Type::Tuple(tuple) => tuple.elems.iter().any(is_untyped_fallback),
+        _ => true,
+    }
+}
+
+/// `char` is serialized as a one-character string. Nix string functions work
+/// on bytes, so a non-ASCII character is accepted as 2-4 non-ASCII bytes.
+const CHAR_TYPE: &str = "types.addCheck types.str (s: builtins.stringLength s == 1 \
+     || (builtins.stringLength s <= 4 && builtins.match \"[^ -~]+\" s != null))";
+
+/// `std::time::Duration` in its serde struct form `{ secs, nanos }`.
+const DURATION_TYPE: &str = "types.submodule { options = { \
+     secs = lib.mkOption { type = types.ints.unsigned; }; \
+     nanos = lib.mkOption { type = types.ints.between 0 999999999; }; }; }";
+
+pub(crate) const IPV4_PATTERN: &str = "((25[0-5]|2[0-4][0-9]|1[0-9][0-9]|[1-9]?[0-9])[.]){3}\
+     (25[0-5]|2[0-4][0-9]|1[0-9][0-9]|[1-9]?[0-9])";
+
+/// Loose IPv6 format check: hex groups separated by colons, optionally
+/// ending in an embedded IPv4 address.
+pub(crate) const IPV6_PATTERN: &str = "[0-9a-fA-F]{0,4}(:[0-9a-fA-F]{0,4}){1,7}(:[0-9.]+)?";
+
+const PORT_PATTERN: &str = "[0-9]{1,5}";
+
+/// Build a `types.strMatching` expression for the std network address types.
+fn ip_address_type(type_name: &str) -> Option<String> {
+    let pattern = match type_name {
+        "Ipv4Addr" => IPV4_PATTERN.to_string(),
+        "Ipv6Addr" => IPV6_PATTERN.to_string(),
+        "IpAddr" => format!("{}|{}", IPV4_PATTERN, IPV6_PATTERN),
+        "SocketAddrV4" => format!("({}):{}", IPV4_PATTERN, PORT_PATTERN),
+        "SocketAddrV6" => format!("[[]({})[]]:{}", IPV6_PATTERN, PORT_PATTERN),
+        "SocketAddr" => format!(
+            "({}):{}|[[]({})[]]:{}",
+            IPV4_PATTERN, PORT_PATTERN, IPV6_PATTERN, PORT_PATTERN
+        ),
+        _ => return None,
+    };
+
+    Some(format!("types.strMatching \"{}\"", pattern))
+}
+
+/// Build the NixOS type for a fixed-size array `[T; N]`.
+///
+/// The length is checked at evaluation time; `N` is emitted as a Rust
+/// expression so const generics and named constants work too.
+pub fn array_to_nixos_type(elem_nixos: TokenStream, len: &syn::Expr) -> TokenStream {
+    quote! {
+        {
+            let inner = #elem_nixos;
+            if inner.contains(' ') {
+                format!(
+                    "types.addCheck (types.listOf ({})) (l: builtins.length l == {})",
+                    inner, #len
+                )
+            } else {
+                format!(
+                    "types.addCheck (types.listOf {}) (l: builtins.length l == {})",
+                    inner, #len
+                )
+            }
+        }
+    }
+}
+
+/// Build the NixOS type for a tuple, serialized by serde as a fixed-length list.
+///
+/// Nix has no heterogeneous list type, so each position is checked against
+/// its element type's `check`. The unit tuple `()` serializes to `null`.
+pub fn tuple_to_nixos_type(elems: &[TokenStream]) -> TokenStream {
+    if elems.is_empty() {
+        return quote! { "types.null" };
+    }
+
+    let len = elems.len();
+    let indices = 0..len;
+    quote! {
+        {
+            let checks: Vec<String> = vec![
+                #(format!("({}).check (builtins.elemAt l {})", #elems, #indices)),*
+            ];
+            format!(
+                "types.addCheck (types.listOf types.anything) (l: builtins.length l == {} && {})",
+                #len,
+                checks.join(" && ")
+            )
+        }
+    }
+}
+
 /// Convert a type path to a NixOS type expression
 fn path_to_nixos_type(path: &Path) -> TokenStream {
     let type_name = path
@@ -19,13 +146,42 @@ fn path_to_nixos_type(path: &Path) -> TokenStream {
         .map(|s| s.ident.to_string())
         .unwrap_or_default();
 
-    match type_name.as_str() {
+    if let Some(nixos_type) = ecosystem_type(path) {
+        return quote! { #nixos_type };
+    }
+    if is_std_duration(path) {
+        return quote! { #DURATION_TYPE };
+    }
+    if is_json_value(path) {
+        return quote! { "types.anything" };
+    }
+
+    match collection_alias(&type_name) {
         // Primitive types
         "bool" => quote! { "types.bool" },
         "String" | "str" => quote! { "types.str" },
         "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128" | "usize"
         | "isize" => quote! { "types.int" },
         "f32" | "f64" => quote! { "types.float" },
+        "char" => quote! { #CHAR_TYPE },
+        "NonZeroU8" | "NonZeroU16" | "NonZeroU32" | "NonZeroU64" | "NonZeroU128"
+        | "NonZeroUsize" => quote! { "types.ints.positive" },
+        "NonZeroI8" | "NonZeroI16" | "NonZeroI32" | "NonZeroI64" | "NonZeroI128"
+        | "NonZeroIsize" => quote! { "types.addCheck types.int (x: x != 0)" },
+        "OsString" | "OsStr" => quote! { "types.str" },
+        "Ipv4Addr" | "Ipv6Addr" | "IpAddr" | "SocketAddrV4" | "SocketAddrV6" | "SocketAddr" => {
+            let ip_type = ip_address_type(&type_name).unwrap_or_default();
+            quote! { #ip_type }
+        }
+
+        // Smart pointers and `Cow` serialize transparently as their inner type
+        "Box" | "Rc" | "Arc" | "Cow" => {
+            if let Some(inner_type) = get_generic_inner_type(path) {
+                rust_type_to_nixos(inner_type)
+            } else {
+                quote! { "types.attrs" }
+            }
+        }
 
         // Container types
         //
@@ -35,7 +191,7 @@ fn path_to_nixos_type(path: &Path) -> TokenStream {
         // Compound inner expressions (containing a space) must be
         // wrapped in parentheses:
         //   types.nullOr (types.listOf types.str)
-        "Vec" => {
+        "Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" => {
             if let Some(inner_type) = get_generic_inner_type(path) {
                 let inner_nixos = rust_type_to_nixos(inner_type);
                 quote! {
@@ -72,16 +228,7 @@ fn path_to_nixos_type(path: &Path) -> TokenStream {
         "HashMap" | "BTreeMap" => {
             if let Some(value_type) = get_map_value_type(path) {
                 let value_nixos = rust_type_to_nixos(value_type);
-                quote! {
-                    {
-                        let inner = #value_nixos;
-                        if inner.contains(' ') {
-                            format!("types.attrsOf ({})", inner)
-                        } else {
-                            format!("types.attrsOf {}", inner)
-                        }
-                    }
-                }
+                map_to_nixos_type(get_generic_inner_type(path), value_nixos)
             } else {
                 quote! { "types.attrsOf types.attrs".to_string() }
             }
@@ -113,13 +260,16 @@ fn path_to_nixos_type(path: &Path) -> TokenStream {
 }
 
 /// Extract the inner type from a generic type like `Vec<T>` or `Option<T>`
+///
+/// Lifetime arguments are skipped, so `Cow<'a, str>` yields `str`.
 pub fn get_generic_inner_type(path: &Path) -> Option<&Type> {
     let last_segment = path.segments.last()?;
 
     if let PathArguments::AngleBracketed(args) = &last_segment.arguments {
-        if let Some(GenericArgument::Type(ty)) = args.args.first() {
-            return Some(ty);
-        }
+        return args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        });
     }
 
     None
@@ -142,6 +292,112 @@ pub fn get_map_value_type(path: &Path) -> Option<&Type> {
     None
 }
 
+/// Build the NixOS type for a map from its key type and value type expression.
+///
+/// Nix attribute names are always strings, so the key type only refines the
+/// plain `types.attrsOf`:
+///   - integer keys add a check that every attribute name parses as that integer type
+///   - keys of a unit-only enum become a submodule with one nullable option per variant
+///   - other custom keys (e.g. newtypes) reuse the key type's `nixos_key_check()`
+pub fn map_to_nixos_type(key_type: Option<&Type>, value_nixos: TokenStream) -> TokenStream {
+    let key_check = key_type
+        .map(map_key_check)
+        .unwrap_or_else(|| quote! { None });
+
+    let enum_variants = match key_type.filter(|ty| get_custom_type_name(ty).is_some()) {
+        Some(key_type) => quote! { <#key_type>::nixos_enum_variants() },
+        None => quote! { None },
+    };
+
+    quote! {
+        {
+            let inner = #value_nixos;
+            let value = if inner.contains(' ') {
+                format!("({})", inner)
+            } else {
+                inner.to_string()
+            };
+            let enum_variants: Option<&[&str]> = #enum_variants;
+            let key_check: Option<String> = #key_check;
+            if let Some(variants) = enum_variants {
+                let options: Vec<String> = variants
+                    .iter()
+                    .map(|variant| {
+                        format!(
+                            "\"{}\" = lib.mkOption {{ type = types.nullOr {}; default = null; }};",
+                            variant, value
+                        )
+                    })
+                    .collect();
+                format!("types.submodule {{ options = {{ {} }}; }}", options.join(" "))
+            } else if let Some(check) = key_check {
+                format!(
+                    "types.addCheck (types.attrsOf {}) (attrs: lib.all ({}) (builtins.attrNames attrs))",
+                    value, check
+                )
+            } else {
+                format!("types.attrsOf {}", value)
+            }
+        }
+    }
+}
+
+/// Nix predicate that attribute names must satisfy when `ty` is a map key.
+///
+/// Expands to an `Option<String>` expression; `None` means any string is a
+/// valid key.
+pub fn map_key_check(ty: &Type) -> TokenStream {
+    if let Type::Path(type_path) = ty {
+        let type_name = type_path
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_default();
+
+        if let Some(check) = integer_key_check(&type_name) {
+            return quote! { Some(#check.to_string()) };
+        }
+        if type_name == "bool" {
+            return quote! { Some("name: builtins.elem name [ \"true\" \"false\" ]".to_string()) };
+        }
+        if get_custom_type_name(ty).is_some() {
+            return quote! { <#ty>::nixos_key_check() };
+        }
+    }
+
+    quote! { None }
+}
+
+/// Nix predicate checking that an attribute name is a valid integer of the
+/// given Rust integer type. Range checks are only emitted where the bounds
+/// fit in a Nix integer.
+fn integer_key_check(type_name: &str) -> Option<String> {
+    const UNSIGNED: &str = "builtins.match \"0|[1-9][0-9]*\" name != null";
+    const SIGNED: &str = "builtins.match \"0|-?[1-9][0-9]*\" name != null";
+
+    let check = match type_name {
+        "u8" => format!("name: {} && builtins.fromJSON name <= 255", UNSIGNED),
+        "u16" => format!("name: {} && builtins.fromJSON name <= 65535", UNSIGNED),
+        "u32" => format!("name: {} && builtins.fromJSON name <= 4294967295", UNSIGNED),
+        "u64" | "u128" | "usize" => format!("name: {}", UNSIGNED),
+        "i8" => signed_range_check(SIGNED, -128, 127),
+        "i16" => signed_range_check(SIGNED, -32768, 32767),
+        "i32" => signed_range_check(SIGNED, -2147483648, 2147483647),
+        "i64" | "i128" | "isize" => format!("name: {}", SIGNED),
+        _ => return None,
+    };
+
+    Some(check)
+}
+
+fn signed_range_check(pattern: &str, min: i64, max: i64) -> String {
+    format!(
+        "name: {} && (let n = builtins.fromJSON name; in n >= {} && n <= {})",
+        pattern, min, max
+    )
+}
+
 /// Generate a NixOS type expression for an enum
 #[allow(dead_code)]
 pub fn enum_to_nixos_type(variants: &[String]) -> TokenStream {
@@ -175,17 +431,38 @@ pub fn unwrap_option_type(ty: &Type) -> &Type {
     ty
 }
 
+/// Whether `path` names `std::time::Duration`, qualified or not
+fn is_std_duration(path: &Path) -> bool {
+    is_type(path, "std", &["Duration"]) || is_type(path, "core", &["Duration"])
+}
+
+/// Whether `path` names `serde_json::Value`, qualified or not
+fn is_json_value(path: &Path) -> bool {
+    is_type(path, "serde_json", &["Value"])
+}
+
 /// Get the custom type name if this is a custom struct/enum (not a built-in type)
 pub fn get_custom_type_name(ty: &Type) -> Option<String> {
     if let Type::Path(type_path) = ty {
         let type_name = type_path.path.segments.last()?.ident.to_string();
 
+        if ecosystem_type(&type_path.path).is_some()
+            || is_std_duration(&type_path.path)
+            || is_json_value(&type_path.path)
+        {
+            return None;
+        }
+
         // Check if it's a built-in type
-        match type_name.as_str() {
+        match collection_alias(&type_name) {
             "bool" | "String" | "str" | "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16"
-            | "i32" | "i64" | "i128" | "usize" | "isize" | "f32" | "f64" | "Vec" | "Option"
-            | "HashMap" | "BTreeMap" | "HashSet" | "BTreeSet" | "PathBuf" | "Path" | "Box"
-            | "Rc" | "Arc" | "Value" => None,
+            | "i32" | "i64" | "i128" | "usize" | "isize" | "f32" | "f64" | "char" | "Vec"
+            | "VecDeque" | "LinkedList" | "BinaryHeap" | "Option" | "HashMap" | "BTreeMap"
+            | "HashSet" | "BTreeSet" | "PathBuf" | "Path" | "OsString" | "OsStr" | "Box" | "Rc"
+            | "Arc" | "Cow" | "IpAddr" | "Ipv4Addr" | "Ipv6Addr" | "SocketAddr"
+            | "SocketAddrV4" | "SocketAddrV6" | "NonZeroU8" | "NonZeroU16" | "NonZeroU32"
+            | "NonZeroU64" | "NonZeroU128" | "NonZeroUsize" | "NonZeroI8" | "NonZeroI16"
+            | "NonZeroI32" | "NonZeroI64" | "NonZeroI128" | "NonZeroIsize" => None,
             _ => Some(type_name),
         }
     } else {
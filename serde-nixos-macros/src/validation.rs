@@ -0,0 +1,310 @@
+//! Rust-side checks matching the generated NixOS types.
+//!
+//! Every derived type gets a hidden `nixos_validate_into()` that walks its
+//! fields; `#[nixos(validate)]` adds the public `nixos_validate()` entry
+//! point. The checks mirror what the Nix type enforces but the Rust type
+//! does not: integers outside Nix's signed 64-bit range and the
+//! `#[nixos(min, max, pattern, non_empty, unique)]` constraints.
+
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::{Data, Fields, Path, Result, Type};
+
+use crate::adapters::{wire_type, WireType};
+use crate::attributes::{
+    apply_rename_rule_to_field, combine_attributes, parse_nixos_attributes, parse_serde_attributes,
+    Constraints, RenameRule,
+};
+use crate::constraints::{FLOAT_TYPES, INT_TYPES, STRING_TYPES};
+use crate::ecosystem::collection_alias;
+use crate::type_mapping::{
+    get_custom_type_name, get_generic_inner_type, get_map_value_type, unwrap_option_type,
+};
+
+/// Integer types that can hold values outside Nix's signed 64-bit integers
+const WIDE_INT_TYPES: &[&str] = &["u64", "u128", "usize", "i128"];
+
+/// Generate the body of `nixos_validate_into(&self, path, errors)`.
+pub fn generate_validate_body(data: &Data, rename_all: Option<RenameRule>) -> Result<TokenStream> {
+    let Data::Struct(data_struct) = data else {
+        return Ok(quote! {});
+    };
+    let Fields::Named(fields) = &data_struct.fields else {
+        return Ok(quote! {});
+    };
+
+    let mut field_checks = Vec::new();
+    for field in &fields.named {
+        let field_name = field.ident.as_ref().unwrap();
+        let nixos_attrs = parse_nixos_attributes(&field.attrs)?;
+        let serde_attrs = parse_serde_attributes(&field.attrs)?;
+        let mut effective_attrs = combine_attributes(nixos_attrs, serde_attrs, None, false);
+        effective_attrs.fit_imported_rules(unwrap_option_type(&field.ty));
+        if effective_attrs.skip || effective_attrs.type_override.is_some() {
+            continue;
+        }
+        // An adapter changes the wire format, so the Rust value says little
+        // about what Nix sees.
+        let wire = wire_type(
+            &effective_attrs,
+            unwrap_option_type(&field.ty),
+            &|_| quote! {},
+        );
+        if !matches!(wire, WireType::Native) {
+            continue;
+        }
+
+        let checks = value_checks(&field.ty, &effective_attrs.constraints);
+        if checks.is_empty() {
+            continue;
+        }
+
+        let nix_field_name = effective_attrs.name.unwrap_or_else(|| match rename_all {
+            Some(rule) => apply_rename_rule_to_field(&field_name.to_string(), rule),
+            None => field_name.to_string(),
+        });
+        let field_path = if effective_attrs.path {
+            // A nested `#[nixos(path)]` adds one attribute per segment
+            let segments = nix_field_name.split('.');
+            quote! {
+                {
+                    let mut field_path = path.to_string();
+                    #(field_path = ::serde_nixos::validation::join_path(&field_path, #segments);)*
+                    field_path
+                }
+            }
+        } else {
+            quote! { ::serde_nixos::validation::join_path(path, #nix_field_name) }
+        };
+        field_checks.push(quote! {
+            {
+                let path = #field_path;
+                let value = &self.#field_name;
+                #checks
+            }
+        });
+    }
+
+    Ok(quote! { #(#field_checks)* })
+}
+
+/// Checks for `value: &ty`, with `path: String` and `errors` in scope.
+fn value_checks(ty: &Type, constraints: &Constraints) -> TokenStream {
+    let path = type_path(ty);
+    let name = path
+        .and_then(|path| path.segments.last())
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+    let inner = path.and_then(get_generic_inner_type);
+
+    match (collection_alias(&name), inner) {
+        ("Option", Some(inner)) => {
+            let checks = value_checks(inner, constraints);
+            if checks.is_empty() {
+                return checks;
+            }
+            quote! {
+                if let Some(value) = value {
+                    #checks
+                }
+            }
+        }
+        ("Box" | "Rc" | "Arc", Some(inner)) => {
+            let checks = value_checks(inner, constraints);
+            if checks.is_empty() {
+                return checks;
+            }
+            quote! {
+                {
+                    let value = &**value;
+                    #checks
+                }
+            }
+        }
+        (
+            kind @ ("Vec" | "VecDeque" | "LinkedList" | "BinaryHeap" | "HashSet" | "BTreeSet"),
+            Some(element),
+        ) => {
+            let mut checks = collection_checks(constraints);
+            if constraints.unique && !matches!(kind, "HashSet" | "BTreeSet") {
+                checks.extend(quote! {
+                    let items: Vec<_> = value.iter().collect();
+                    if items
+                        .iter()
+                        .enumerate()
+                        .any(|(index, item)| items[..index].contains(item))
+                    {
+                        errors.push(::serde_nixos::NixosValidationError::new(
+                            path.clone(),
+                            "items must be unique",
+                        ));
+                    }
+                });
+            }
+            let item_checks = value_checks(element, &item_constraints(constraints));
+            if !item_checks.is_empty() {
+                checks.extend(quote! {
+                    for (index, value) in value.iter().enumerate() {
+                        let path = format!("{}[{}]", path, index);
+                        #item_checks
+                    }
+                });
+            }
+            checks
+        }
+        ("HashMap" | "BTreeMap", Some(_)) => {
+            let mut checks = collection_checks(constraints);
+            let value_type = path.and_then(get_map_value_type);
+            let item_checks = value_type
+                .map(|value_type| value_checks(value_type, &item_constraints(constraints)))
+                .unwrap_or_default();
+            if !item_checks.is_empty() {
+                checks.extend(quote! {
+                    for (key, value) in value.iter() {
+                        let path = ::serde_nixos::validation::join_path(
+                            &path,
+                            &::serde_nixos::validation::map_key(key),
+                        );
+                        #item_checks
+                    }
+                });
+            }
+            checks
+        }
+        _ => scalar_checks(ty, &name, constraints),
+    }
+}
+
+/// `min`, `max` and `pattern` apply to the items of a collection.
+fn item_constraints(constraints: &Constraints) -> Constraints {
+    Constraints {
+        non_empty: false,
+        unique: false,
+        min_length: None,
+        max_length: None,
+        ..constraints.clone()
+    }
+}
+
+fn collection_checks(constraints: &Constraints) -> TokenStream {
+    let mut checks = TokenStream::new();
+    if constraints.non_empty {
+        checks.extend(quote! {
+            if value.is_empty() {
+                errors.push(::serde_nixos::NixosValidationError::new(
+                    path.clone(),
+                    "must not be empty",
+                ));
+            }
+        });
+    }
+    if let Some(min) = constraints.min_length {
+        checks.extend(quote! {
+            if (value.len() as u64) < #min {
+                errors.push(::serde_nixos::NixosValidationError::new(
+                    path.clone(),
+                    format!("length must be at least {}, got {}", #min, value.len()),
+                ));
+            }
+        });
+    }
+    if let Some(max) = constraints.max_length {
+        checks.extend(quote! {
+            if (value.len() as u64) > #max {
+                errors.push(::serde_nixos::NixosValidationError::new(
+                    path.clone(),
+                    format!("length must be at most {}, got {}", #max, value.len()),
+                ));
+            }
+        });
+    }
+    checks
+}
+
+fn scalar_checks(ty: &Type, name: &str, constraints: &Constraints) -> TokenStream {
+    if get_custom_type_name(ty).is_some() {
+        return quote! {
+            value.nixos_validate_into(&path, errors);
+        };
+    }
+
+    let mut checks = TokenStream::new();
+    let is_int = INT_TYPES.contains(&name);
+
+    if WIDE_INT_TYPES.contains(&name) {
+        checks.extend(quote! {
+            if i64::try_from(*value).is_err() {
+                errors.push(::serde_nixos::NixosValidationError::new(
+                    path.clone(),
+                    format!("{} does not fit in a Nix integer (signed 64-bit)", value),
+                ));
+            }
+        });
+    }
+
+    if is_int || FLOAT_TYPES.contains(&name) {
+        let bounds = [
+            (&constraints.min, "lt", "at least"),
+            (&constraints.max, "gt", "at most"),
+        ];
+        for (bound, comparison, wording) in bounds {
+            let Some(bound) = bound else {
+                continue;
+            };
+            // Compare integers exactly; anything involving floats as f64
+            let (value_expr, bound_expr) = match bound.parse::<i128>() {
+                Ok(int) if is_int => (
+                    quote! { (*value as i128) },
+                    Literal::i128_unsuffixed(int).into_token_stream(),
+                ),
+                _ => {
+                    let float = bound.parse::<f64>().unwrap_or_default();
+                    (
+                        quote! { (*value as f64) },
+                        Literal::f64_unsuffixed(float).into_token_stream(),
+                    )
+                }
+            };
+            let comparison = format_ident!("{}", comparison);
+            let message = format!("{} {}", wording, bound);
+            checks.extend(quote! {
+                if PartialOrd::#comparison(&#value_expr, &#bound_expr) {
+                    errors.push(::serde_nixos::NixosValidationError::new(
+                        path.clone(),
+                        format!("must be {}, got {}", #message, value),
+                    ));
+                }
+            });
+        }
+    }
+
+    if STRING_TYPES.contains(&name) {
+        let as_str = if matches!(name, "OsString" | "OsStr") {
+            quote! { &*value.to_string_lossy() }
+        } else {
+            quote! { AsRef::<str>::as_ref(value) }
+        };
+        if let Some(pattern) = &constraints.pattern {
+            let message = format!("must match `{}`", pattern);
+            checks.extend(quote! {
+                if !::serde_nixos::validation::matches_pattern(#as_str, #pattern) {
+                    errors.push(::serde_nixos::NixosValidationError::new(
+                        path.clone(),
+                        #message,
+                    ));
+                }
+            });
+        }
+        checks.extend(collection_checks(constraints));
+    }
+
+    checks
+}
+
+fn type_path(ty: &Type) -> Option<&Path> {
+    match ty {
+        Type::Path(type_path) => Some(&type_path.path),
+        Type::Reference(reference) => type_path(&reference.elem),
+        _ => None,
+    }
+}
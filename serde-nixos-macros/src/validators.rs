@@ -0,0 +1,348 @@
+//! Constraints from the `validator` and `garde` crates' field attributes.
+//!
+//! Rules with a NixOS equivalent (`range`, `length`, `email`, `url`, IP
+//! addresses, literal `contains` / `prefix` / `suffix`, simple patterns) are
+//! translated into [`Constraints`]; the rest are kept as text so the option
+//! description can mention them. Each rule is translated completely or not
+//! at all.
+
+use quote::ToTokens;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, Lit, Meta, Token, UnOp};
+
+use crate::attributes::Constraints;
+use crate::patterns::check_pattern;
+use crate::type_mapping::{IPV4_PATTERN, IPV6_PATTERN};
+
+/// Loose email check: something, `@`, something, without whitespace
+const EMAIL_PATTERN: &str = "[^@[:space:]]+@[^@[:space:]]+";
+/// Loose URL check: a scheme followed by anything without whitespace
+const URL_PATTERN: &str = "[a-zA-Z][a-zA-Z0-9+.-]*:[^[:space:]]+";
+
+/// Rules read from `#[validate(...)]` and `#[garde(...)]`.
+#[derive(Debug, Default, Clone)]
+pub struct ImportedRules {
+    /// Constraints translated from the rules
+    pub constraints: Constraints,
+    /// The translated rules as written
+    pub translated: Vec<String>,
+    /// Rules without a NixOS equivalent, as written
+    pub untranslated: Vec<String>,
+    /// The translated `validator` `length` bounds, as written: they count
+    /// the characters of a string, not its bytes
+    pub char_length: Option<String>,
+}
+
+impl ImportedRules {
+    /// All rules as written, for when the constraints can't be used.
+    pub fn all_rules(&self) -> Vec<String> {
+        self.translated
+            .iter()
+            .chain(&self.untranslated)
+            .cloned()
+            .collect()
+    }
+
+    /// Leave the `validator` `length` bounds untranslated, for a string
+    /// field. `builtins.stringLength` counts bytes, so they can't be checked
+    /// in Nix.
+    pub fn untranslate_char_length(&mut self) -> bool {
+        let Some(rule) = self.char_length.take() else {
+            return false;
+        };
+        self.constraints.min_length = None;
+        self.constraints.max_length = None;
+        self.translated.retain(|translated| *translated != rule);
+        self.untranslated.push(rule);
+        true
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Dialect {
+    Validator,
+    Garde,
+}
+
+/// Read the `validator` and `garde` rules of a field.
+pub fn parse_validator_attributes(attrs: &[Attribute]) -> syn::Result<ImportedRules> {
+    let mut rules = ImportedRules::default();
+
+    for attr in attrs {
+        let dialect = if attr.path().is_ident("validate") {
+            Dialect::Validator
+        } else if attr.path().is_ident("garde") {
+            Dialect::Garde
+        } else {
+            continue;
+        };
+        let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        for meta in metas {
+            let name = meta
+                .path()
+                .get_ident()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            // These only control how nested values are validated
+            if matches!(
+                (dialect, name.as_str()),
+                (Dialect::Validator, "nested") | (Dialect::Garde, "dive" | "skip")
+            ) {
+                continue;
+            }
+            let text = rule_text(&meta);
+            let lengths = (rules.constraints.min_length, rules.constraints.max_length);
+            if translate(dialect, &name, &meta, &mut rules.constraints) {
+                if dialect == Dialect::Validator
+                    && lengths != (rules.constraints.min_length, rules.constraints.max_length)
+                {
+                    rules.char_length = Some(text.clone());
+                }
+                rules.translated.push(text);
+            } else {
+                rules.untranslated.push(text);
+            }
+        }
+    }
+
+    Ok(rules)
+}
+
+/// Add the constraints of one rule, returning whether it could be translated.
+fn translate(dialect: Dialect, name: &str, meta: &Meta, constraints: &mut Constraints) -> bool {
+    let mut translated = constraints.clone();
+    let ok = match (dialect, name, meta) {
+        (_, "range", Meta::List(_)) => translate_range(meta, &mut translated),
+        (_, "length", Meta::List(_)) => translate_length(dialect, meta, &mut translated),
+        (_, "email", Meta::Path(_)) => set_pattern(&mut translated, EMAIL_PATTERN.to_string()),
+        (_, "url", Meta::Path(_)) => set_pattern(&mut translated, URL_PATTERN.to_string()),
+        (_, "ip", Meta::Path(_)) => set_pattern(
+            &mut translated,
+            format!("{}|{}", IPV4_PATTERN, IPV6_PATTERN),
+        ),
+        (Dialect::Garde, "ipv4", Meta::Path(_)) => {
+            set_pattern(&mut translated, IPV4_PATTERN.to_string())
+        }
+        (Dialect::Garde, "ipv6", Meta::Path(_)) => {
+            set_pattern(&mut translated, IPV6_PATTERN.to_string())
+        }
+        (Dialect::Validator, "ip", Meta::List(list)) => {
+            match list.parse_args::<syn::Ident>().map(|v| v.to_string()) {
+                Ok(v) if v == "v4" => set_pattern(&mut translated, IPV4_PATTERN.to_string()),
+                Ok(v) if v == "v6" => set_pattern(&mut translated, IPV6_PATTERN.to_string()),
+                _ => false,
+            }
+        }
+        (Dialect::Validator, "non_control_character", Meta::Path(_)) => {
+            set_pattern(&mut translated, "[^[:cntrl:]]*".to_string())
+        }
+        (Dialect::Validator, "contains", Meta::List(_)) => {
+            match args(meta).and_then(|args| string_arg(&args, "pattern")) {
+                Some(needle) => literal_pattern(&needle)
+                    .is_some_and(|p| set_pattern(&mut translated, format!(".*{}.*", p))),
+                None => false,
+            }
+        }
+        (Dialect::Garde, "contains" | "prefix" | "suffix", Meta::List(list)) => {
+            match list.parse_args::<syn::LitStr>() {
+                Ok(needle) => literal_pattern(&needle.value()).is_some_and(|p| {
+                    let pattern = match name {
+                        "contains" => format!(".*{}.*", p),
+                        "prefix" => format!("{}.*", p),
+                        _ => format!(".*{}", p),
+                    };
+                    set_pattern(&mut translated, pattern)
+                }),
+                Err(_) => false,
+            }
+        }
+        (Dialect::Garde, "pattern", Meta::List(list)) => {
+            match list
+                .parse_args::<syn::LitStr>()
+                .ok()
+                .and_then(|p| search_pattern(&p.value()))
+                .filter(|pattern| check_pattern(pattern).is_ok())
+            {
+                Some(pattern) => set_pattern(&mut translated, pattern),
+                None => false,
+            }
+        }
+        _ => false,
+    };
+
+    if ok {
+        *constraints = translated;
+    }
+    ok
+}
+
+/// `range(min = A, max = B)`; exclusive bounds have no NixOS equivalent.
+fn translate_range(meta: &Meta, constraints: &mut Constraints) -> bool {
+    let Some(args) = args(meta) else {
+        return false;
+    };
+    let mut any = false;
+    for arg in &args {
+        match arg {
+            Meta::NameValue(nv) if nv.path.is_ident("min") || nv.path.is_ident("max") => {
+                let Some(number) = number(&nv.value) else {
+                    return false;
+                };
+                if nv.path.is_ident("min") {
+                    constraints.min = Some(number);
+                } else {
+                    constraints.max = Some(number);
+                }
+                any = true;
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("message") || nv.path.is_ident("code") => {}
+            _ => return false,
+        }
+    }
+    any
+}
+
+/// `length(min = A, max = B)` or `length(equal = N)`.
+///
+/// Nix counts the bytes of a string and the items of a collection. garde's
+/// `simple` and `bytes` modes do the same; `validator` counts the characters
+/// of a string, so the caller drops its bounds on string fields (see
+/// [`ImportedRules::untranslate_char_length`]). A minimum of 1 is the same
+/// in all cases and becomes `non_empty`.
+fn translate_length(dialect: Dialect, meta: &Meta, constraints: &mut Constraints) -> bool {
+    let Some(args) = args(meta) else {
+        return false;
+    };
+    let (mut min, mut max) = (None, None);
+    for arg in &args {
+        match arg {
+            Meta::NameValue(nv) if ["min", "max", "equal"].iter().any(|k| nv.path.is_ident(k)) => {
+                let Some(Ok(number)) = number(&nv.value).map(|n| n.parse::<u64>()) else {
+                    return false;
+                };
+                if !nv.path.is_ident("max") {
+                    min = Some(number);
+                }
+                if !nv.path.is_ident("min") {
+                    max = Some(number);
+                }
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("message") || nv.path.is_ident("code") => {}
+            // garde counts bytes in these modes, like `builtins.stringLength`
+            Meta::Path(mode)
+                if dialect == Dialect::Garde
+                    && (mode.is_ident("simple") || mode.is_ident("bytes")) => {}
+            _ => return false,
+        }
+    }
+
+    match (min, max) {
+        (None, None) => return false,
+        (Some(1), None) => constraints.non_empty = true,
+        _ => {
+            constraints.min_length = min;
+            constraints.max_length = max;
+        }
+    }
+    true
+}
+
+/// Only one pattern can be used; later ones stay untranslated.
+fn set_pattern(constraints: &mut Constraints, pattern: String) -> bool {
+    if constraints.pattern.is_some() {
+        return false;
+    }
+    constraints.pattern = Some(pattern);
+    true
+}
+
+/// A regex matching `s` literally, if it can be written without backslashes.
+fn literal_pattern(s: &str) -> Option<String> {
+    let mut pattern = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' | '[' | ']' | '^' => return None,
+            '.' | '*' | '+' | '?' | '(' | ')' | '{' | '}' | '|' | '$' => {
+                pattern.push('[');
+                pattern.push(c);
+                pattern.push(']');
+            }
+            _ => pattern.push(c),
+        }
+    }
+    Some(pattern)
+}
+
+/// Turn a garde (search) regex into a whole-string POSIX one.
+///
+/// Patterns using backslashes or `(?` groups read differently as POSIX
+/// regexes, and anchors are only handled around the whole pattern. The
+/// caller also leaves out results `check_pattern` rejects.
+fn search_pattern(pattern: &str) -> Option<String> {
+    if pattern.contains('\\') || pattern.contains("(?") {
+        return None;
+    }
+    let start = pattern.strip_prefix('^');
+    let body = start.unwrap_or(pattern);
+    let end = body.strip_suffix('$');
+    let body = end.unwrap_or(body);
+    if body.contains(['^', '$']) || ((start.is_some() || end.is_some()) && body.contains('|')) {
+        return None;
+    }
+    let prefix = if start.is_some() { "" } else { ".*" };
+    let suffix = if end.is_some() { "" } else { ".*" };
+    if prefix.is_empty() && suffix.is_empty() {
+        Some(body.to_string())
+    } else {
+        Some(format!("{}({}){}", prefix, body, suffix))
+    }
+}
+
+fn args(meta: &Meta) -> Option<Punctuated<Meta, Token![,]>> {
+    match meta {
+        Meta::List(list) => list
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .ok(),
+        _ => None,
+    }
+}
+
+fn string_arg(args: &Punctuated<Meta, Token![,]>, name: &str) -> Option<String> {
+    args.iter().find_map(|arg| match arg {
+        Meta::NameValue(nv) if nv.path.is_ident(name) => match &nv.value {
+            Expr::Lit(expr) => match &expr.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// A numeric literal such as `1`, `-5` or `0.5`; constants can't be read.
+fn number(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(expr) => match &expr.lit {
+            Lit::Int(int) => Some(int.base10_digits().to_string()),
+            Lit::Float(float) => Some(float.base10_digits().to_string()),
+            _ => None,
+        },
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => {
+            number(&unary.expr).map(|n| format!("-{}", n))
+        }
+        Expr::Group(group) => number(&group.expr),
+        Expr::Paren(paren) => number(&paren.expr),
+        _ => None,
+    }
+}
+
+/// The rule roughly as written, e.g. `custom(function = "check")`.
+fn rule_text(meta: &Meta) -> String {
+    meta.to_token_stream()
+        .to_string()
+        .replace(" (", "(")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(" ,", ",")
+}
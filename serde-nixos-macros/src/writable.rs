@@ -0,0 +1,104 @@
+//! `#[nixos(writable)]` fields.
+//!
+//! A writable field holds a path the service writes to. The generated
+//! service module makes it writable despite the hardening profile, through
+//! `StateDirectory=` for paths below `/var/lib` and `ReadWritePaths=` for
+//! the others.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, Fields, Result, Type};
+
+use crate::adapters::{wire_type, WireType};
+use crate::attributes::{
+    apply_rename_rule_to_field, combine_attributes, parse_nixos_attributes, parse_serde_attributes,
+    RenameRule,
+};
+use crate::type_mapping::{get_custom_type_name, is_optional_type, unwrap_option_type};
+
+/// Generate the body of `nixos_writable_paths_at(prefix)`, listing the
+/// option paths of the writable fields.
+///
+/// Writable fields of nested struct fields are included; those inside
+/// collections or `Option`s are not, as their options don't have a single
+/// path.
+pub fn generate_writable_paths(data: &Data, rename_all: Option<RenameRule>) -> Result<TokenStream> {
+    let fields = match data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields) => fields,
+            _ => return Ok(quote! { Vec::new() }),
+        },
+        _ => return Ok(quote! { Vec::new() }),
+    };
+
+    let mut paths = Vec::new();
+    for field in &fields.named {
+        let field_name = field.ident.as_ref().unwrap();
+        let attrs = combine_attributes(
+            parse_nixos_attributes(&field.attrs)?,
+            parse_serde_attributes(&field.attrs)?,
+            None,
+            false,
+        );
+        let option_name = attrs.name.clone().unwrap_or_else(|| match rename_all {
+            Some(rule) => apply_rename_rule_to_field(&field_name.to_string(), rule),
+            None => field_name.to_string(),
+        });
+
+        if attrs.writable {
+            let invalid = if attrs.skip || attrs.flatten {
+                Some("skipped and flattened fields have no option to make writable")
+            } else if attrs.secret {
+                Some("secret fields hold a value, not a writable path")
+            } else if !is_path_type(unwrap_option_type(&field.ty)) {
+                Some("`writable` applies to paths: `PathBuf`, `String` or an `Option` of them")
+            } else {
+                None
+            };
+            if let Some(message) = invalid {
+                return Err(syn::Error::new_spanned(field, message));
+            }
+            paths.push(quote! {
+                paths.push(::serde_nixos::utils::option_path(prefix, #option_name));
+            });
+            continue;
+        }
+        if attrs.skip || attrs.type_override.is_some() {
+            continue;
+        }
+
+        let nested = !is_optional_type(&field.ty)
+            && matches!(
+                wire_type(&attrs, &field.ty, &|_| quote! {}),
+                WireType::Native
+            )
+            && get_custom_type_name(&field.ty).is_some();
+        if nested {
+            let field_type = &field.ty;
+            paths.push(quote! {
+                paths.extend(<#field_type>::nixos_writable_paths_at(
+                    &::serde_nixos::utils::option_path(prefix, #option_name),
+                ));
+            });
+        }
+    }
+
+    Ok(quote! {
+        let mut paths: Vec<String> = Vec::new();
+        #(#paths)*
+        paths
+    })
+}
+
+/// Whether the type holds a filesystem path
+fn is_path_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last().is_some_and(|segment| {
+            matches!(
+                segment.ident.to_string().as_str(),
+                "PathBuf" | "String" | "OsString"
+            )
+        }),
+        _ => false,
+    }
+}
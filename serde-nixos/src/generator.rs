@@ -21,6 +21,7 @@ pub struct NixosModuleBuilder {
     options: Vec<NixosOption>,
     imports: Vec<String>,
     config_lines: Vec<String>,
+    assertions: Vec<String>,
 }
 
 impl NixosModuleBuilder {
@@ -31,6 +32,7 @@ pub fn new(module_name: impl Into<String>) -> Self {
             options: Vec::new(),
             imports: Vec::new(),
             config_lines: Vec::new(),
+            assertions: Vec::new(),
         }
     }
 
@@ -52,6 +54,13 @@ pub fn add_config_line(&mut self, line: impl Into<String>) -> &mut Self {
         self
     }
 
+    /// Add a list of assertions (e.g. from `T::nixos_assertions(path)`)
+    /// to the module's `config.assertions`
+    pub fn add_assertions(&mut self, assertions: impl Into<String>) -> &mut Self {
+        self.assertions.push(assertions.into());
+        self
+    }
+
     /// Build the complete NixOS module
     pub fn build(&self) -> String {
         let mut result = String::new();
@@ -81,7 +90,7 @@ pub fn build(&self) -> String {
         writeln!(result, "  }};").unwrap();
 
         // Config
-        if !self.config_lines.is_empty() {
+        if !self.config_lines.is_empty() || !self.assertions.is_empty() {
             writeln!(result).unwrap();
             writeln!(
                 result,
@@ -89,6 +98,10 @@ pub fn build(&self) -> String {
                 self.module_name
             )
             .unwrap();
+            if !self.assertions.is_empty() {
+                let assertions = self.assertions.join(" ++ ").replace('\n', "\n    ");
+                writeln!(result, "    assertions = {};", assertions).unwrap();
+            }
             for line in &self.config_lines {
                 writeln!(result, "    {}", line).unwrap();
             }
@@ -209,6 +222,23 @@ pub struct TypeRegistration {
     /// For named structs this is typically the type name itself
     /// (e.g. `"agentDefinitionType"`).
     pub type_expr: String,
+
+    /// The type's description from `T::nixos_description()`, written as
+    /// a comment above its `let` binding.
+    pub description: Option<&'static str>,
+
+    /// The name the settings function of this type is bound to, from
+    /// `T::nixos_to_settings_name()` (e.g. `"databaseConfigToSettings"`).
+    pub to_settings_name: &'static str,
+
+    /// The Nix function converting option values of this type into its
+    /// serde shape, referring to those of other types by their
+    /// `to_settings_name`. `None` leaves values as they are.
+    pub to_settings: Option<String>,
+
+    /// Whether the options use the submodule's attribute `name`
+    /// (`#[nixos(default_from_name)]`), from `T::nixos_takes_name()`.
+    pub takes_name: bool,
 }
 
 /// What to export from the generated `in { ... }` block.
@@ -386,7 +416,6 @@ pub fn generate(&self) -> String {
         let w = self.indent_width;
         let i1 = " ".repeat(w); // 1 level
         let i2 = " ".repeat(w * 2); // 2 levels
-        let i3 = " ".repeat(w * 3); // 3 levels
 
         let mut out = String::new();
 
@@ -421,34 +450,7 @@ pub fn generate(&self) -> String {
             writeln!(out, "let").unwrap();
 
             // Type definitions
-            for reg in &self.types {
-                if reg.type_expr.trim() == reg.type_name {
-                    // Struct-like registrations reference themselves as a named
-                    // type and need the submodule body.  Use `options_named` so
-                    // that nested custom types reference other let-bound names
-                    // instead of emitting inline `types.submodule { ... }`.
-                    writeln!(out, "{}{} = types.submodule {{", i1, reg.type_name).unwrap();
-                    writeln!(out, "{}options = {{", i2).unwrap();
-
-                    // Indent each options line by 3 levels
-                    for line in reg.options_named.lines() {
-                        if line.trim().is_empty() {
-                            writeln!(out).unwrap();
-                        } else {
-                            writeln!(out, "{}{}", i3, line.trim_start()).unwrap();
-                        }
-                    }
-
-                    writeln!(out, "{}}};", i2).unwrap();
-                    writeln!(out, "{}}};", i1).unwrap();
-                    writeln!(out).unwrap();
-                } else {
-                    // Enum-like registrations already have a complete type
-                    // expression (e.g. `types.enum [ ... ]`).
-                    writeln!(out, "{}{} = {};", i1, reg.type_name, reg.type_expr.trim()).unwrap();
-                    writeln!(out).unwrap();
-                }
-            }
+            write_type_bindings(&mut out, &self.types, w);
 
             // Extra let bindings
             for binding in &self.extra_let_bindings {
@@ -491,13 +493,7 @@ pub fn generate(&self) -> String {
             if let Export::Custom { name, body } = export {
                 writeln!(out).unwrap();
                 writeln!(out, "{}{} = {{", i1, name).unwrap();
-                for line in body.lines() {
-                    if line.trim().is_empty() {
-                        writeln!(out).unwrap();
-                    } else {
-                        writeln!(out, "{}{}", i2, line.trim_start()).unwrap();
-                    }
-                }
+                write_reindented(&mut out, body, &i2);
                 writeln!(out, "{}}};", i1).unwrap();
             }
         }
@@ -508,10 +504,91 @@ pub fn generate(&self) -> String {
     }
 }
 
+/// Write the `let` bindings of registered types, one level deep with
+/// `indent_width` spaces per level.
+pub(crate) fn write_type_bindings(
+    out: &mut String,
+    types: &[TypeRegistration],
+    indent_width: usize,
+) {
+    let i1 = " ".repeat(indent_width);
+    let i2 = " ".repeat(indent_width * 2);
+    let i3 = " ".repeat(indent_width * 3);
+    for reg in types {
+        if let Some(description) = reg.description {
+            out.push_str(&crate::utils::nix_comment(description, &i1));
+        }
+        if reg.type_expr.trim() == reg.type_name {
+            // Struct-like registrations reference themselves as a named
+            // type and need the submodule body.  Use `options_named` so
+            // that nested custom types reference other let-bound names
+            // instead of emitting inline `types.submodule { ... }`.
+            writeln!(
+                out,
+                "{}{} = {}",
+                i1,
+                reg.type_name,
+                crate::utils::submodule_open(reg.takes_name)
+            )
+            .unwrap();
+            writeln!(out, "{}options = {{", i2).unwrap();
+
+            // Indent the options by 3 levels
+            write_reindented(out, &reg.options_named, &i3);
+
+            writeln!(out, "{}}};", i2).unwrap();
+            writeln!(
+                out,
+                "{}{};",
+                i1,
+                crate::utils::submodule_close(reg.takes_name)
+            )
+            .unwrap();
+            writeln!(out).unwrap();
+        } else {
+            // Enum-like registrations already have a complete type
+            // expression (e.g. `types.enum [ ... ]`).
+            writeln!(out, "{}{} = {};", i1, reg.type_name, reg.type_expr.trim()).unwrap();
+            writeln!(out).unwrap();
+        }
+    }
+}
+
+/// Write `text` indented by `indent` in place of its common indentation.
+///
+/// Relative indentation is kept, which matters inside multi-line `''`
+/// strings such as descriptions with code blocks.
+pub(crate) fn write_reindented(out: &mut String, text: &str, indent: &str) {
+    let common = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            writeln!(out).unwrap();
+        } else {
+            writeln!(out, "{}{}", indent, &line[common..]).unwrap();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_module_builder_assertions() {
+        let mut builder = NixosModuleBuilder::new("services.app");
+        builder.add_assertions("[\n  {\n    assertion = true;\n    message = \"ok\";\n  }\n]");
+        let output = builder.build();
+
+        assert!(output.contains("  config = mkIf config.services.app.enable {\n"));
+        assert!(output.contains("    assertions = [\n      {\n        assertion = true;\n"));
+        assert!(output.contains("      }\n    ];\n  };\n"), "{}", output);
+    }
+
     #[test]
     fn test_module_generator_empty() {
         let gen = NixosModuleGenerator::new();
@@ -540,6 +617,10 @@ fn test_module_generator_with_type() {
             options: opts.clone(),
             options_named: opts,
             type_expr: "fooType".to_string(),
+            description: None,
+            to_settings_name: "fooToSettings",
+            to_settings: None,
+            takes_name: false,
         };
         let gen = NixosModuleGenerator::new()
             .register(reg)
@@ -556,6 +637,64 @@ fn test_module_generator_with_type() {
         assert!(output.contains("fooType"));
     }
 
+    #[test]
+    fn test_module_generator_keeps_relative_indentation() {
+        let opts = "    name = lib.mkOption {\n      description = ''\n        First\n\n          indented\n      '';\n    };\n"
+            .to_string();
+        let reg = TypeRegistration {
+            type_name: "fooType",
+            options: opts.clone(),
+            options_named: opts,
+            type_expr: "fooType".to_string(),
+            description: None,
+            to_settings_name: "fooToSettings",
+            to_settings: None,
+            takes_name: false,
+        };
+        let output = NixosModuleGenerator::new().register(reg).generate();
+
+        assert!(output.contains(
+            "      name = lib.mkOption {\n        description = ''\n          First\n\n            indented\n        '';\n      };\n"
+        ));
+    }
+
+    #[test]
+    fn test_module_generator_submodule_taking_name() {
+        let reg = TypeRegistration {
+            type_name: "workerType",
+            options: String::new(),
+            options_named: "    name = lib.mkOption { default = name; };\n".to_string(),
+            type_expr: "workerType".to_string(),
+            description: None,
+            to_settings_name: "workerToSettings",
+            to_settings: None,
+            takes_name: true,
+        };
+        let output = NixosModuleGenerator::new().register(reg).generate();
+
+        assert!(output.contains("  workerType = types.submodule ({ name, ... }: {\n"));
+        assert!(output.contains("      name = lib.mkOption { default = name; };\n    };\n  });\n"));
+    }
+
+    #[test]
+    fn test_module_generator_type_description_comment() {
+        let reg = TypeRegistration {
+            type_name: "dbType",
+            options: String::new(),
+            options_named: String::new(),
+            type_expr: "dbType".to_string(),
+            description: Some("Database connection.\n\nUsed by the API."),
+            to_settings_name: "dbToSettings",
+            to_settings: None,
+            takes_name: false,
+        };
+        let output = NixosModuleGenerator::new().register(reg).generate();
+
+        assert!(output.contains(
+            "  # Database connection.\n  #\n  # Used by the API.\n  dbType = types.submodule {"
+        ));
+    }
+
     #[test]
     fn test_module_generator_multiple_types() {
         let leaf_opts = "value = lib.mkOption {\n  type = types.str;\n};\n".to_string();
@@ -564,6 +703,10 @@ fn test_module_generator_multiple_types() {
             options: leaf_opts.clone(),
             options_named: leaf_opts,
             type_expr: "leafType".to_string(),
+            description: None,
+            to_settings_name: "leafToSettings",
+            to_settings: None,
+            takes_name: false,
         };
         let parent_opts = "child = lib.mkOption {\n  type = leafType;\n};\n".to_string();
         let parent = TypeRegistration {
@@ -571,6 +714,10 @@ fn test_module_generator_multiple_types() {
             options: parent_opts.clone(),
             options_named: parent_opts,
             type_expr: "parentType".to_string(),
+            description: None,
+            to_settings_name: "parentToSettings",
+            to_settings: None,
+            takes_name: false,
         };
         let gen = NixosModuleGenerator::new()
             .register(leaf)
@@ -593,6 +740,10 @@ fn test_module_generator_custom_export() {
             options: opts.clone(),
             options_named: opts,
             type_expr: "myType".to_string(),
+            description: None,
+            to_settings_name: "myToSettings",
+            to_settings: None,
+            takes_name: false,
         };
         let gen = NixosModuleGenerator::new()
             .register(reg.clone())
@@ -611,12 +762,20 @@ fn test_module_generator_export_all() {
             options: String::new(),
             options_named: String::new(),
             type_expr: "alphaType".to_string(),
+            description: None,
+            to_settings_name: "alphaToSettings",
+            to_settings: None,
+            takes_name: false,
         };
         let b = TypeRegistration {
             type_name: "betaType",
             options: String::new(),
             options_named: String::new(),
             type_expr: "betaType".to_string(),
+            description: None,
+            to_settings_name: "betaToSettings",
+            to_settings: None,
+            takes_name: false,
         };
         let gen = NixosModuleGenerator::new()
             .register(a)
@@ -636,6 +795,10 @@ fn test_module_generator_uses_type_expr_for_non_submodule_types() {
             options: String::new(),
             options_named: String::new(),
             type_expr: "types.enum [ \"fast\" \"safe\" ]".to_string(),
+            description: None,
+            to_settings_name: "modeToSettings",
+            to_settings: None,
+            takes_name: false,
         };
 
         let gen = NixosModuleGenerator::new()
@@ -88,6 +88,19 @@
 // Re-export key generator types for convenience
 pub use generator::{NixosModuleGenerator, TypeRegistration};
 
+/// Secrets passed to services as systemd credentials
+pub mod secrets;
+
+/// NixOS service modules generated from a settings type
+pub mod service;
+
+pub use service::{Hardening, PortProtocol, ServiceModule};
+
+/// Runtime checks matching the generated NixOS types
+pub mod validation;
+
+pub use validation::NixosValidationError;
+
 /// Create a [`TypeRegistration`] from a type that derives [`NixosType`].
 ///
 /// This macro captures the output of the derive-generated inherent methods
@@ -118,11 +131,18 @@ macro_rules! type_registration {
             options: <$ty>::nixos_options(),
             options_named: <$ty>::nixos_options_named(),
             type_expr: <$ty>::nixos_type(),
+            description: <$ty>::nixos_description(),
+            to_settings_name: <$ty>::nixos_to_settings_name(),
+            to_settings: Some(<$ty>::nixos_to_settings_function()),
+            takes_name: <$ty>::nixos_takes_name(),
         }
     };
 }
 
 /// Helper trait for types that can generate NixOS definitions
+///
+/// `#[derive(NixosType)]` implements it by forwarding to the generated
+/// inherent methods, so generic code such as [`ServiceModule`] can use them.
 pub trait NixosTypeGenerator {
     /// Generate a complete NixOS module definition
     fn nixos_type_definition() -> String;
@@ -132,11 +152,54 @@ pub trait NixosTypeGenerator {
 
     /// Get the NixOS type expression for this type
     fn nixos_type() -> String;
+
+    /// Generate the options using named type references, declared at
+    /// `option_prefix`
+    fn nixos_options_named_at(option_prefix: &str) -> String {
+        let _ = option_prefix;
+        Self::nixos_options()
+    }
+
+    /// Get the NixOS `assertions` list for the option at `option_path`
+    fn nixos_assertions(option_path: &str) -> String {
+        let _ = option_path;
+        "[ ]".to_string()
+    }
+
+    /// Get the credential names of the `#[nixos(secret)]` options, relative
+    /// to this type's value. The file option of each is `<name>File`.
+    fn nixos_secrets() -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Get the option paths of the `#[nixos(writable)]` fields, relative to
+    /// this type's value.
+    fn nixos_writable_paths() -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Get the ports of the `#[nixos(port, open_firewall)]` fields as Nix
+    /// list expressions by protocol, for the option value `value`.
+    fn nixos_firewall_ports(value: &str) -> Vec<(PortProtocol, String)> {
+        let _ = value;
+        Vec::new()
+    }
+
+    /// Register this type and the custom types its options refer to by
+    /// name, dependencies first.
+    #[doc(hidden)]
+    fn nixos_register_types(types: &mut Vec<TypeRegistration>) {
+        let _ = types;
+    }
 }
 
 /// Utility functions for working with NixOS types
 pub mod utils {
 
+    use serde::Serialize;
+
+    use crate::generator::TypeRegistration;
+
     /// Format a Rust value as a Nix expression
     pub fn format_nix_value(value: &serde_json::Value) -> String {
         match value {
@@ -151,22 +214,209 @@ pub fn format_nix_value(value: &serde_json::Value) -> String {
             serde_json::Value::Object(obj) => {
                 let attrs: Vec<String> = obj
                     .iter()
-                    .map(|(k, v)| format!("{} = {};", k, format_nix_value(v)))
+                    .map(|(k, v)| format!("{} = {};", nix_attr_name(k), format_nix_value(v)))
                     .collect();
                 format!("{{ {} }}", attrs.join(" "))
             }
         }
     }
 
+    /// Serialize a value with serde and format it as a Nix expression.
+    ///
+    /// Used for `#[nixos(default_value = "...")]`. Panics if the value
+    /// cannot be serialized, e.g. a map with non-string keys.
+    pub fn to_nix_value<T: Serialize + ?Sized>(value: &T) -> String {
+        match serde_json::to_value(value) {
+            Ok(value) => format_nix_value(&value),
+            Err(err) => panic!("cannot express value in Nix: {}", err),
+        }
+    }
+
+    /// Like [`to_nix_value`], for `#[nixos(example_value = "...")]`.
+    ///
+    /// Lists and attribute sets are wrapped in `lib.literalExpression` so
+    /// the manual shows them as written instead of re-rendering them.
+    pub fn to_nix_example<T: Serialize + ?Sized>(value: &T) -> String {
+        let nix = to_nix_value(value);
+        if nix.starts_with('[') || nix.starts_with('{') {
+            format!("lib.literalExpression \"{}\"", escape_nix_string(&nix))
+        } else {
+            nix
+        }
+    }
+
+    /// Quote an attribute name unless it is a valid Nix identifier.
+    pub(crate) fn nix_attr_name(name: &str) -> String {
+        let is_identifier = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\''));
+        if is_identifier {
+            name.to_string()
+        } else {
+            format!("\"{}\"", escape_nix_string(name))
+        }
+    }
+
     /// Escape a string for use in Nix expressions
     pub fn escape_nix_string(s: &str) -> String {
         s.replace('\\', "\\\\")
             .replace('"', "\\\"")
+            .replace("${", "\\${")
             .replace('\n', "\\n")
             .replace('\r', "\\r")
             .replace('\t', "\\t")
     }
 
+    /// Format `text` as a Nix string literal.
+    ///
+    /// Single lines become double-quoted strings. Multi-line text becomes an
+    /// indented `''` string whose lines are indented two spaces past
+    /// `indent`, which Nix strips again, so descriptions stay readable.
+    pub fn format_nix_string(text: &str, indent: &str) -> String {
+        if !text.contains('\n') {
+            return format!("\"{}\"", escape_nix_string(text));
+        }
+        let mut result = String::from("''\n");
+        for line in text.lines() {
+            if !line.trim().is_empty() {
+                result.push_str(indent);
+                result.push_str("  ");
+                result.push_str(&escape_indented_line(line));
+            }
+            result.push('\n');
+        }
+        result.push_str(indent);
+        result.push_str("''");
+        result
+    }
+
+    /// Escape a line of an indented `''` string in a single pass.
+    ///
+    /// `''` becomes `'''` and `${` becomes `''${`. A lone `'` right before
+    /// `${` is written as `''\'`, since `'''${` would read as an escaped
+    /// `''` followed by an interpolation.
+    fn escape_indented_line(line: &str) -> String {
+        let mut result = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with("''") {
+                result.push_str("'''");
+                rest = &rest[2..];
+            } else if rest.starts_with("'${") {
+                result.push_str("''\\'");
+                rest = &rest[1..];
+            } else if rest.starts_with("${") {
+                result.push_str("''${");
+                rest = &rest[2..];
+            } else {
+                result.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        result
+    }
+
+    /// The start of a submodule type, up to its opening brace. Submodules
+    /// whose options use the attribute `name` are written as a function.
+    pub fn submodule_open(takes_name: bool) -> &'static str {
+        if takes_name {
+            "types.submodule ({ name, ... }: {"
+        } else {
+            "types.submodule {"
+        }
+    }
+
+    /// The end of a submodule type started with [`submodule_open`].
+    pub fn submodule_close(takes_name: bool) -> &'static str {
+        if takes_name {
+            "})"
+        } else {
+            "}"
+        }
+    }
+
+    /// Append an option name to an option path, e.g. `storage.dataDir`.
+    pub fn option_path(prefix: &str, name: &str) -> String {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        }
+    }
+
+    /// Turn `text` into `#` comment lines, each prefixed with `indent`.
+    pub fn nix_comment(text: &str, indent: &str) -> String {
+        let mut result = String::new();
+        for line in text.lines() {
+            result.push_str(indent);
+            if line.trim().is_empty() {
+                result.push_str("#\n");
+            } else {
+                result.push_str("# ");
+                result.push_str(line.trim_end());
+                result.push('\n');
+            }
+        }
+        result
+    }
+
+    /// Write `let` bindings for the settings functions of `types`, each
+    /// line prefixed with `indent`.
+    pub fn to_settings_bindings(types: &[TypeRegistration], indent: &str) -> String {
+        let mut result = String::new();
+        for reg in types {
+            let function = reg.to_settings.as_deref().unwrap_or("v: v");
+            result.push_str(indent);
+            result.push_str(reg.to_settings_name);
+            result.push_str(" = ");
+            for (index, line) in function.lines().enumerate() {
+                if index > 0 {
+                    result.push('\n');
+                    result.push_str(indent);
+                }
+                result.push_str(line);
+            }
+            result.push_str(";\n");
+        }
+        result
+    }
+
+    /// Format the settings functions of `types` as one `let` expression
+    /// evaluating to the function of the last one.
+    pub fn format_to_settings(types: &[TypeRegistration]) -> String {
+        let Some(last) = types.last() else {
+            return "v: v".to_string();
+        };
+        format!(
+            "let\n{}in\n{}",
+            to_settings_bindings(types, "  "),
+            last.to_settings_name
+        )
+    }
+
+    /// Format `(assertion, message)` pairs as a NixOS `assertions` list.
+    pub fn format_assertions(assertions: &[(String, String)]) -> String {
+        if assertions.is_empty() {
+            return "[ ]".to_string();
+        }
+        let mut result = String::from("[\n");
+        for (assertion, message) in assertions {
+            result.push_str("  {\n");
+            result.push_str(&format!("    assertion = {};\n", assertion));
+            result.push_str(&format!(
+                "    message = \"{}\";\n",
+                escape_nix_string(message)
+            ));
+            result.push_str("  }\n");
+        }
+        result.push(']');
+        result
+    }
+
     /// Generate a NixOS module file with proper formatting
     pub fn generate_module_file(module_name: &str, options: &str, config: Option<&str>) -> String {
         let mut result = String::new();
@@ -215,6 +465,52 @@ fn test_nix_value_formatting() {
         );
     }
 
+    #[test]
+    fn test_format_assertions() {
+        assert_eq!(utils::format_assertions(&[]), "[ ]");
+        assert_eq!(
+            utils::format_assertions(&[("let self = cfg; in self.a <= self.b".into(), "a: \"too big\"".into())]),
+            "[\n  {\n    assertion = let self = cfg; in self.a <= self.b;\n    message = \"a: \\\"too big\\\"\";\n  }\n]"
+        );
+    }
+
+    #[test]
+    fn test_format_nix_string() {
+        assert_eq!(utils::format_nix_string("a \"b\"", "  "), "\"a \\\"b\\\"\"");
+        assert_eq!(
+            utils::format_nix_string("First ''x''.\n\n    ${code}", "  "),
+            "''\n    First '''x'''.\n\n        ''${code}\n  ''"
+        );
+        // `'''${` would be an escaped `''` followed by an interpolation
+        assert_eq!(
+            utils::format_nix_string("Quote '${x}\nand '''${y}", ""),
+            "''\n  Quote ''\\'''${x}\n  and '''''\\'''${y}\n''"
+        );
+    }
+
+    #[test]
+    fn test_nix_comment() {
+        assert_eq!(
+            utils::nix_comment("First line.\n\nSecond line.", "  "),
+            "  # First line.\n  #\n  # Second line.\n"
+        );
+    }
+
+    #[test]
+    fn test_to_nix_value() {
+        use std::collections::BTreeMap;
+
+        assert_eq!(utils::to_nix_value(&30u64), "30");
+        assert_eq!(utils::to_nix_value("${HOME}"), "\"\\${HOME}\"");
+        let map = BTreeMap::from([("a b", 1), ("c", 2)]);
+        assert_eq!(utils::to_nix_value(&map), "{ \"a b\" = 1; c = 2; }");
+        assert_eq!(utils::to_nix_example(&Some(1)), "1");
+        assert_eq!(
+            utils::to_nix_example(&vec!["a"]),
+            "lib.literalExpression \"[ \\\"a\\\" ]\""
+        );
+    }
+
     #[test]
     fn test_escape_nix_string() {
         assert_eq!(utils::escape_nix_string("hello"), "hello");
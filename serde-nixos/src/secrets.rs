@@ -0,0 +1,73 @@
+//! Secrets passed to services as systemd credentials
+//!
+//! Options of `#[nixos(secret)]` fields take the path of a file instead of
+//! the value, so the secret never ends up in the Nix store. `ServiceModule`
+//! passes each file with `LoadCredential=`, named after the option path, and
+//! the derived `nixos_load_secrets()` reads them back into the struct after
+//! the config file has been deserialized.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory systemd places the service's credentials in
+/// (`$CREDENTIALS_DIRECTORY`), if any.
+pub fn credentials_directory() -> Option<PathBuf> {
+    std::env::var_os("CREDENTIALS_DIRECTORY").map(PathBuf::from)
+}
+
+/// Append an option name to a credential name, e.g. `database.password`.
+///
+/// Credential names are the option paths of the secrets, built by
+/// [`option_path`](crate::utils::option_path).
+pub fn credential_name(prefix: &str, name: &str) -> String {
+    crate::utils::option_path(prefix, name)
+}
+
+/// Read the credential `name` from `dir`, without a trailing newline.
+///
+/// Returns `None` if the credential doesn't exist, i.e. its file option is
+/// unset.
+pub fn read_credential(dir: &Path, name: &str) -> io::Result<Option<String>> {
+    match std::fs::read_to_string(dir.join(name)) {
+        Ok(mut value) => {
+            if value.ends_with('\n') {
+                value.pop();
+                if value.ends_with('\r') {
+                    value.pop();
+                }
+            }
+            Ok(Some(value))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(io::Error::new(
+            err.kind(),
+            format!("reading credential `{}`: {}", name, err),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credential_name() {
+        assert_eq!(credential_name("", "password"), "password");
+        assert_eq!(credential_name("database", "password"), "database.password");
+    }
+
+    #[test]
+    fn test_read_credential() {
+        let dir =
+            std::env::temp_dir().join(format!("serde-nixos-credential-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("token"), "secret\r\n").unwrap();
+
+        assert_eq!(
+            read_credential(&dir, "token").unwrap().as_deref(),
+            Some("secret")
+        );
+        assert_eq!(read_credential(&dir, "missing").unwrap(), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,745 @@
+//! NixOS service modules generated from a settings type
+//!
+//! [`ServiceModule`] wraps the options of a `#[derive(NixosType)]` struct in
+//! a complete service module: `enable` and `package` options, the settings
+//! converted to the serde shape and written to a JSON config file, and a
+//! systemd unit running the program with that file and the files of secret
+//! options as credentials, sandboxed by a [`Hardening`] profile. Batch jobs
+//! can run on a systemd timer instead of at boot.
+
+use std::fmt::Write;
+use std::marker::PhantomData;
+
+use crate::generator::{write_reindented, write_type_bindings};
+use crate::utils::{escape_nix_string, to_settings_bindings};
+use crate::NixosTypeGenerator;
+
+/// A set of systemd sandboxing directives for the generated service.
+///
+/// Directives set with [`ServiceModule::service_config`] take precedence,
+/// e.g. `.service_config("CapabilityBoundingSet", "[ \"CAP_NET_BIND_SERVICE\" ]")`
+/// to bind a privileged port under [`Hardening::Strict`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Hardening {
+    /// No sandboxing; the service runs as root
+    #[default]
+    None,
+    /// Keep the service away from the system and home directories, devices
+    /// and kernel settings, without restricting its user or system calls
+    Basic,
+    /// Run the service as a dynamic user with a read-only filesystem, no
+    /// capabilities and the system calls of a typical service
+    Strict,
+}
+
+impl Hardening {
+    /// The `serviceConfig` attributes of the profile, as raw Nix values
+    fn directives(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Hardening::None => &[],
+            Hardening::Basic => &[
+                ("NoNewPrivileges", "true"),
+                ("PrivateTmp", "true"),
+                ("PrivateDevices", "true"),
+                ("ProtectSystem", "\"full\""),
+                ("ProtectHome", "true"),
+                ("ProtectKernelTunables", "true"),
+                ("ProtectKernelModules", "true"),
+                ("ProtectControlGroups", "true"),
+                ("RestrictSUIDSGID", "true"),
+            ],
+            Hardening::Strict => &[
+                ("DynamicUser", "true"),
+                ("NoNewPrivileges", "true"),
+                ("PrivateTmp", "true"),
+                ("PrivateDevices", "true"),
+                ("ProtectSystem", "\"strict\""),
+                ("ProtectHome", "true"),
+                ("ProtectProc", "\"invisible\""),
+                ("ProtectKernelTunables", "true"),
+                ("ProtectKernelModules", "true"),
+                ("ProtectKernelLogs", "true"),
+                ("ProtectControlGroups", "true"),
+                ("ProtectClock", "true"),
+                ("ProtectHostname", "true"),
+                ("RestrictNamespaces", "true"),
+                ("RestrictRealtime", "true"),
+                ("RestrictSUIDSGID", "true"),
+                (
+                    "RestrictAddressFamilies",
+                    "[ \"AF_UNIX\" \"AF_INET\" \"AF_INET6\" ]",
+                ),
+                ("LockPersonality", "true"),
+                ("MemoryDenyWriteExecute", "true"),
+                ("SystemCallArchitectures", "\"native\""),
+                (
+                    "SystemCallFilter",
+                    "[ \"@system-service\" \"~@privileged\" \"~@resources\" ]",
+                ),
+                ("CapabilityBoundingSet", "\"\""),
+                ("UMask", "\"0077\""),
+            ],
+        }
+    }
+}
+
+/// The protocol of a `#[nixos(port)]` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortProtocol {
+    Tcp,
+    Udp,
+}
+
+impl PortProtocol {
+    /// The `networking.firewall` option opening ports of this protocol
+    fn firewall_option(self) -> &'static str {
+        match self {
+            PortProtocol::Tcp => "allowedTCPPorts",
+            PortProtocol::Udp => "allowedUDPPorts",
+        }
+    }
+}
+
+/// Builder for a NixOS service module configured by the settings type `T`.
+///
+/// # Example
+///
+/// ```rust
+/// use serde::{Serialize, Deserialize};
+/// use serde_nixos::{NixosType, ServiceModule};
+///
+/// #[derive(Serialize, Deserialize, NixosType)]
+/// struct Settings {
+///     /// Port to listen on
+///     #[nixos(default = "8080")]
+///     port: u16,
+/// }
+///
+/// let module = ServiceModule::<Settings>::new("services.myapp")
+///     .description("My application")
+///     .args(["--config", "${configFile}"])
+///     .generate();
+///
+/// assert!(module.contains("options.services.myapp = {"));
+/// assert!(module.contains("systemd.services.myapp = {"));
+/// ```
+pub struct ServiceModule<T> {
+    path: String,
+    header: Option<String>,
+    description: Option<String>,
+    package: Option<String>,
+    binary: Option<String>,
+    args: Option<Vec<String>>,
+    config_path: Option<String>,
+    settings_option: Option<String>,
+    instances: bool,
+    timer: Option<String>,
+    hardening: Hardening,
+    service_config: Vec<(String, String)>,
+    settings: PhantomData<fn() -> T>,
+}
+
+impl<T: NixosTypeGenerator> ServiceModule<T> {
+    /// Create a module declaring its options at `path`
+    /// (e.g. `"services.myapp"`).
+    ///
+    /// The last segment of the path names the systemd unit, the config
+    /// file and, unless set otherwise, the package and its binary.
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            header: None,
+            description: None,
+            package: None,
+            binary: None,
+            args: None,
+            config_path: None,
+            settings_option: None,
+            instances: false,
+            timer: None,
+            hardening: Hardening::None,
+            service_config: Vec::new(),
+            settings: PhantomData,
+        }
+    }
+
+    /// Set the file header comment. Each line is prefixed with `# `.
+    pub fn header(mut self, comment: &str) -> Self {
+        self.header = Some(comment.to_string());
+        self
+    }
+
+    /// Describe the service, for `mkEnableOption` and the systemd unit.
+    /// Defaults to the service name.
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Set the Nix expression of the default package (default
+    /// `pkgs.<name>`).
+    pub fn package(mut self, expr: &str) -> Self {
+        self.package = Some(expr.to_string());
+        self
+    }
+
+    /// Set the binary in the package's `bin` directory to run (default:
+    /// the service name).
+    pub fn binary(mut self, binary: &str) -> Self {
+        self.binary = Some(binary.to_string());
+        self
+    }
+
+    /// Set the arguments passed to the binary.
+    ///
+    /// Each argument is the content of a Nix string, so `${configFile}`
+    /// refers to the generated config file and `${cfg.port}` to an option.
+    /// The arguments are passed verbatim, escaped with
+    /// `utils.escapeSystemdExecArgs`. Defaults to the path of the config
+    /// file.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = Some(args.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Install the config file at `path` instead of passing its Nix store
+    /// path. Paths below `/etc` use `environment.etc`, others a
+    /// `systemd.tmpfiles` symlink.
+    pub fn config_path(mut self, path: &str) -> Self {
+        self.config_path = Some(path.to_string());
+        self
+    }
+
+    /// Declare the settings as one option `name` of the settings type
+    /// (e.g. `services.myapp.settings`) instead of next to `enable`.
+    ///
+    /// Needed when the settings type has an `enable` or `package` field.
+    pub fn settings_option(mut self, name: &str) -> Self {
+        self.settings_option = Some(name.to_string());
+        self
+    }
+
+    /// Run several instances of the service, declared as
+    /// `services.myapp.instances.<instance>` with the settings type. Each
+    /// gets its own config file and systemd service `<name>-<instance>`.
+    ///
+    /// In [`args`](Self::args), `${instance}` refers to the instance name,
+    /// `${configFile}` to its config file and `${settings.port}` to its
+    /// options; [`config_path`](Self::config_path) may contain
+    /// `${instance}` too. Replaces [`settings_option`](Self::settings_option).
+    pub fn instances(mut self) -> Self {
+        self.instances = true;
+        self
+    }
+
+    /// Run the program periodically as a one-shot job started by a systemd
+    /// timer, instead of as a service started at boot.
+    ///
+    /// The module gets the options `startAt` (systemd calendar events,
+    /// defaulting to `on_calendar`, e.g. `"daily"`), `persistent` and
+    /// `randomizedDelaySec`. The service defaults to `Type = "oneshot"`.
+    pub fn timer(mut self, on_calendar: &str) -> Self {
+        self.timer = Some(on_calendar.to_string());
+        self
+    }
+
+    /// Sandbox the service with a hardening profile (default
+    /// [`Hardening::None`]).
+    ///
+    /// The paths of `#[nixos(writable)]` fields stay writable: paths below
+    /// `/var/lib` become a `StateDirectory`, created for the service and
+    /// owned by it, and other paths are added to `ReadWritePaths`.
+    pub fn hardening(mut self, hardening: Hardening) -> Self {
+        self.hardening = hardening;
+        self
+    }
+
+    /// Add a `serviceConfig` attribute with a raw Nix value
+    /// (e.g. `("Type", "\"oneshot\"")`).
+    pub fn service_config(mut self, key: &str, value: &str) -> Self {
+        self.service_config
+            .push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// The service name, from the last segment of the option path.
+    fn name(&self) -> &str {
+        self.path.rsplit('.').next().unwrap_or(&self.path)
+    }
+
+    /// Generate the complete module as a string.
+    pub fn generate(&self) -> String {
+        let name = self.name();
+        let description = self.description.as_deref().unwrap_or(name);
+        let package = match &self.package {
+            Some(package) => package.clone(),
+            None => format!("pkgs.{}", name),
+        };
+
+        // The settings type registers itself after its dependencies
+        let mut types = Vec::new();
+        T::nixos_register_types(&mut types);
+        let settings_function = types.last().map_or("lib.id", |t| t.to_settings_name);
+        let converters = to_settings_bindings(&types, "  ");
+        let type_name = types.last().map_or("types.attrs", |t| t.type_name);
+        let settings_value = match (&self.settings_option, self.instances) {
+            (_, true) => "settings".to_string(),
+            (Some(option), false) => format!("cfg.{}", option),
+            (None, false) => "cfg".to_string(),
+        };
+        let mut binds_name = false;
+        let (settings_path, options) = match (&self.settings_option, self.instances) {
+            (_, true) => (
+                format!("{}.instances.{}", self.path, INSTANCE_PLACEHOLDER),
+                format!(
+                    "instances = mkOption {{\n  type = types.attrsOf {};\n  default = {{ }};\n  description = \"Instances of {}, each running as the systemd service `{}-<instance>`.\";\n}};\n",
+                    type_name,
+                    escape_nix_string(name),
+                    escape_nix_string(name)
+                ),
+            ),
+            (Some(option), false) => (
+                format!("{}.{}", self.path, option),
+                format!(
+                    "{} = mkOption {{\n  type = {};\n  description = \"Configuration of {}, written to its config file.\";\n}};\n",
+                    option,
+                    type_name,
+                    escape_nix_string(name)
+                ),
+            ),
+            (None, false) => {
+                binds_name = types.pop().is_some_and(|t| t.takes_name);
+                (self.path.clone(), T::nixos_options_named_at(&self.path))
+            }
+        };
+
+        let mut out = String::new();
+
+        if let Some(header) = &self.header {
+            out.push_str(&crate::utils::nix_comment(header, ""));
+            writeln!(out).unwrap();
+        }
+        writeln!(out, "{{ config, lib, pkgs, utils, ... }}:").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "with lib;").unwrap();
+        writeln!(out).unwrap();
+
+        // ── Let block ───────────────────────────────────────────
+        writeln!(out, "let").unwrap();
+        writeln!(out, "  cfg = config.{};", self.path).unwrap();
+        writeln!(out).unwrap();
+        write_type_bindings(&mut out, &types, 2);
+        if binds_name {
+            // `#[nixos(default_from_name)]` options default to the service
+            // name when not in a submodule
+            writeln!(out, "  name = \"{}\";", escape_nix_string(name)).unwrap();
+            writeln!(out).unwrap();
+        }
+        out.push_str(&converters);
+        writeln!(out).unwrap();
+        let writable_paths = T::nixos_writable_paths();
+        if !writable_paths.is_empty() {
+            let paths: Vec<String> = writable_paths
+                .iter()
+                .map(|path| format!("settings.{}", path))
+                .collect();
+            writeln!(
+                out,
+                "  writablePaths = settings: map toString (lib.filter (path: path != null) [ {} ]);",
+                paths.join(" ")
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "  stateDirectories = settings: map (lib.removePrefix \"/var/lib/\") (lib.filter (lib.hasPrefix \"/var/lib/\") (writablePaths settings));"
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "  readWritePaths = settings: lib.filter (path: !lib.hasPrefix \"/var/lib/\" path) (writablePaths settings);"
+            )
+            .unwrap();
+            writeln!(out).unwrap();
+        }
+        if self.instances {
+            writeln!(
+                out,
+                "  instanceConfigFile = instance: settings: pkgs.writeText \"{}-${{instance}}.json\" (builtins.toJSON ({} settings));",
+                name, settings_function
+            )
+            .unwrap();
+        } else {
+            writeln!(
+                out,
+                "  settings = {} {};",
+                settings_function, settings_value
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "  configFile = pkgs.writeText \"{}.json\" (builtins.toJSON settings);",
+                name
+            )
+            .unwrap();
+        }
+        writeln!(out, "in").unwrap();
+        writeln!(out, "{{").unwrap();
+
+        // ── Options ─────────────────────────────────────────────
+        writeln!(out, "  options.{} = {{", self.path).unwrap();
+        writeln!(
+            out,
+            "    enable = mkEnableOption \"{}\";",
+            escape_nix_string(description)
+        )
+        .unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "    package = mkOption {{").unwrap();
+        writeln!(out, "      type = types.package;").unwrap();
+        writeln!(out, "      default = {};", package).unwrap();
+        writeln!(
+            out,
+            "      defaultText = literalExpression \"{}\";",
+            escape_nix_string(&package)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "      description = \"The {} package to use.\";",
+            escape_nix_string(name)
+        )
+        .unwrap();
+        writeln!(out, "    }};").unwrap();
+        writeln!(out).unwrap();
+        if let Some(on_calendar) = &self.timer {
+            writeln!(out, "    startAt = mkOption {{").unwrap();
+            writeln!(
+                out,
+                "      type = types.either types.str (types.listOf types.str);"
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "      default = \"{}\";",
+                escape_nix_string(on_calendar)
+            )
+            .unwrap();
+            writeln!(out, "      example = \"*-*-* 03:00:00\";").unwrap();
+            writeln!(
+                out,
+                "      description = \"When to run {}, as systemd calendar events (see {{manpage}}`systemd.time(7)`).\";",
+                escape_nix_string(name)
+            )
+            .unwrap();
+            writeln!(out, "    }};").unwrap();
+            writeln!(out).unwrap();
+            writeln!(out, "    persistent = mkOption {{").unwrap();
+            writeln!(out, "      type = types.bool;").unwrap();
+            writeln!(out, "      default = true;").unwrap();
+            writeln!(
+                out,
+                "      description = \"Whether to run {} when the system starts if a run was missed while it was off.\";",
+                escape_nix_string(name)
+            )
+            .unwrap();
+            writeln!(out, "    }};").unwrap();
+            writeln!(out).unwrap();
+            writeln!(out, "    randomizedDelaySec = mkOption {{").unwrap();
+            writeln!(out, "      type = types.str;").unwrap();
+            writeln!(out, "      default = \"0\";").unwrap();
+            writeln!(out, "      example = \"1h\";").unwrap();
+            writeln!(
+                out,
+                "      description = \"Random delay before each run of {}, as a systemd time span.\";",
+                escape_nix_string(name)
+            )
+            .unwrap();
+            writeln!(out, "    }};").unwrap();
+            writeln!(out).unwrap();
+        }
+        let firewall_ports = T::nixos_firewall_ports(&settings_value);
+        if !firewall_ports.is_empty() {
+            writeln!(out, "    openFirewall = mkOption {{").unwrap();
+            writeln!(out, "      type = types.bool;").unwrap();
+            writeln!(out, "      default = false;").unwrap();
+            writeln!(
+                out,
+                "      description = \"Whether to open the ports of {} in the firewall.\";",
+                escape_nix_string(name)
+            )
+            .unwrap();
+            writeln!(out, "    }};").unwrap();
+            writeln!(out).unwrap();
+        }
+        write_reindented(&mut out, &options, "    ");
+        writeln!(out, "  }};").unwrap();
+        writeln!(out).unwrap();
+
+        // ── Config ──────────────────────────────────────────────
+        writeln!(out, "  config = mkIf cfg.enable {{").unwrap();
+        let assertions = T::nixos_assertions(&settings_path);
+        if assertions != "[ ]" {
+            let assertions = if self.instances {
+                format!(
+                    "lib.concatLists (lib.mapAttrsToList (instance: settings: {}) cfg.instances)",
+                    with_instance(&assertions)
+                )
+            } else {
+                assertions
+            };
+            writeln!(
+                out,
+                "    assertions = {};",
+                assertions.replace('\n', "\n    ")
+            )
+            .unwrap();
+            writeln!(out).unwrap();
+        }
+        let config_path = self
+            .config_path
+            .as_deref()
+            .map(|path| with_instance(&escape_nix_string(path)));
+        if let Some(path) = &config_path {
+            match (path.strip_prefix("/etc/"), self.instances) {
+                (Some(etc_path), false) => writeln!(
+                    out,
+                    "    environment.etc.\"{}\".source = configFile;",
+                    etc_path
+                )
+                .unwrap(),
+                (Some(etc_path), true) => writeln!(
+                    out,
+                    "    environment.etc = lib.mapAttrs' (instance: settings:\n      lib.nameValuePair \"{}\" {{ source = instanceConfigFile instance settings; }}\n    ) cfg.instances;",
+                    etc_path
+                )
+                .unwrap(),
+                (None, false) => writeln!(
+                    out,
+                    "    systemd.tmpfiles.rules = [ \"L+ {} - - - - ${{configFile}}\" ];",
+                    path
+                )
+                .unwrap(),
+                (None, true) => writeln!(
+                    out,
+                    "    systemd.tmpfiles.rules = lib.mapAttrsToList (instance: settings:\n      \"L+ {} - - - - ${{instanceConfigFile instance settings}}\"\n    ) cfg.instances;",
+                    path
+                )
+                .unwrap(),
+            }
+            writeln!(out).unwrap();
+        }
+
+        if !firewall_ports.is_empty() {
+            writeln!(out, "    networking.firewall = mkIf cfg.openFirewall {{").unwrap();
+            for protocol in [PortProtocol::Tcp, PortProtocol::Udp] {
+                let ports: Vec<&str> = firewall_ports
+                    .iter()
+                    .filter(|(p, _)| *p == protocol)
+                    .map(|(_, ports)| ports.as_str())
+                    .collect();
+                if ports.is_empty() {
+                    continue;
+                }
+                let ports = match (self.instances, ports.len()) {
+                    (false, 1) => format!(" {}", ports[0]),
+                    (false, _) => format!("\n        {}", ports.join("\n        ++ ")),
+                    (true, _) => format!(
+                        " lib.concatMap (settings:\n        {}\n      ) (lib.attrValues cfg.instances)",
+                        ports.join("\n        ++ ")
+                    ),
+                };
+                writeln!(out, "      {} ={};", protocol.firewall_option(), ports).unwrap();
+            }
+            writeln!(out, "    }};").unwrap();
+            writeln!(out).unwrap();
+        }
+
+        if self.instances {
+            writeln!(
+                out,
+                "    systemd.services = lib.mapAttrs' (instance: settings:"
+            )
+            .unwrap();
+            writeln!(out, "      let").unwrap();
+            writeln!(
+                out,
+                "        configFile = instanceConfigFile instance settings;"
+            )
+            .unwrap();
+            writeln!(out, "      in").unwrap();
+            writeln!(out, "      lib.nameValuePair \"{}-${{instance}}\" {{", name).unwrap();
+            self.write_service(
+                &mut out,
+                &format!("{} (${{instance}})", escape_nix_string(description)),
+                &settings_value,
+                config_path.as_deref(),
+                "        ",
+            );
+            writeln!(out, "      }}) cfg.instances;").unwrap();
+        } else {
+            writeln!(out, "    systemd.services.{} = {{", name).unwrap();
+            self.write_service(
+                &mut out,
+                &escape_nix_string(description),
+                &settings_value,
+                config_path.as_deref(),
+                "      ",
+            );
+            writeln!(out, "    }};").unwrap();
+        }
+
+        if self.timer.is_some() {
+            writeln!(out).unwrap();
+            if self.instances {
+                writeln!(
+                    out,
+                    "    systemd.timers = lib.mapAttrs' (instance: settings:"
+                )
+                .unwrap();
+                writeln!(out, "      lib.nameValuePair \"{}-${{instance}}\" {{", name).unwrap();
+                write_timer(
+                    &mut out,
+                    &format!("{} (${{instance}})", escape_nix_string(description)),
+                    "        ",
+                );
+                writeln!(out, "      }}) cfg.instances;").unwrap();
+            } else {
+                writeln!(out, "    systemd.timers.{} = {{", name).unwrap();
+                write_timer(&mut out, &escape_nix_string(description), "      ");
+                writeln!(out, "    }};").unwrap();
+            }
+        }
+        writeln!(out, "  }};").unwrap();
+        writeln!(out, "}}").unwrap();
+
+        out
+    }
+
+    /// Write the attributes of the systemd service at `indent`. The config
+    /// file is bound to `configFile` and the settings options are at
+    /// `settings_value`.
+    fn write_service(
+        &self,
+        out: &mut String,
+        description: &str,
+        settings_value: &str,
+        config_path: Option<&str>,
+        indent: &str,
+    ) {
+        let name = self.name();
+        let default_args = vec![match config_path {
+            Some(path) => path.to_string(),
+            None => "${configFile}".to_string(),
+        }];
+        let args = self.args.as_ref().unwrap_or(&default_args);
+        let mut exec_start = format!(
+            "${{cfg.package}}/bin/{}",
+            self.binary.as_deref().unwrap_or(name)
+        );
+        if !args.is_empty() {
+            let args: Vec<String> = args.iter().map(|arg| format!("\"{}\"", arg)).collect();
+            // systemd expands `%` specifiers and `$VAR`s in `ExecStart=`,
+            // which shell quoting doesn't protect
+            exec_start.push_str(&format!(
+                " ${{utils.escapeSystemdExecArgs [ {} ]}}",
+                args.join(" ")
+            ));
+        }
+
+        writeln!(out, "{}description = \"{}\";", indent, description).unwrap();
+        // Timer jobs are started by their timer only
+        if self.timer.is_none() {
+            writeln!(out, "{}wantedBy = [ \"multi-user.target\" ];", indent).unwrap();
+        }
+        writeln!(out, "{}after = [ \"network.target\" ];", indent).unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "{}serviceConfig = {{", indent).unwrap();
+        writeln!(out, "{}  ExecStart = \"{}\";", indent, exec_start).unwrap();
+        // Secret files are passed as credentials named after their option
+        let credentials: Vec<String> = T::nixos_secrets()
+            .iter()
+            .map(|name| {
+                let file = format!("{}.{}File", settings_value, name);
+                format!(
+                    "lib.optional ({} != null) \"{}:${{{}}}\"",
+                    file,
+                    escape_nix_string(name),
+                    file
+                )
+            })
+            .collect();
+        if !credentials.is_empty() {
+            writeln!(
+                out,
+                "{}  LoadCredential =\n{}    {};",
+                indent,
+                indent,
+                credentials.join(&format!("\n{}    ++ ", indent))
+            )
+            .unwrap();
+        }
+        // The job type, writable paths and the hardening profile, unless
+        // overridden
+        let mut directives = Vec::new();
+        if self.timer.is_some() {
+            directives.push(("Type", "\"oneshot\"".to_string()));
+        }
+        if !T::nixos_writable_paths().is_empty() {
+            directives.push((
+                "StateDirectory",
+                format!("stateDirectories {}", settings_value),
+            ));
+            directives.push((
+                "ReadWritePaths",
+                format!("readWritePaths {}", settings_value),
+            ));
+        }
+        for (key, value) in self.hardening.directives() {
+            directives.push((key, value.to_string()));
+        }
+        for (key, value) in directives {
+            if !self.service_config.iter().any(|(k, _)| k == key) {
+                writeln!(out, "{}  {} = {};", indent, key, value).unwrap();
+            }
+        }
+        for (key, value) in &self.service_config {
+            writeln!(out, "{}  {} = {};", indent, key, value).unwrap();
+        }
+        writeln!(out, "{}}};", indent).unwrap();
+    }
+}
+
+/// Write the attributes of the timer starting the service at `indent`.
+fn write_timer(out: &mut String, description: &str, indent: &str) {
+    writeln!(
+        out,
+        "{}description = \"Timer for {}\";",
+        indent, description
+    )
+    .unwrap();
+    writeln!(out, "{}wantedBy = [ \"timers.target\" ];", indent).unwrap();
+    writeln!(out, "{}timerConfig = {{", indent).unwrap();
+    writeln!(out, "{}  OnCalendar = cfg.startAt;", indent).unwrap();
+    writeln!(out, "{}  Persistent = cfg.persistent;", indent).unwrap();
+    writeln!(
+        out,
+        "{}  RandomizedDelaySec = cfg.randomizedDelaySec;",
+        indent
+    )
+    .unwrap();
+    writeln!(out, "{}}};", indent).unwrap();
+}
+
+/// Stands for the instance name in option paths of instances mode
+const INSTANCE_PLACEHOLDER: &str = "<instance>";
+
+/// Replace the instance placeholder, and an escaped `${instance}`, with the
+/// `instance` variable.
+fn with_instance(text: &str) -> String {
+    text.replace(INSTANCE_PLACEHOLDER, "${instance}")
+        .replace("\\${instance}", "${instance}")
+}
@@ -0,0 +1,96 @@
+//! Runtime checks matching the generated NixOS option types.
+//!
+//! Types deriving [`NixosType`](crate::NixosType) with `#[nixos(validate)]`
+//! get a `nixos_validate()` method that enforces the same constraints as
+//! the Nix side, so configs loaded from elsewhere (dev configs, tests) are
+//! held to the same rules. The helpers here are used by that generated code.
+
+use serde::Serialize;
+use std::fmt;
+
+/// A value that the generated NixOS option type would reject.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NixosValidationError {
+    /// Option path using the Nix names, e.g. `server.listenPort` or `tags[2]`
+    pub path: String,
+    /// What is wrong with the value
+    pub message: String,
+}
+
+impl NixosValidationError {
+    /// Create an error for the option at `path`.
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for NixosValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for NixosValidationError {}
+
+/// Append an attribute name to an option path, quoting it like Nix would.
+pub fn join_path(parent: &str, name: &str) -> String {
+    let name = crate::utils::nix_attr_name(name);
+
+    if parent.is_empty() {
+        name
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+/// Get the attribute name a map key serializes to.
+pub fn map_key<K: Serialize + ?Sized>(key: &K) -> String {
+    match serde_json::to_value(key) {
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(other) => other.to_string(),
+        Err(_) => String::from("<key>"),
+    }
+}
+
+/// Whether the whole of `value` matches `pattern`, like `types.strMatching`.
+///
+/// The derive macro only accepts patterns in the part of POSIX ERE that
+/// `regex-lite` reads the same way.
+pub fn matches_pattern(value: &str, pattern: &str) -> bool {
+    regex_lite::Regex::new(&format!("^(?:{})$", pattern))
+        .map(|regex| regex.is_match(value))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_path() {
+        assert_eq!(join_path("", "port"), "port");
+        assert_eq!(join_path("server", "listenPort"), "server.listenPort");
+        assert_eq!(join_path("hosts", "a b"), "hosts.\"a b\"");
+        assert_eq!(join_path("ports", "80"), "ports.\"80\"");
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("abc", "[a-z]+"));
+        assert!(!matches_pattern("abc1", "[a-z]+"));
+        assert!(matches_pattern("a b", "a[[:space:]]b"));
+    }
+
+    #[test]
+    fn test_error_display() {
+        let error = NixosValidationError::new("server.port", "must be at least 1");
+        assert_eq!(error.to_string(), "server.port: must be at least 1");
+    }
+}
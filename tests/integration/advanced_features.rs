@@ -145,8 +145,10 @@ struct MultilineDoc {
 
     let options = MultilineDoc::nixos_options();
 
-    // Should preserve the multiline doc
-    assert!(options.contains("description = \"This is a multi-line\\ndocumentation comment\\nthat spans multiple lines\""));
+    // Should preserve the multiline doc as an indented string
+    assert!(options.contains(
+        "description = ''\n      This is a multi-line\n      documentation comment\n      that spans multiple lines\n    '';"
+    ));
 }
 
 #[test]
@@ -0,0 +1,151 @@
+//! Tests for cross-field `#[nixos(assert(...))]` checks
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::generator::NixosModuleBuilder;
+use serde_nixos::NixosType;
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[nixos(assert(
+    expr = "self.enable -> self.cert != null",
+    message = "cert is required when TLS is enabled",
+    rust = "!self.enable || self.cert.is_some()"
+))]
+struct TlsConfig {
+    enable: bool,
+    cert: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(rename_all = "camelCase")]
+#[nixos(assert(
+    expr = "self.minWorkers <= self.maxWorkers",
+    message = "minWorkers must not exceed maxWorkers",
+    rust = "self.min_workers <= self.max_workers"
+))]
+#[nixos(assert(
+    expr = "builtins.pathExists self.dataDir",
+    message = "dataDir must exist"
+))]
+struct ServerConfig {
+    min_workers: u32,
+    max_workers: u32,
+    data_dir: String,
+    tls: TlsConfig,
+    admin_tls: Option<TlsConfig>,
+}
+
+#[test]
+fn test_assertions_list() {
+    let assertions = ServerConfig::nixos_assertions("services.app");
+    assert_eq!(
+        assertions,
+        "[\n\
+         \x20 {\n\
+         \x20   assertion = let self = config.services.app; in self.minWorkers <= self.maxWorkers;\n\
+         \x20   message = \"services.app: minWorkers must not exceed maxWorkers\";\n\
+         \x20 }\n\
+         \x20 {\n\
+         \x20   assertion = let self = config.services.app; in builtins.pathExists self.dataDir;\n\
+         \x20   message = \"services.app: dataDir must exist\";\n\
+         \x20 }\n\
+         \x20 {\n\
+         \x20   assertion = let self = config.services.app.tls; in self.enable -> self.cert != null;\n\
+         \x20   message = \"services.app.tls: cert is required when TLS is enabled\";\n\
+         \x20 }\n\
+         \x20 {\n\
+         \x20   assertion = config.services.app.adminTls == null \
+         || (let self = config.services.app.adminTls; in self.enable -> self.cert != null);\n\
+         \x20   message = \"services.app.adminTls: cert is required when TLS is enabled\";\n\
+         \x20 }\n\
+         ]"
+    );
+}
+
+#[test]
+fn test_types_without_assertions() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Plain {
+        port: u16,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    enum Mode {
+        Fast,
+    }
+
+    assert_eq!(Plain::nixos_assertions("services.app"), "[ ]");
+    assert_eq!(Mode::nixos_assertions("services.app.mode"), "[ ]");
+}
+
+#[test]
+fn test_rust_side_check() {
+    let config = ServerConfig {
+        min_workers: 8,
+        max_workers: 4,
+        data_dir: "/var/lib/app".to_string(),
+        tls: TlsConfig {
+            enable: true,
+            cert: None,
+        },
+        admin_tls: Some(TlsConfig {
+            enable: false,
+            cert: None,
+        }),
+    };
+
+    let errors: Vec<String> = config
+        .nixos_validate()
+        .unwrap_err()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "tls: cert is required when TLS is enabled",
+            "minWorkers must not exceed maxWorkers",
+        ]
+    );
+
+    let valid = ServerConfig {
+        min_workers: 1,
+        max_workers: 4,
+        tls: TlsConfig {
+            enable: true,
+            cert: Some("/run/cert.pem".to_string()),
+        },
+        ..config
+    };
+    assert!(valid.nixos_validate().is_ok());
+}
+
+#[test]
+fn test_module_builder_collects_assertions() {
+    let mut builder = NixosModuleBuilder::new("services.app");
+    builder.add_assertions(ServerConfig::nixos_assertions("services.app.settings"));
+    let module = builder.build();
+
+    assert!(module.contains("  config = mkIf config.services.app.enable {\n    assertions = [\n"));
+    assert!(module.contains(
+        "        assertion = let self = config.services.app.settings; \
+         in self.minWorkers <= self.maxWorkers;\n"
+    ));
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[nixos(assert(
+    expr = "self.lib != \"\" && self.cfg > 0",
+    message = "lib and cfg are required"
+))]
+struct Shadowing {
+    lib: String,
+    cfg: u32,
+}
+
+#[test]
+fn test_assertions_refer_to_options_through_self() {
+    // Options named like lexically bound names are still reachable
+    assert!(Shadowing::nixos_assertions("services.app").contains(
+        "assertion = let self = config.services.app; in self.lib != \"\" && self.cfg > 0;"
+    ));
+}
@@ -25,7 +25,9 @@ struct ConfigWithAutoDocs {
 
     // Multi-line doc comments should be preserved
     assert!(options.contains("field2 = lib.mkOption"));
-    assert!(options.contains("description = \"This is the second field\\nwith multiple lines\""));
+    assert!(options.contains(
+        "description = ''\n      This is the second field\n      with multiple lines\n    '';"
+    ));
 
     // Field without doc comment should not have description
     assert!(options.contains("field3 = lib.mkOption"));
@@ -270,3 +272,89 @@ struct ManualDocConfig {
     // Manual should use explicit description
     assert!(manual_options.contains("description = \"Manual description\""));
 }
+
+/// Connection settings for the database.
+///
+/// Shared by all workers.
+#[derive(Serialize, Deserialize, NixosType)]
+struct DocumentedDatabase {
+    /// Host name
+    host: String,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[nixos(description = "How much to log")]
+enum DocumentedLevel {
+    Info,
+    Debug,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct DocumentedApp {
+    database: DocumentedDatabase,
+    /// Database used for reports
+    reports: DocumentedDatabase,
+    #[serde(default)]
+    level: Option<DocumentedLevel>,
+}
+
+#[test]
+fn test_type_description() {
+    assert_eq!(
+        DocumentedDatabase::nixos_description(),
+        Some("Connection settings for the database.\n\nShared by all workers.")
+    );
+    assert_eq!(
+        DocumentedLevel::nixos_description(),
+        Some("How much to log")
+    );
+    assert_eq!(DocumentedApp::nixos_description(), None);
+}
+
+#[test]
+fn test_type_description_describes_fields() {
+    let options = DocumentedApp::nixos_options();
+
+    // Fields without a description of their own use the type's
+    assert!(options.contains(
+        "description = ''\n      Connection settings for the database.\n\n      Shared by all workers.\n    '';"
+    ));
+    assert!(options.contains("description = \"Database used for reports\";"));
+    assert!(options.contains("description = \"How much to log\";"));
+}
+
+#[test]
+fn test_type_description_comments() {
+    let definition = DocumentedDatabase::nixos_type_definition();
+    assert!(definition.contains(
+        "# Connection settings for the database.\n#\n# Shared by all workers.\ndocumentedDatabaseType = types.submodule {"
+    ));
+
+    let definition = DocumentedLevel::nixos_type_definition();
+    assert!(definition.contains("# How much to log\ndocumentedLevelType = types.enum"));
+
+    let full = DocumentedApp::nixos_type_full_definition();
+    assert!(full.contains(
+        "  # Connection settings for the database.\n  #\n  # Shared by all workers.\n  documentedDatabaseType = types.submodule {"
+    ));
+}
+
+#[test]
+fn test_container_description_with_auto_doc() {
+    /// From the doc comment
+    #[derive(Serialize, Deserialize, NixosType)]
+    #[nixos(description = "From the attribute")]
+    struct Plain {
+        value: u8,
+    }
+
+    /// From the doc comment
+    #[derive(Serialize, Deserialize, NixosType)]
+    #[nixos(auto_doc, description = "From the attribute")]
+    struct AutoDoc {
+        value: u8,
+    }
+
+    assert_eq!(Plain::nixos_description(), Some("From the attribute"));
+    assert_eq!(AutoDoc::nixos_description(), Some("From the doc comment"));
+}
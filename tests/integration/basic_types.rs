@@ -212,3 +212,177 @@ struct PascalConfig {
     assert!(options.contains("MyField = lib.mkOption"));
     assert!(options.contains("AnotherLongField = lib.mkOption"));
 }
+
+#[test]
+fn test_std_scalar_types() {
+    use std::borrow::Cow;
+    use std::ffi::OsString;
+    use std::num::{NonZeroI32, NonZeroU16};
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct StdScalars<'a> {
+        separator: char,
+        workers: NonZeroU16,
+        offset: NonZeroI32,
+        label: Cow<'a, str>,
+        name: Box<str>,
+        raw: OsString,
+        extra: serde_json::Value,
+    }
+
+    let options = StdScalars::nixos_options();
+    let block = |name: &str| {
+        let start = options.find(&format!("{} = lib.mkOption", name)).unwrap();
+        let rest = &options[start..];
+        rest[..rest.find("};").unwrap()].to_string()
+    };
+
+    assert!(block("separator").contains("builtins.stringLength s == 1"));
+    assert!(block("workers").contains("type = types.ints.positive;"));
+    assert!(block("offset").contains("type = types.addCheck types.int (x: x != 0);"));
+    assert!(block("label").contains("type = types.str;"));
+    assert!(block("name").contains("type = types.str;"));
+    assert!(block("raw").contains("type = types.str;"));
+    assert!(block("extra").contains("type = types.anything;"));
+}
+
+#[test]
+fn test_shared_str_types() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[derive(NixosType)]
+    #[allow(dead_code)]
+    struct Shared {
+        a: Arc<str>,
+        b: Rc<String>,
+    }
+
+    let options = Shared::nixos_options();
+    assert_eq!(
+        options.matches("type = types.str;").count(),
+        2,
+        "{}",
+        options
+    );
+}
+
+#[test]
+fn test_network_address_types() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Network {
+        any: IpAddr,
+        v4: Ipv4Addr,
+        v6: Ipv6Addr,
+        listen: SocketAddr,
+    }
+
+    let options = Network::nixos_options();
+    assert_eq!(
+        options.matches("type = types.strMatching \"").count(),
+        4,
+        "{}",
+        options
+    );
+    assert!(
+        options.contains(
+            "type = types.strMatching \"((25[0-5]|2[0-4][0-9]|1[0-9][0-9]|[1-9]?[0-9])[.]){3}"
+        ),
+        "{}",
+        options
+    );
+    // SocketAddr accepts `ip:port` and `[ipv6]:port`
+    assert!(options.contains("[[]([0-9a-fA-F]{0,4}"), "{}", options);
+    assert!(options.contains("[]]:[0-9]{1,5}"), "{}", options);
+}
+
+#[test]
+fn test_duration_struct_form() {
+    use std::time::Duration;
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Timeouts {
+        connect: Duration,
+        idle: Option<Duration>,
+    }
+
+    let options = Timeouts::nixos_options();
+    assert!(
+        options.contains("type = types.submodule { options = { secs = lib.mkOption { type = types.ints.unsigned; }; nanos = lib.mkOption { type = types.ints.between 0 999999999; }; }; };"),
+        "{}",
+        options
+    );
+    assert!(
+        options.contains("type = types.nullOr (types.submodule {"),
+        "{}",
+        options
+    );
+
+    // Duration is a std type, not a custom one needing a let binding
+    let full = Timeouts::nixos_type_full_definition();
+    assert!(!full.contains("durationType"), "{}", full);
+}
+
+#[test]
+fn test_user_types_named_like_std_types() {
+    mod user {
+        use serde::{Deserialize, Serialize};
+        use serde_nixos::NixosType;
+
+        #[derive(Serialize, Deserialize, NixosType)]
+        pub struct Value {
+            pub amount: u32,
+        }
+
+        #[derive(Serialize, Deserialize, NixosType)]
+        pub struct Duration {
+            pub days: u32,
+        }
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Billing {
+        price: user::Value,
+        period: user::Duration,
+        extra: serde_json::Value,
+        timeout: std::time::Duration,
+    }
+
+    // Only `serde_json::Value` and `std::time::Duration` are mapped by name
+    let options = Billing::nixos_options();
+    let block = |name: &str| {
+        let start = options.find(&format!("{} = lib.mkOption", name)).unwrap();
+        let rest = &options[start..];
+        rest[..rest.find("};").unwrap()].to_string()
+    };
+    assert!(
+        block("price").contains("type = types.submodule"),
+        "{}",
+        options
+    );
+    assert!(!block("price").contains("types.anything"), "{}", options);
+    assert!(
+        !block("period").contains("secs = lib.mkOption"),
+        "{}",
+        options
+    );
+    assert!(block("extra").contains("type = types.anything;"));
+    assert!(block("timeout").contains("secs = lib.mkOption"));
+
+    // User types get a binding of their own
+    let full = Billing::nixos_type_full_definition();
+    assert!(
+        full.contains("  valueType = types.submodule {\n"),
+        "{}",
+        full
+    );
+    assert!(full.contains("amount = lib.mkOption"), "{}", full);
+    assert!(
+        full.contains("  durationType = types.submodule {\n"),
+        "{}",
+        full
+    );
+    assert!(full.contains("type = valueType;"), "{}", full);
+}
@@ -353,3 +353,90 @@ struct C {
         opts
     );
 }
+
+// ── Fixed-length and other std collections ──────────────────────────────
+
+#[test]
+fn test_array_fixed_length() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct C {
+        v: [u8; 4],
+        n: [Vec<String>; 2],
+    }
+    let opts = C::nixos_options();
+    assert!(
+        opts.contains("types.addCheck (types.listOf types.int) (l: builtins.length l == 4)"),
+        "{}",
+        opts
+    );
+    assert!(
+        opts.contains(
+            "types.addCheck (types.listOf (types.listOf types.str)) (l: builtins.length l == 2)"
+        ),
+        "compound element type must be parenthesized: {}",
+        opts
+    );
+}
+
+#[test]
+fn test_tuple_fixed_length() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct C {
+        pair: (String, u16),
+        unit: (),
+    }
+    let opts = C::nixos_options();
+    assert!(
+        opts.contains("types.addCheck (types.listOf types.anything) (l: builtins.length l == 2 && (types.str).check (builtins.elemAt l 0) && (types.int).check (builtins.elemAt l 1))"),
+        "{}",
+        opts
+    );
+    assert!(opts.contains("type = types.null;"), "{}", opts);
+}
+
+#[test]
+fn test_array_and_tuple_custom_types_use_named_reference() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Item {
+        name: String,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct C {
+        items: [Item; 3],
+        pair: (Item, u32),
+    }
+
+    let full = C::nixos_type_full_definition();
+    assert!(full.contains("itemType = types.submodule"), "{}", full);
+    assert!(
+        full.contains("types.addCheck (types.listOf itemType) (l: builtins.length l == 3)"),
+        "{}",
+        full
+    );
+    assert!(
+        full.contains("(itemType).check (builtins.elemAt l 0)"),
+        "{}",
+        full
+    );
+}
+
+#[test]
+fn test_vecdeque_and_binary_heap_are_lists() {
+    use std::collections::{BinaryHeap, VecDeque};
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct C {
+        queue: VecDeque<String>,
+        heap: BinaryHeap<u32>,
+        nested: VecDeque<Vec<String>>,
+    }
+    let opts = C::nixos_options();
+    assert!(opts.contains("type = types.listOf types.str;"), "{}", opts);
+    assert!(opts.contains("type = types.listOf types.int;"), "{}", opts);
+    assert!(
+        opts.contains("type = types.listOf (types.listOf types.str);"),
+        "{}",
+        opts
+    );
+}
@@ -0,0 +1,229 @@
+//! Tests for `#[nixos(min, max, pattern, non_empty, unique)]` value constraints
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::NixosType;
+use std::collections::HashMap;
+
+fn option_block<'a>(options: &'a str, name: &str) -> &'a str {
+    let start = options
+        .find(&format!("{} = lib.mkOption", name))
+        .unwrap_or_else(|| panic!("missing option {}:\n{}", name, options));
+    let rest = &options[start..];
+    let end = rest.find("\n  };").unwrap_or(rest.len());
+    &rest[..end]
+}
+
+#[test]
+fn test_numeric_bounds() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Limits {
+        /// Listening port
+        #[nixos(min = 1, max = 65535)]
+        port: u16,
+        #[nixos(min = 1024)]
+        unprivileged_port: Option<u16>,
+        #[nixos(min = -1.5, max = 1.5)]
+        offset: f64,
+        #[nixos(max = 10)]
+        retries: i32,
+    }
+
+    let options = Limits::nixos_options();
+
+    let port = option_block(&options, "port");
+    assert!(
+        port.contains("type = types.ints.between 1 65535;"),
+        "{}",
+        port
+    );
+    assert!(
+        port.contains(
+            "description = ''\n      Listening port\n\n      Constraints: value between 1 and 65535.\n    '';"
+        ),
+        "{}",
+        port
+    );
+
+    let unprivileged = option_block(&options, "unprivileged_port");
+    assert!(
+        unprivileged.contains("type = types.nullOr (types.addCheck types.int (x: x >= 1024));"),
+        "{}",
+        unprivileged
+    );
+    assert!(unprivileged.contains("default = null;"), "{}", unprivileged);
+
+    let offset = option_block(&options, "offset");
+    assert!(
+        offset.contains("type = types.addCheck types.float (x: x >= (-1.5) && x <= 1.5);"),
+        "{}",
+        offset
+    );
+
+    let retries = option_block(&options, "retries");
+    assert!(
+        retries.contains("type = types.addCheck types.int (x: x <= 10);"),
+        "{}",
+        retries
+    );
+}
+
+#[test]
+fn test_string_constraints() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Names {
+        #[nixos(pattern = "[a-z][a-z0-9-]*")]
+        hostname: String,
+        #[nixos(non_empty)]
+        user: String,
+        #[nixos(pattern = "[0-9]+", non_empty)]
+        id: Option<String>,
+        #[nixos(pattern = "a\\.b\"c")]
+        escaped: String,
+    }
+
+    let options = Names::nixos_options();
+    assert!(options.contains("type = types.strMatching \"[a-z][a-z0-9-]*\";"));
+    assert!(options.contains("type = types.nonEmptyStr;"));
+    assert!(
+        options.contains(
+            "type = types.nullOr (types.addCheck (types.strMatching \"[0-9]+\") (s: s != \"\"));"
+        ),
+        "{}",
+        options
+    );
+    assert!(
+        options.contains("type = types.strMatching \"a\\\\.b\\\"c\";"),
+        "{}",
+        options
+    );
+    assert!(options.contains("Constraints: must not be empty; value matching `[0-9]+`."));
+}
+
+#[test]
+fn test_posix_patterns() {
+    // Syntax that Nix and `nixos_validate()` read alike; anything else is a
+    // compile error
+    #[derive(Serialize, Deserialize, NixosType)]
+    #[nixos(validate)]
+    struct Patterns {
+        #[nixos(pattern = "[[:alpha:]_][[:alnum:]_-]*")]
+        ident: String,
+        #[nixos(pattern = "^v[0-9]+\\.[0-9]+(\\.[0-9]+)?$")]
+        version: String,
+        #[nixos(pattern = "(ab|cd){2,3}")]
+        pairs: String,
+        #[nixos(pattern = "[]^a-]+")]
+        brackets: String,
+        #[nixos(pattern = "[^[:space:]]+")]
+        word: String,
+    }
+
+    let options = Patterns::nixos_options();
+    assert!(options.contains("type = types.strMatching \"[[:alpha:]_][[:alnum:]_-]*\";"));
+    assert!(
+        options.contains("type = types.strMatching \"^v[0-9]+\\\\.[0-9]+(\\\\.[0-9]+)?$\";"),
+        "{}",
+        options
+    );
+
+    let valid = Patterns {
+        ident: "_web-1".to_string(),
+        version: "v1.20.3".to_string(),
+        pairs: "abcdab".to_string(),
+        brackets: "a]^-".to_string(),
+        word: "nospace".to_string(),
+    };
+    assert!(valid.nixos_validate().is_ok());
+
+    let invalid = Patterns {
+        ident: "1web".to_string(),
+        version: "v1x2".to_string(),
+        pairs: "ab".to_string(),
+        brackets: "b".to_string(),
+        word: "has space".to_string(),
+    };
+    let errors: Vec<String> = invalid
+        .nixos_validate()
+        .unwrap_err()
+        .into_iter()
+        .map(|error| error.path)
+        .collect();
+    assert_eq!(
+        errors,
+        vec!["ident", "version", "pairs", "brackets", "word"]
+    );
+}
+
+#[test]
+fn test_collection_constraints() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Lists {
+        #[nixos(non_empty, unique, pattern = "[a-z]+")]
+        tags: Vec<String>,
+        #[nixos(min = 1, max = 100)]
+        weights: Vec<u8>,
+        #[nixos(non_empty)]
+        servers: Option<Vec<String>>,
+        #[nixos(non_empty, max = 10)]
+        quotas: HashMap<String, u32>,
+    }
+
+    let options = Lists::nixos_options();
+
+    let tags = option_block(&options, "tags");
+    assert!(
+        tags.contains(
+            "type = types.addCheck (types.nonEmptyListOf (types.strMatching \"[a-z]+\")) \
+             (l: builtins.length (lib.unique l) == builtins.length l);"
+        ),
+        "{}",
+        tags
+    );
+    assert!(
+        tags.contains(
+            "Constraints: must not be empty; items must be unique; each item matching `[a-z]+`."
+        ),
+        "{}",
+        tags
+    );
+
+    let weights = option_block(&options, "weights");
+    assert!(
+        weights.contains("type = types.listOf (types.ints.between 1 100);"),
+        "{}",
+        weights
+    );
+
+    let servers = option_block(&options, "servers");
+    assert!(
+        servers.contains("type = types.nullOr (types.nonEmptyListOf types.str);"),
+        "{}",
+        servers
+    );
+
+    let quotas = option_block(&options, "quotas");
+    assert!(
+        quotas.contains(
+            "type = types.addCheck (types.attrsOf (types.addCheck types.int (x: x <= 10))) \
+             (attrs: attrs != { });"
+        ),
+        "{}",
+        quotas
+    );
+}
+
+#[test]
+fn test_constraints_with_serde_default() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Cfg {
+        #[serde(default)]
+        #[nixos(min = 1)]
+        workers: u32,
+    }
+
+    let options = Cfg::nixos_options();
+    assert!(options.contains("type = types.nullOr (types.addCheck types.int (x: x >= 1));"));
+    assert!(options.contains(
+        "description = ''\n      Constraints: value at least 1.\n\n      If unset, the program's built-in default is used.\n    '';"
+    ));
+}
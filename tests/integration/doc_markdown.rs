@@ -0,0 +1,173 @@
+//! Tests for the conversion of doc comments to NixOS manual markdown.
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::NixosType;
+
+/// Connection pool settings.
+///
+/// # Examples
+///
+/// ```
+/// let pool = PoolConfig { max_size: 4 };
+/// ```
+#[derive(Serialize, Deserialize, NixosType)]
+struct PoolConfig {
+    /// Largest number of open connections.
+    #[serde(rename = "maxSize")]
+    max_size: u32,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(rename_all = "camelCase")]
+struct ServerConfig {
+    /// Port to listen on, next to [`listen_address`](Self::listen_address).
+    ///
+    /// ```
+    /// # use std::net::TcpListener;
+    /// let listener = TcpListener::bind(("::", 8080))?;
+    /// ## Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Never.
+    ///
+    /// # Privileged ports
+    ///
+    /// Ports below 1024 need [`CAP_NET_BIND_SERVICE`][caps].
+    ///
+    /// [caps]: https://man7.org/linux/man-pages/man7/capabilities.7.html
+    port: u16,
+
+    /// Address to bind.
+    listen_address: String,
+
+    /// Pools, each limited by [`PoolConfig::max_size`].
+    pools: Vec<PoolConfig>,
+
+    /// Raise the [limit][PoolConfig::max_size] with [`workers`] and
+    /// [`Unknown::field`]; [this] stays as written.
+    ///
+    /// [`workers`]: ServerConfig::workers
+    workers: u32,
+
+    /// Nix syntax like `${pkgs.hello}` and `''` stays literal.
+    ///
+    /// Second paragraph.
+    script: String,
+
+    /// Shell quoting like echo '${HOME}' stays literal.
+    ///
+    /// Second paragraph.
+    quoted: String,
+}
+
+#[test]
+fn test_rustdoc_sections_are_stripped() {
+    let options = ServerConfig::nixos_options();
+
+    assert!(
+        options.contains("```rust\n      let listener"),
+        "{}",
+        options
+    );
+    assert!(
+        !options.contains("use std::net::TcpListener"),
+        "{}",
+        options
+    );
+    assert!(
+        options.contains("# Ok::<(), std::io::Error>(())"),
+        "{}",
+        options
+    );
+    assert!(!options.contains("Never."), "{}", options);
+    assert!(
+        options.contains("**Privileged ports**\n\n      Ports below 1024"),
+        "{}",
+        options
+    );
+    // Links to URLs are plain markdown
+    assert!(
+        options.contains("need [`CAP_NET_BIND_SERVICE`][caps].\n\n      [caps]: https://"),
+        "{}",
+        options
+    );
+}
+
+#[test]
+fn test_multiline_docs_use_indented_strings() {
+    let options = ServerConfig::nixos_options();
+
+    assert!(
+        options.contains(
+            "description = ''\n      Nix syntax like `''${pkgs.hello}` and `'''` stays literal.\n\n      Second paragraph.\n    '';"
+        ),
+        "{}",
+        options
+    );
+    assert!(options.contains("description = \"Address to bind.\";"));
+    // A quote right before `${` can't be followed by the `''${` escape
+    assert!(
+        options.contains("      Shell quoting like echo ''\\'''${HOME}' stays literal.\n"),
+        "{}",
+        options
+    );
+}
+
+#[test]
+fn test_intra_doc_links_become_option_references() {
+    let options = ServerConfig::nixos_options();
+
+    assert!(
+        options.contains("next to {option}`listenAddress`."),
+        "{}",
+        options
+    );
+    assert!(
+        options.contains("each limited by {option}`pools.*.maxSize`."),
+        "{}",
+        options
+    );
+    assert!(
+        options.contains(
+            "Raise the limit ({option}`pools.*.maxSize`) with {option}`workers` and\n      `Unknown::field`; [this] stays as written."
+        ),
+        "{}",
+        options
+    );
+}
+
+#[test]
+fn test_option_references_use_the_option_prefix() {
+    let options = ServerConfig::nixos_options_at("services.server.settings");
+
+    assert!(
+        options.contains("next to {option}`services.server.settings.listenAddress`."),
+        "{}",
+        options
+    );
+    assert!(
+        options.contains("{option}`services.server.settings.pools.*.maxSize`"),
+        "{}",
+        options
+    );
+    assert_eq!(
+        ServerConfig::nixos_options_at(""),
+        ServerConfig::nixos_options()
+    );
+}
+
+#[test]
+fn test_type_docs_are_converted() {
+    assert_eq!(
+        PoolConfig::nixos_description(),
+        Some("Connection pool settings.")
+    );
+    assert_eq!(
+        ServerConfig::nixos_field_name("listen_address"),
+        Some("listenAddress")
+    );
+    assert_eq!(PoolConfig::nixos_field_name("max_size"), Some("maxSize"));
+    assert_eq!(PoolConfig::nixos_field_name("missing"), None);
+}
@@ -0,0 +1,269 @@
+//! Feature-gated ecosystem type mappings.
+//!
+//! The mappings match on type names, so small stand-in modules named after
+//! each crate are enough to exercise them without the real dependencies.
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::NixosType;
+
+#[allow(dead_code)]
+mod url {
+    pub struct Url;
+}
+
+#[allow(dead_code)]
+mod uuid {
+    pub struct Uuid;
+}
+
+#[allow(dead_code)]
+mod chrono {
+    pub struct Utc;
+    pub struct DateTime<Tz>(pub Tz);
+    pub struct NaiveDate;
+    pub struct NaiveTime;
+}
+
+#[allow(dead_code)]
+mod time {
+    pub struct OffsetDateTime;
+    pub struct Date;
+}
+
+#[allow(dead_code)]
+mod humantime_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub struct Serde<T>(pub T);
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{}s", value.as_secs()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let secs = s
+            .trim_end_matches('s')
+            .parse()
+            .map_err(serde::de::Error::custom)?;
+        Ok(Duration::from_secs(secs))
+    }
+}
+
+#[allow(dead_code)]
+mod ipnet {
+    pub struct IpNet;
+    pub struct Ipv4Net;
+}
+
+#[allow(dead_code)]
+mod bytesize {
+    pub struct ByteSize;
+}
+
+#[allow(dead_code)]
+mod semver {
+    pub struct Version;
+    pub struct VersionReq;
+}
+
+#[allow(dead_code)]
+mod indexmap {
+    pub type IndexMap<K, V> = std::collections::HashMap<K, V>;
+    pub type IndexSet<T> = std::collections::HashSet<T>;
+}
+
+#[allow(dead_code)]
+mod http {
+    pub struct Uri;
+}
+
+#[allow(dead_code)]
+mod regex {
+    pub struct Regex;
+}
+
+fn option_type<'a>(options: &'a str, name: &str) -> &'a str {
+    let start = options
+        .find(&format!("{} = lib.mkOption", name))
+        .unwrap_or_else(|| panic!("missing option {}:\n{}", name, options));
+    let rest = &options[start..];
+    let type_start = rest.find("type = ").unwrap() + "type = ".len();
+    let type_end = rest[type_start..].find(";\n").unwrap() + type_start;
+    &rest[type_start..type_end]
+}
+
+#[test]
+fn test_string_like_types() {
+    #[derive(NixosType)]
+    #[allow(dead_code)]
+    struct Endpoints {
+        homepage: url::Url,
+        id: uuid::Uuid,
+        upstream: http::Uri,
+        filter: regex::Regex,
+        version: semver::Version,
+        requirement: semver::VersionReq,
+    }
+
+    let options = Endpoints::nixos_options();
+    assert!(option_type(&options, "homepage").starts_with("types.strMatching"));
+    assert!(option_type(&options, "id").contains("[0-9a-fA-F]{8}"));
+    assert!(option_type(&options, "upstream").starts_with("types.strMatching"));
+    assert_eq!(option_type(&options, "filter"), "types.str");
+    assert!(option_type(&options, "version").contains("[.](0|[1-9][0-9]*)"));
+    assert_eq!(option_type(&options, "requirement"), "types.str");
+    assert!(!options.contains("types.submodule"), "{}", options);
+}
+
+#[test]
+fn test_timestamp_types() {
+    #[derive(NixosType)]
+    #[allow(dead_code)]
+    struct Schedule {
+        created: chrono::DateTime<chrono::Utc>,
+        day: chrono::NaiveDate,
+        at: chrono::NaiveTime,
+        updated: time::OffsetDateTime,
+        expires: time::Date,
+        timeout: humantime_serde::Serde<std::time::Duration>,
+    }
+
+    let options = Schedule::nixos_options();
+    assert!(option_type(&options, "created").contains("[Tt ]"));
+    assert!(option_type(&options, "created").contains("[Zz]"));
+    assert_eq!(
+        option_type(&options, "day"),
+        "types.strMatching \"[0-9]{4}-[0-9]{2}-[0-9]{2}\""
+    );
+    assert!(option_type(&options, "at").starts_with("types.strMatching"));
+    assert!(option_type(&options, "updated").contains("[.][0-9]{1,9} [+-]"));
+    assert_eq!(
+        option_type(&options, "expires"),
+        option_type(&options, "day")
+    );
+    assert!(option_type(&options, "timeout").contains("[a-zA-Z]+"));
+}
+
+/// The real `time` crate, which the stand-in module above shadows at the top
+/// level.
+mod real_time {
+    use super::option_type;
+    use serde::Serialize;
+    use serde_nixos::validation::matches_pattern;
+    use serde_nixos::NixosType;
+    use time::macros::{date, datetime};
+
+    fn pattern(option_type: &str) -> String {
+        let quoted = option_type
+            .strip_prefix("types.strMatching ")
+            .unwrap_or_else(|| panic!("not a pattern: {}", option_type));
+        serde_json::from_str(quoted).unwrap()
+    }
+
+    #[test]
+    fn test_time_formats_match_their_types() {
+        #[derive(Serialize, NixosType)]
+        struct Stamps {
+            offset: time::OffsetDateTime,
+            primitive: time::PrimitiveDateTime,
+            date: time::Date,
+            #[serde(with = "time::serde::rfc3339")]
+            rfc3339: time::OffsetDateTime,
+            #[serde(with = "time::serde::rfc2822")]
+            rfc2822: time::OffsetDateTime,
+            #[serde(with = "time::serde::iso8601")]
+            iso8601: time::OffsetDateTime,
+        }
+
+        let at = datetime!(2003-07-01 10:52:37.123 +02:00);
+        let stamps = Stamps {
+            offset: at,
+            primitive: datetime!(2023-01-01 00:00),
+            date: date!(2023 - 01 - 01),
+            rfc3339: at,
+            rfc2822: at,
+            iso8601: at,
+        };
+        let options = Stamps::nixos_options();
+        let json = serde_json::to_value(&stamps).unwrap();
+
+        for name in ["offset", "primitive", "date", "rfc3339", "rfc2822"] {
+            let value = json[name].as_str().unwrap();
+            let pattern = pattern(option_type(&options, name));
+            assert!(
+                matches_pattern(value, &pattern),
+                "{} = {:?} does not match {}",
+                name,
+                value,
+                pattern
+            );
+        }
+        assert!(!matches_pattern(
+            json["rfc2822"].as_str().unwrap(),
+            &pattern(option_type(&options, "rfc3339"))
+        ));
+        assert_eq!(option_type(&options, "iso8601"), "types.str");
+    }
+}
+
+#[test]
+fn test_network_and_size_types() {
+    #[derive(NixosType)]
+    #[allow(dead_code)]
+    struct Limits {
+        subnet: ipnet::Ipv4Net,
+        allowed: Vec<ipnet::IpNet>,
+        max_body: bytesize::ByteSize,
+    }
+
+    let options = Limits::nixos_options();
+    assert!(option_type(&options, "subnet").ends_with("/[0-9]{1,2}\""));
+    assert!(option_type(&options, "allowed").starts_with("types.listOf (types.strMatching"));
+    assert!(option_type(&options, "max_body").starts_with("types.either types.ints.unsigned"));
+}
+
+#[test]
+fn test_indexmap_collections() {
+    use indexmap::{IndexMap, IndexSet};
+
+    #[derive(NixosType)]
+    #[allow(dead_code)]
+    struct Ordered {
+        headers: IndexMap<String, String>,
+        tags: IndexSet<String>,
+    }
+
+    let options = Ordered::nixos_options();
+    assert_eq!(option_type(&options, "headers"), "types.attrsOf types.str");
+    assert_eq!(option_type(&options, "tags"), "types.listOf types.str");
+}
+
+#[test]
+fn test_serde_with_adapter() {
+    use std::time::Duration;
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Timeouts {
+        #[serde(with = "humantime_serde")]
+        connect: Duration,
+        #[serde(serialize_with = "humantime_serde::serialize")]
+        idle: Duration,
+        plain: Duration,
+    }
+
+    let options = Timeouts::nixos_options();
+    let humantime = option_type(&options, "connect");
+    assert!(humantime.contains("[a-zA-Z]+"), "{}", options);
+    assert_eq!(option_type(&options, "idle"), humantime);
+    assert!(option_type(&options, "plain").starts_with("types.submodule"));
+
+    let json = serde_json::to_string(&Timeouts {
+        connect: Duration::from_secs(5),
+        idle: Duration::from_secs(60),
+        plain: Duration::from_secs(1),
+    })
+    .unwrap();
+    assert!(json.contains("\"connect\":\"5s\""));
+}
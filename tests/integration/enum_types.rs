@@ -147,3 +147,137 @@ enum Priority {
     assert!(nixos_type.contains("\"LOW\""));
     assert!(nixos_type.contains("\"VERY_HIGH\""));
 }
+
+#[test]
+fn test_enum_default_variant() {
+    #[derive(Default, Serialize, Deserialize, NixosType)]
+    #[serde(rename_all = "lowercase")]
+    enum Mode {
+        #[default]
+        Safe,
+        Fast,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    enum Plain {
+        A,
+        B,
+    }
+
+    assert_eq!(Mode::nixos_default_variant(), Some("safe"));
+    assert_eq!(Plain::nixos_default_variant(), None);
+}
+
+#[test]
+fn test_enum_field_uses_default_variant() {
+    #[derive(Default, Serialize, Deserialize, NixosType)]
+    enum Mode {
+        Safe,
+        #[default]
+        #[serde(rename = "turbo")]
+        Fast,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Config {
+        mode: Mode,
+    }
+
+    let options = Config::nixos_options();
+    assert!(
+        options.contains("default = \"turbo\";"),
+        "enum field should default to the #[default] variant: {}",
+        options
+    );
+
+    let full = Config::nixos_type_full_definition();
+    assert!(full.contains("default = \"turbo\";"), "{}", full);
+}
+
+#[test]
+fn test_enum_field_explicit_default_overrides_variant() {
+    #[derive(Default, Serialize, Deserialize, NixosType)]
+    enum Mode {
+        #[default]
+        Safe,
+        Fast,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Config {
+        #[nixos(default = "\"Fast\"")]
+        mode: Mode,
+        fallback: Option<Mode>,
+    }
+
+    let options = Config::nixos_options();
+    assert!(options.contains("default = \"Fast\";"), "{}", options);
+    assert!(!options.contains("default = \"Safe\";"), "{}", options);
+    assert!(options.contains("default = null;"), "{}", options);
+}
+
+#[test]
+fn test_serde_repr_enum_uses_discriminants() {
+    use serde_repr::{Deserialize_repr, Serialize_repr};
+
+    #[derive(Debug, PartialEq, Serialize_repr, Deserialize_repr, NixosType)]
+    #[nixos(repr)]
+    #[repr(u8)]
+    enum Level {
+        Low = 1,
+        Medium,
+        High = 5,
+    }
+
+    assert_eq!(Level::nixos_type(), "types.enum [ 1 2 5 ]");
+    assert_eq!(Level::nixos_enum_variants(), Some(&["1", "2", "5"][..]));
+    assert_eq!(
+        Level::nixos_value_description(),
+        Some("Values: 1 = Low, 2 = Medium, 5 = High.")
+    );
+    assert!(Level::nixos_type_definition().contains("# Values: 1 = Low, 2 = Medium, 5 = High."));
+
+    // The NixOS values match what serde produces
+    assert_eq!(serde_json::to_string(&Level::High).unwrap(), "5");
+    assert_eq!(serde_json::from_str::<Level>("2").unwrap(), Level::Medium);
+}
+
+#[test]
+fn test_nixos_repr_enum_field() {
+    use serde_repr::{Deserialize_repr, Serialize_repr};
+
+    #[derive(Default, Serialize_repr, Deserialize_repr, NixosType)]
+    #[nixos(repr)]
+    #[repr(i8)]
+    enum Offset {
+        Behind = -1,
+        #[default]
+        Even,
+        Ahead,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Config {
+        /// Clock offset
+        offset: Offset,
+        previous: Option<Offset>,
+    }
+
+    assert_eq!(Offset::nixos_type(), "types.enum [ (-1) 0 1 ]");
+    assert_eq!(Offset::nixos_default_variant(), Some("0"));
+
+    let options = Config::nixos_options();
+    assert!(options.contains("default = 0;"), "{}", options);
+    assert!(
+        options.contains(
+            "description = ''\n      Clock offset\n\n      Values: -1 = Behind, 0 = Even, 1 = Ahead.\n    '';"
+        ),
+        "{}",
+        options
+    );
+    assert!(
+        options.contains("description = \"Values: -1 = Behind, 0 = Even, 1 = Ahead.\";"),
+        "{}",
+        options
+    );
+}
@@ -0,0 +1,153 @@
+//! Tests for `#[nixos(port, open_firewall)]` and the `openFirewall` option
+//! of `ServiceModule`
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::{NixosType, PortProtocol, ServiceModule};
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct Listener {
+    /// Port to listen on
+    #[nixos(port, open_firewall)]
+    port: u16,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(rename_all = "camelCase")]
+struct Settings {
+    /// HTTP port
+    #[nixos(port, open_firewall, default = "8080")]
+    http_port: u16,
+    /// DNS ports
+    #[nixos(port = "udp", open_firewall)]
+    dns_ports: Vec<u16>,
+    /// Metrics port, if enabled
+    #[nixos(port, open_firewall)]
+    metrics_port: Option<u16>,
+    /// Local administration port, never opened
+    #[nixos(port)]
+    admin_port: u16,
+    listeners: Vec<Listener>,
+    named_listeners: BTreeMap<String, Listener>,
+    backup: Option<Listener>,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct Node {
+    #[nixos(port, open_firewall)]
+    port: u16,
+    children: Vec<Node>,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct Plain {
+    port: u16,
+}
+
+#[test]
+fn test_port_option_types() {
+    let options = Settings::nixos_options();
+
+    assert!(options.contains("  httpPort = lib.mkOption {\n    type = types.port;\n"));
+    assert!(options.contains("  dnsPorts = lib.mkOption {\n    type = types.listOf types.port;\n"));
+    assert!(
+        options.contains("  metricsPort = lib.mkOption {\n    type = types.nullOr types.port;\n")
+    );
+    assert!(options.contains("  adminPort = lib.mkOption {\n    type = types.port;\n"));
+    // Without `port`, `u16` is still a plain integer
+    assert!(Plain::nixos_options().contains("type = types.int;"));
+}
+
+#[test]
+fn test_firewall_ports() {
+    assert_eq!(
+        Settings::nixos_firewall_ports("cfg"),
+        vec![
+            (PortProtocol::Tcp, "[ cfg.httpPort ]".to_string()),
+            (PortProtocol::Udp, "cfg.dnsPorts".to_string()),
+            (
+                PortProtocol::Tcp,
+                "lib.optional (cfg.metricsPort != null) cfg.metricsPort".to_string()
+            ),
+            (
+                PortProtocol::Tcp,
+                "lib.concatMap (v: [ v.port ]) cfg.listeners".to_string()
+            ),
+            (
+                PortProtocol::Tcp,
+                "lib.concatMap (v: [ v.port ]) (lib.attrValues cfg.namedListeners)".to_string()
+            ),
+            (
+                PortProtocol::Tcp,
+                "lib.optionals (cfg.backup != null) ([ cfg.backup.port ])".to_string()
+            ),
+        ]
+    );
+    assert!(Plain::nixos_firewall_ports("cfg").is_empty());
+}
+
+#[test]
+fn test_firewall_ports_in_flattened_struct() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Outer {
+        #[serde(flatten)]
+        listener: Listener,
+        name: String,
+    }
+
+    // Flattened fields are declared as a nested option under their name
+    assert!(Outer::nixos_options().contains("listener = lib.mkOption"));
+    assert_eq!(
+        Outer::nixos_firewall_ports("cfg"),
+        vec![(PortProtocol::Tcp, "[ cfg.listener.port ]".to_string())]
+    );
+}
+
+#[test]
+fn test_firewall_ports_of_recursive_types() {
+    // Only the outermost value is opened
+    assert_eq!(
+        Node::nixos_firewall_ports("cfg"),
+        vec![(PortProtocol::Tcp, "[ cfg.port ]".to_string())]
+    );
+}
+
+#[test]
+fn test_service_module_opens_firewall() {
+    let module = ServiceModule::<Settings>::new("services.app").generate();
+
+    assert!(module.contains(
+        "    openFirewall = mkOption {\n\
+         \x20     type = types.bool;\n\
+         \x20     default = false;\n"
+    ));
+    assert!(module.contains(
+        "    networking.firewall = mkIf cfg.openFirewall {\n\
+         \x20     allowedTCPPorts =\n\
+         \x20       [ cfg.httpPort ]\n\
+         \x20       ++ lib.optional (cfg.metricsPort != null) cfg.metricsPort\n"
+    ));
+    assert!(module.contains("      allowedUDPPorts = cfg.dnsPorts;\n"));
+}
+
+#[test]
+fn test_service_module_instances_open_firewall() {
+    let module = ServiceModule::<Settings>::new("services.app")
+        .instances()
+        .generate();
+
+    assert!(module.contains(
+        "      allowedUDPPorts = lib.concatMap (settings:\n\
+         \x20       settings.dnsPorts\n\
+         \x20     ) (lib.attrValues cfg.instances);\n"
+    ));
+}
+
+#[test]
+fn test_no_firewall_without_open_ports() {
+    let module = ServiceModule::<Plain>::new("services.app").generate();
+
+    assert!(!module.contains("openFirewall"));
+    assert!(!module.contains("networking.firewall"));
+}
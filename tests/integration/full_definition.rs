@@ -433,3 +433,41 @@ struct TaskList {
         full_def
     );
 }
+
+/// Enum fields are bound to their `types.enum`, and keep the enum's
+/// `#[default]` variant as their default.
+#[test]
+fn test_full_definition_enum_field_with_default() {
+    #[derive(Serialize, Deserialize, NixosType, Default)]
+    #[serde(rename_all = "lowercase")]
+    enum LogLevel {
+        Debug,
+        #[default]
+        Info,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Logging {
+        level: LogLevel,
+        fallback: Option<LogLevel>,
+    }
+
+    let full_def = Logging::nixos_type_full_definition();
+
+    assert!(
+        full_def.contains("  logLevelType = types.enum [ \"debug\" \"info\" ];\n"),
+        "LogLevel should be bound to its types.enum: {}",
+        full_def
+    );
+    assert!(
+        !full_def.contains("logLevelType = types.submodule"),
+        "{}",
+        full_def
+    );
+    assert!(
+        full_def.contains("type = logLevelType;\n      default = \"info\";\n"),
+        "{}",
+        full_def
+    );
+    assert!(full_def.contains("type = types.nullOr logLevelType;"));
+}
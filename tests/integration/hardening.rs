@@ -0,0 +1,133 @@
+//! Tests for `ServiceModule` hardening profiles and `#[nixos(writable)]`
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::{Hardening, NixosType, NixosTypeGenerator, ServiceModule};
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(rename_all = "camelCase")]
+struct Storage {
+    /// Directory for cached downloads
+    #[nixos(writable)]
+    cache_dir: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(rename_all = "camelCase")]
+struct Settings {
+    /// Directory holding the database
+    #[nixos(writable, default = "\"/var/lib/app\"")]
+    data_dir: PathBuf,
+    /// Directory for uploaded files
+    #[nixos(writable)]
+    upload_dir: String,
+    storage: Storage,
+    port: u16,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct Plain {
+    port: u16,
+}
+
+#[test]
+fn test_writable_paths() {
+    assert_eq!(
+        Settings::nixos_writable_paths(),
+        vec!["dataDir", "uploadDir", "storage.cacheDir"]
+    );
+    assert_eq!(
+        <Settings as NixosTypeGenerator>::nixos_writable_paths(),
+        Settings::nixos_writable_paths()
+    );
+    assert!(Plain::nixos_writable_paths().is_empty());
+}
+
+#[test]
+fn test_writable_paths_in_flattened_struct() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Outer {
+        #[serde(flatten)]
+        storage: Storage,
+        port: u16,
+    }
+
+    // Flattened fields are declared as a nested option under their name
+    assert!(Outer::nixos_options().contains("storage = lib.mkOption"));
+    assert_eq!(Outer::nixos_writable_paths(), vec!["storage.cacheDir"]);
+}
+
+#[test]
+fn test_no_hardening_by_default() {
+    let module = ServiceModule::<Plain>::new("services.app").generate();
+
+    assert!(!module.contains("DynamicUser"));
+    assert!(!module.contains("ProtectSystem"));
+    assert!(!module.contains("writablePaths"));
+}
+
+#[test]
+fn test_strict_hardening() {
+    let module = ServiceModule::<Plain>::new("services.app")
+        .hardening(Hardening::Strict)
+        .generate();
+
+    assert!(module.contains("      DynamicUser = true;\n"));
+    assert!(module.contains("      ProtectSystem = \"strict\";\n"));
+    assert!(module.contains("      PrivateTmp = true;\n"));
+    assert!(module.contains("      NoNewPrivileges = true;\n"));
+    assert!(module.contains(
+        "      SystemCallFilter = [ \"@system-service\" \"~@privileged\" \"~@resources\" ];\n"
+    ));
+    assert!(module.contains("      CapabilityBoundingSet = \"\";\n"));
+}
+
+#[test]
+fn test_basic_hardening() {
+    let module = ServiceModule::<Plain>::new("services.app")
+        .hardening(Hardening::Basic)
+        .generate();
+
+    assert!(module.contains("      ProtectSystem = \"full\";\n"));
+    assert!(module.contains("      NoNewPrivileges = true;\n"));
+    assert!(!module.contains("DynamicUser"));
+    assert!(!module.contains("SystemCallFilter"));
+}
+
+#[test]
+fn test_service_config_overrides_hardening() {
+    let module = ServiceModule::<Plain>::new("services.app")
+        .hardening(Hardening::Strict)
+        .service_config("CapabilityBoundingSet", "[ \"CAP_NET_BIND_SERVICE\" ]")
+        .generate();
+
+    assert!(!module.contains("CapabilityBoundingSet = \"\";"));
+    assert_eq!(module.matches("CapabilityBoundingSet").count(), 1);
+    assert!(module.contains("      CapabilityBoundingSet = [ \"CAP_NET_BIND_SERVICE\" ];\n"));
+}
+
+#[test]
+fn test_writable_paths_in_service() {
+    let module = ServiceModule::<Settings>::new("services.app")
+        .hardening(Hardening::Strict)
+        .generate();
+
+    assert!(module.contains(
+        "  writablePaths = settings: map toString (lib.filter (path: path != null) \
+         [ settings.dataDir settings.uploadDir settings.storage.cacheDir ]);\n"
+    ));
+    assert!(module.contains("      StateDirectory = stateDirectories cfg;\n"));
+    assert!(module.contains("      ReadWritePaths = readWritePaths cfg;\n"));
+}
+
+#[test]
+fn test_writable_paths_in_instances() {
+    let module = ServiceModule::<Settings>::new("services.app")
+        .instances()
+        .hardening(Hardening::Strict)
+        .generate();
+
+    assert!(module.contains("        StateDirectory = stateDirectories settings;\n"));
+    assert!(module.contains("        ReadWritePaths = readWritePaths settings;\n"));
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_nixos::NixosType;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[test]
 fn test_hashmap_string_keys() {
@@ -31,9 +31,44 @@ struct PortConfig {
     let options = PortConfig::nixos_options();
     assert!(options.contains("ports = lib.mkOption"));
 
-    // Integer-keyed maps are tricky in Nix since attribute names must be strings
-    // The implementation might use attrsOf or a different strategy
-    println!("Integer-keyed HashMap: {}", options);
+    // Attribute names are always strings, so integer keys get a check that
+    // each name parses as a u16
+    assert!(
+        options.contains("types.addCheck (types.attrsOf types.str)"),
+        "integer keys should add a check: {}",
+        options
+    );
+    assert!(
+        options.contains("builtins.match \"0|[1-9][0-9]*\" name != null"),
+        "{}",
+        options
+    );
+    assert!(
+        options.contains("builtins.fromJSON name <= 65535"),
+        "{}",
+        options
+    );
+}
+
+#[test]
+fn test_hashmap_signed_integer_keys() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Offsets {
+        offsets: BTreeMap<i8, u32>,
+        ids: HashMap<u64, String>,
+    }
+
+    let options = Offsets::nixos_options();
+    assert!(
+        options.contains("builtins.match \"0|-?[1-9][0-9]*\" name != null"),
+        "{}",
+        options
+    );
+    assert!(options.contains("n >= -128 && n <= 127"), "{}", options);
+
+    // 64-bit bounds do not fit a Nix integer, so only the format is checked
+    let ids = &options[options.find("ids = lib.mkOption").unwrap()..];
+    assert!(!ids.contains("fromJSON"), "{}", ids);
 }
 
 #[test]
@@ -47,7 +82,11 @@ struct FlagMap {
     assert!(options.contains("flags = lib.mkOption"));
 
     // Boolean keys are unusual but valid in Rust
-    println!("Boolean-keyed HashMap: {}", options);
+    assert!(
+        options.contains("name: builtins.elem name [ \"true\" \"false\" ]"),
+        "{}",
+        options
+    );
 }
 
 #[test]
@@ -68,7 +107,93 @@ struct EnvConfig {
     let options = EnvConfig::nixos_options();
     assert!(options.contains("environments = lib.mkOption"));
 
-    println!("Enum-keyed HashMap: {}", options);
+    // Unit-only enum keys become a submodule with one option per variant
+    assert!(
+        options.contains("types.submodule { options = { \"Development\" = lib.mkOption { type = types.nullOr types.str; default = null; };"),
+        "enum keys should become a submodule: {}",
+        options
+    );
+    assert!(
+        options.contains("\"Staging\" = lib.mkOption"),
+        "{}",
+        options
+    );
+    assert!(
+        options.contains("\"Production\" = lib.mkOption"),
+        "{}",
+        options
+    );
+    assert!(!options.contains("types.attrsOf"), "{}", options);
+
+    assert_eq!(
+        Environment::nixos_enum_variants(),
+        Some(&["Development", "Staging", "Production"][..])
+    );
+}
+
+#[test]
+fn test_hashmap_renamed_enum_keys_with_named_values() {
+    #[derive(Serialize, Deserialize, NixosType, Hash, Eq, PartialEq)]
+    #[serde(rename_all = "kebab-case")]
+    enum Region {
+        EuWest,
+        UsEast,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Quota {
+        limit: u32,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Quotas {
+        quotas: HashMap<Region, Quota>,
+    }
+
+    let full = Quotas::nixos_type_full_definition();
+    assert!(
+        full.contains(
+            "\"eu-west\" = lib.mkOption { type = types.nullOr quotaType; default = null; };"
+        ),
+        "{}",
+        full
+    );
+    assert!(full.contains("\"us-east\" = lib.mkOption"), "{}", full);
+}
+
+#[test]
+fn test_hashmap_newtype_keys() {
+    #[derive(Serialize, Deserialize, NixosType, Hash, Eq, PartialEq)]
+    struct PortNumber(u16);
+
+    #[derive(Serialize, Deserialize, NixosType, Hash, Eq, PartialEq)]
+    struct Hostname(String);
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Upstreams {
+        by_port: HashMap<PortNumber, String>,
+        by_host: HashMap<Hostname, String>,
+    }
+
+    assert!(PortNumber::nixos_key_check()
+        .unwrap()
+        .contains("builtins.fromJSON name <= 65535"));
+    assert_eq!(Hostname::nixos_key_check(), None);
+
+    let options = Upstreams::nixos_options();
+    let by_port = &options[options.find("by_port").unwrap()..options.find("by_host").unwrap()];
+    assert!(
+        by_port.contains("types.addCheck (types.attrsOf types.str)"),
+        "{}",
+        by_port
+    );
+
+    let by_host = &options[options.find("by_host").unwrap()..];
+    assert!(
+        by_host.contains("type = types.attrsOf types.str;"),
+        "{}",
+        by_host
+    );
 }
 
 #[test]
@@ -0,0 +1,75 @@
+//! Tests for `#[nixos(deny(...))]`.
+//!
+//! Violations are compile errors, so these cover the ways to satisfy each
+//! lint.
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::NixosType;
+
+#[derive(Default, Serialize, Deserialize, NixosType)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    #[default]
+    Info,
+    Debug,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+enum Backend {
+    Memory,
+    Disk,
+}
+
+/// Storage settings
+#[derive(Serialize, Deserialize, NixosType)]
+#[nixos(deny(missing_description, missing_default, untyped_fallback))]
+struct Storage {
+    /// Storage backend
+    #[nixos(default = "\"memory\"")]
+    backend: Backend,
+    /// Size limit in bytes
+    limit: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[nixos(deny(missing_description, missing_default, untyped_fallback))]
+struct Config {
+    /// Address to listen on
+    #[nixos(default = "\"127.0.0.1\"")]
+    host: String,
+    #[nixos(description = "Port to listen on", default = "8080")]
+    port: u16,
+    /// Log level
+    level: Level,
+    /// Worker threads, chosen by the program when unset
+    #[serde(default)]
+    workers: u32,
+    /// Extra settings passed through verbatim
+    #[nixos(type = "types.attrs", default = "{ }")]
+    #[serde(default)]
+    extra: serde_json::Map<String, serde_json::Value>,
+    /// Storage settings
+    #[nixos(default = "{ }")]
+    storage: Storage,
+    #[nixos(skip)]
+    #[serde(skip)]
+    #[allow(dead_code)]
+    runtime_state: Option<fn()>,
+}
+
+#[test]
+fn test_denied_lints_accept_complete_options() {
+    let options = Config::nixos_options();
+    assert!(options.contains("description = \"Address to listen on\";"));
+    assert!(options.contains("description = \"Port to listen on\";"));
+    assert!(options.contains("default = \"info\";"), "{}", options);
+    assert!(options.contains("type = types.attrs;"));
+    assert!(!options.contains("runtimeState") && !options.contains("runtime_state"));
+}
+
+#[test]
+fn test_has_default() {
+    assert!(Level::nixos_has_default());
+    assert!(!Backend::nixos_has_default());
+    assert!(!Storage::nixos_has_default());
+}
@@ -454,3 +454,22 @@ fn test_kebab_case_with_default_null() {
         timeout_block
     );
 }
+
+/// A branch condition.
+#[derive(Serialize, Deserialize, NixosType)]
+struct DocumentedCondition {
+    field: String,
+}
+
+#[test]
+fn test_generator_comments_type_description() {
+    let reg = type_registration!(DocumentedCondition);
+    assert_eq!(reg.description, Some("A branch condition."));
+
+    let nix = NixosModuleGenerator::new().register(reg).generate();
+    assert!(
+        nix.contains("  # A branch condition.\n  documentedConditionType = types.submodule {"),
+        "{}",
+        nix
+    );
+}
@@ -0,0 +1,109 @@
+//! Tests for the compile-time checks of Nix snippets in `#[nixos(...)]`.
+//!
+//! Invalid snippets are compile errors, so these only cover snippets that
+//! must keep compiling and come out verbatim.
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::NixosType;
+use std::collections::HashMap;
+use std::num::NonZeroU16;
+use std::path::PathBuf;
+
+#[test]
+fn test_literal_defaults_matching_field_types() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Config {
+        #[nixos(default = "\"localhost\"")]
+        host: String,
+        #[nixos(default = "65535")]
+        port: u16,
+        #[nixos(default = "-128")]
+        offset: i8,
+        #[nixos(default = "(-1)")]
+        delta: i64,
+        #[nixos(default = "0.5")]
+        ratio: f32,
+        #[nixos(default = "2")]
+        scale: f64,
+        #[nixos(default = "true")]
+        enabled: bool,
+        #[nixos(default = "1")]
+        workers: NonZeroU16,
+        #[nixos(default = "/var/lib/app")]
+        state_dir: PathBuf,
+        #[nixos(default = "\"/etc/app\"")]
+        config_dir: PathBuf,
+        #[nixos(default = "[ \"a\" ]")]
+        tags: Vec<String>,
+        #[nixos(default = "{ }")]
+        labels: HashMap<String, String>,
+        #[nixos(default = "null")]
+        comment: Option<String>,
+    }
+
+    let options = Config::nixos_options();
+    for expected in [
+        "default = \"localhost\";",
+        "default = 65535;",
+        "default = -128;",
+        "default = (-1);",
+        "default = 0.5;",
+        "default = 2;",
+        "default = true;",
+        "default = 1;",
+        "default = /var/lib/app;",
+        "default = \"/etc/app\";",
+        "default = [ \"a\" ];",
+        "default = { };",
+        "default = null;",
+    ] {
+        assert!(
+            options.contains(expected),
+            "missing {}:\n{}",
+            expected,
+            options
+        );
+    }
+}
+
+#[test]
+fn test_expressions_are_not_type_checked() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Config {
+        // Only the syntax of non-literal expressions is checked
+        #[nixos(default = "config.networking.hostName")]
+        host: String,
+        #[nixos(default = "if config.services.nginx.enable then 443 else 8443")]
+        port: u16,
+        #[nixos(example = "lib.literalExpression \"pkgs.hello\"")]
+        package: String,
+        #[nixos(
+            type = "types.nullOr types.str",
+            default = "null",
+            apply = "v: if v == null then \"\" else v",
+            visible = "false"
+        )]
+        overridden: String,
+    }
+
+    let options = Config::nixos_options();
+    assert!(options.contains("default = config.networking.hostName;"));
+    assert!(options.contains("default = if config.services.nginx.enable then 443 else 8443;"));
+    assert!(options.contains("example = lib.literalExpression \"pkgs.hello\";"));
+    assert!(options.contains("apply = v: if v == null then \"\" else v;"));
+    assert!(options.contains("visible = false;"));
+}
+
+#[test]
+fn test_rust_defaulted_fields_accept_null() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Config {
+        // Rust-defaulted fields are `types.nullOr`, so `null` fits
+        #[serde(default)]
+        #[nixos(example = "null")]
+        retries: u32,
+    }
+
+    let options = Config::nixos_options();
+    assert!(options.contains("example = null;"), "{}", options);
+}
@@ -0,0 +1,92 @@
+//! Tests for NixOS option names that differ from serde names
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::{NixosType, ServiceModule};
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct Pool {
+    max_size: u32,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[nixos(rename_all = "camelCase")]
+#[nixos(assert(
+    expr = "self.maxConnections > 0",
+    message = "maxConnections must be positive"
+))]
+struct ServerSettings {
+    /// Limit for [`idle_timeout`](Self::idle_timeout)
+    max_connections: u32,
+    idle_timeout: Option<u64>,
+    #[serde(rename = "db-pool")]
+    database_pool: Pool,
+    #[nixos(rename = "listenAddress")]
+    bind_address: String,
+    #[serde(default)]
+    worker_threads: u32,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(rename_all = "kebab-case")]
+#[nixos(rename_all = "camelCase")]
+struct Renamed {
+    log_level: String,
+}
+
+#[test]
+fn test_nixos_rename_all_renames_options() {
+    let options = ServerSettings::nixos_options();
+
+    assert!(options.contains("maxConnections = lib.mkOption"));
+    assert!(options.contains("idleTimeout = lib.mkOption"));
+    assert!(options.contains("workerThreads = lib.mkOption"));
+    assert!(!options.contains("max_connections"));
+    assert_eq!(
+        ServerSettings::nixos_field_name("max_connections"),
+        Some("maxConnections")
+    );
+}
+
+#[test]
+fn test_field_renames_win_over_rename_all() {
+    let options = ServerSettings::nixos_options();
+
+    assert!(options.contains("db-pool = lib.mkOption"));
+    assert!(options.contains("listenAddress = lib.mkOption"));
+}
+
+#[test]
+fn test_settings_function_maps_back_to_serde_names() {
+    let function = ServerSettings::nixos_to_settings_function();
+
+    assert!(function.contains("  max_connections = cfg.maxConnections;\n"));
+    assert!(function.contains("  idle_timeout = cfg.idleTimeout;\n"));
+    assert!(function.contains("  db-pool = poolToSettings cfg.db-pool;\n"));
+    assert!(function.contains("  bind_address = cfg.listenAddress;\n"));
+    assert!(function.contains(
+        "lib.optionalAttrs (cfg.workerThreads != null) { worker_threads = cfg.workerThreads; }"
+    ));
+
+    assert_eq!(
+        Renamed::nixos_to_settings_function(),
+        "cfg: {\n  log-level = cfg.logLevel;\n}"
+    );
+}
+
+#[test]
+fn test_option_names_in_links_and_assertions() {
+    let options = ServerSettings::nixos_options_at("services.server");
+    assert!(options.contains("{option}`services.server.idleTimeout`"));
+
+    let assertions = ServerSettings::nixos_assertions("services.server");
+    assert!(assertions.contains("let self = config.services.server; in self.maxConnections > 0"));
+}
+
+#[test]
+fn test_service_module_writes_serde_names() {
+    let module = ServiceModule::<ServerSettings>::new("services.server").generate();
+
+    assert!(module.contains("    maxConnections = lib.mkOption {\n"));
+    assert!(module.contains("  settings = serverSettingsToSettings cfg;\n"));
+    assert!(module.contains("    max_connections = cfg.maxConnections;\n"));
+}
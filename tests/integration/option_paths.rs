@@ -0,0 +1,67 @@
+//! Tests for `#[nixos(path = "...")]`
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::{NixosType, ServiceModule};
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[nixos(assert(expr = "self.tls.cert != \"\"", message = "a certificate is required"))]
+struct Server {
+    /// Certificate matching [`tls_key`](Self::tls_key)
+    #[nixos(path = "tls.cert")]
+    tls_cert: String,
+    #[nixos(path = "tls.key")]
+    #[serde(default)]
+    tls_key: String,
+    #[nixos(path = "listen.port", min = 1)]
+    port: u16,
+}
+
+#[test]
+fn test_path_nests_options() {
+    let options = Server::nixos_options();
+
+    assert!(options.contains("  tls.cert = lib.mkOption {\n    type = types.str;\n"));
+    assert!(options.contains("  tls.key = lib.mkOption {\n    type = types.nullOr types.str;\n"));
+    assert!(options.contains("  listen.port = lib.mkOption {\n"));
+    assert!(!options.contains("tls_cert"));
+    assert_eq!(Server::nixos_field_name("tls_key"), Some("tls.key"));
+}
+
+#[test]
+fn test_links_and_assertions_use_paths() {
+    let options = Server::nixos_options_at("services.server");
+    assert!(options.contains("{option}`services.server.tls.key`"));
+
+    let assertions = Server::nixos_assertions("services.server");
+    assert!(assertions.contains("let self = config.services.server; in self.tls.cert != \"\""));
+}
+
+#[test]
+fn test_settings_function_flattens_paths() {
+    assert_eq!(
+        Server::nixos_to_settings_function(),
+        "cfg: {\n  tls_cert = cfg.tls.cert;\n  port = cfg.listen.port;\n}\n\
+         // lib.optionalAttrs (cfg.tls.key != null) { tls_key = cfg.tls.key; }"
+    );
+}
+
+#[test]
+fn test_validation_reports_option_path() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    #[nixos(validate)]
+    struct Listener {
+        #[nixos(path = "listen.port", min = 1)]
+        port: u16,
+    }
+
+    let errors = Listener { port: 0 }.nixos_validate().unwrap_err();
+    assert_eq!(errors[0].path, "listen.port");
+}
+
+#[test]
+fn test_service_module_with_paths() {
+    let module = ServiceModule::<Server>::new("services.server").generate();
+
+    assert!(module.contains("    tls.cert = lib.mkOption {\n"));
+    assert!(module.contains("  settings = serverToSettings cfg;\n"));
+}
@@ -0,0 +1,94 @@
+//! Tests for `#[nixos(secret)]`
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::{NixosType, ServiceModule};
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct Database {
+    host: String,
+    /// Password of the database user
+    #[nixos(secret)]
+    password: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[nixos(rename_all = "camelCase")]
+struct Settings {
+    /// Token for the upstream API
+    #[nixos(secret, example = "\"/run/secrets/api-token\"")]
+    #[serde(default)]
+    api_token: String,
+    database: Database,
+    #[nixos(secret, path = "tls.key")]
+    tls_key: Option<String>,
+}
+
+#[test]
+fn test_secret_file_option() {
+    let options = Settings::nixos_options();
+
+    assert!(options.contains(
+        "  apiTokenFile = lib.mkOption {\n    type = types.nullOr types.path;\n    description = ''\n      Token for the upstream API\n\n      Path of a file containing the value"
+    ));
+    assert!(options.contains("    example = \"/run/secrets/api-token\";\n  };\n"));
+    assert!(options.contains("  tls.keyFile = lib.mkOption {\n"));
+    assert!(!options.contains("apiToken ="));
+    assert_eq!(
+        Settings::nixos_field_name("api_token"),
+        Some("apiTokenFile")
+    );
+
+    let database = Database::nixos_options();
+    assert!(database.contains("  passwordFile = lib.mkOption {\n"));
+}
+
+#[test]
+fn test_secrets_are_left_out_of_settings() {
+    assert_eq!(
+        Database::nixos_to_settings_function(),
+        "cfg: {\n  host = cfg.host;\n}"
+    );
+    assert!(!Settings::nixos_to_settings_function().contains("apiToken"));
+}
+
+#[test]
+fn test_secret_credential_names() {
+    assert_eq!(
+        Settings::nixos_secrets(),
+        vec!["apiToken", "database.password", "tls.key"]
+    );
+}
+
+#[test]
+fn test_service_module_loads_credentials() {
+    let module = ServiceModule::<Settings>::new("services.app").generate();
+    assert!(module.contains(
+        "        LoadCredential =\n\
+         \x20         lib.optional (cfg.apiTokenFile != null) \"apiToken:${cfg.apiTokenFile}\"\n\
+         \x20         ++ lib.optional (cfg.database.passwordFile != null) \"database.password:${cfg.database.passwordFile}\"\n\
+         \x20         ++ lib.optional (cfg.tls.keyFile != null) \"tls.key:${cfg.tls.keyFile}\";\n"
+    ));
+
+    let nested = ServiceModule::<Settings>::new("services.app")
+        .settings_option("settings")
+        .generate();
+    assert!(nested.contains("\"apiToken:${cfg.settings.apiTokenFile}\""));
+}
+
+#[test]
+fn test_load_secrets_from_credentials() {
+    let dir = std::env::temp_dir().join(format!("serde-nixos-secrets-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("apiToken"), "token\n").unwrap();
+    std::fs::write(dir.join("database.password"), "hunter2").unwrap();
+
+    let mut settings: Settings =
+        serde_json::from_str(r#"{"database": {"host": "localhost"}, "tls_key": null}"#).unwrap();
+    settings.nixos_load_secrets_from(&dir, "").unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(settings.api_token, "token");
+    assert_eq!(settings.database.password.as_deref(), Some("hunter2"));
+    // Unset file options leave the field alone
+    assert_eq!(settings.tls_key, None);
+}
@@ -5,6 +5,10 @@
 //! need to interpret. If a future serde release adds new attributes, the
 //! catch-all parser should consume them gracefully.
 
+// Custom `with` adapters trigger the "set `#[nixos(type)]`" hint, which is
+// reported as a deprecation warning; these tests only check parsing.
+#![allow(deprecated)]
+
 use serde::{Deserialize, Serialize};
 use serde_nixos::NixosType;
 
@@ -2,6 +2,10 @@
 //!
 //! This tests the bug fix for parsing #[serde(default = "function")] pattern
 
+// Custom `with` adapters trigger the "set `#[nixos(type)]`" hint, which is
+// reported as a deprecation warning; the parse-compat test only checks parsing.
+#![allow(deprecated)]
+
 use serde::{Deserialize, Serialize};
 use serde_nixos::NixosType;
 
@@ -212,3 +216,82 @@ struct ContainerBoundConfig {
     let nixos_type = ContainerBoundConfig::nixos_type();
     assert!(!nixos_type.is_empty());
 }
+
+/// Return the `lib.mkOption { ... }` block for a given option name.
+fn option_block<'a>(options: &'a str, name: &str) -> &'a str {
+    let start = options
+        .find(&format!("{} = lib.mkOption", name))
+        .unwrap_or_else(|| panic!("option {} not found in:\n{}", name, options));
+    let rest = &options[start..];
+    &rest[..rest.find("};").unwrap()]
+}
+
+#[test]
+fn test_serde_default_field_becomes_nullable() {
+    let options = ServerConfig::nixos_options();
+
+    let port = option_block(&options, "port");
+    assert!(port.contains("type = types.nullOr types.int;"), "{}", port);
+    assert!(port.contains("default = null;"), "{}", port);
+    assert!(
+        port.contains(
+            "Port with default function\n\n      If unset, the program's built-in default is used."
+        ),
+        "{}",
+        port
+    );
+
+    let debug = option_block(&options, "debug");
+    assert!(
+        debug.contains("type = types.nullOr types.bool;"),
+        "{}",
+        debug
+    );
+    assert!(debug.contains("default = null;"), "{}", debug);
+}
+
+#[test]
+fn test_nixos_optional_field_becomes_nullable() {
+    #[derive(Debug, Serialize, Deserialize, NixosType)]
+    struct OptionalConfig {
+        #[nixos(optional)]
+        workers: Vec<String>,
+    }
+
+    let options = OptionalConfig::nixos_options();
+    let workers = option_block(&options, "workers");
+    assert!(
+        workers.contains("type = types.nullOr (types.listOf types.str);"),
+        "{}",
+        workers
+    );
+    assert!(workers.contains("default = null;"), "{}", workers);
+    assert!(
+        workers.contains("description = \"If unset, the program's built-in default is used.\";"),
+        "{}",
+        workers
+    );
+}
+
+#[test]
+fn test_serde_default_with_explicit_nixos_default_stays_typed() {
+    #[derive(Debug, Serialize, Deserialize, NixosType)]
+    struct ExplicitConfig {
+        #[serde(default = "default_port")]
+        #[nixos(default = "8080")]
+        port: u16,
+
+        #[serde(default)]
+        name: Option<String>,
+    }
+
+    let options = ExplicitConfig::nixos_options();
+    let port = option_block(&options, "port");
+    assert!(port.contains("type = types.int;"), "{}", port);
+    assert!(port.contains("default = 8080;"), "{}", port);
+    assert!(!port.contains("built-in default"), "{}", port);
+
+    let name = option_block(&options, "name");
+    assert!(name.contains("type = types.nullOr types.str;"), "{}", name);
+    assert!(!name.contains("built-in default"), "{}", name);
+}
@@ -0,0 +1,187 @@
+//! Tests for `serde_with` adapters and the `#[nixos(type)]` override.
+//!
+//! The adapters are recognised by name, so a small stand-in `serde_with`
+//! module is enough; `#[serde_as(...)]` is accepted as an inert attribute.
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::NixosType;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[allow(dead_code)]
+mod serde_with {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::marker::PhantomData;
+    use std::str::FromStr;
+
+    pub struct DisplayFromStr;
+    pub struct CommaSeparator;
+    pub struct SpaceSeparator;
+    pub struct DurationSeconds<F = u64>(PhantomData<F>);
+    pub struct OneOrMany<T>(PhantomData<T>);
+    pub struct StringWithSeparator<Sep, T>(PhantomData<(Sep, T)>);
+
+    /// Stand-in for `serde_with::As`, only implementing `DisplayFromStr`.
+    pub struct As<T>(PhantomData<T>);
+
+    impl<T> As<T> {
+        pub fn serialize<S: Serializer, V: Display>(
+            value: &V,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(value)
+        }
+
+        pub fn deserialize<'de, D, V>(deserializer: D) -> Result<V, D::Error>
+        where
+            D: Deserializer<'de>,
+            V: FromStr,
+            V::Err: Display,
+        {
+            let s = String::deserialize(deserializer)?;
+            s.parse().map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+fn option_type<'a>(options: &'a str, name: &str) -> &'a str {
+    let start = options
+        .find(&format!("{} = lib.mkOption", name))
+        .unwrap_or_else(|| panic!("missing option {}:\n{}", name, options));
+    let rest = &options[start..];
+    let type_start = rest.find("type = ").unwrap() + "type = ".len();
+    let type_end = rest[type_start..].find(";\n").unwrap() + type_start;
+    &rest[type_start..type_end]
+}
+
+#[test]
+fn test_serde_as_scalar_adapters() {
+    #[derive(NixosType)]
+    #[allow(dead_code)]
+    struct Cfg {
+        #[serde_as(as = "DisplayFromStr")]
+        port: u16,
+        #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+        timeout: Duration,
+        #[serde_as(as = "DurationSeconds<f64>")]
+        grace: Duration,
+        #[serde_as(as = "Option<DisplayFromStr>")]
+        limit: Option<u32>,
+    }
+
+    let options = Cfg::nixos_options();
+    assert_eq!(option_type(&options, "port"), "types.str");
+    assert_eq!(option_type(&options, "timeout"), "types.ints.unsigned");
+    assert_eq!(option_type(&options, "grace"), "types.float");
+    assert_eq!(option_type(&options, "limit"), "types.nullOr types.str");
+}
+
+#[test]
+fn test_serde_as_one_or_many() {
+    #[derive(NixosType)]
+    #[allow(dead_code)]
+    struct Cfg {
+        #[serde_as(as = "OneOrMany<_>")]
+        hosts: Vec<String>,
+        #[serde_as(as = "OneOrMany<DisplayFromStr>")]
+        ports: Vec<u16>,
+        #[serde_as(as = "OneOrMany<_>")]
+        ids: Vec<Option<u32>>,
+    }
+
+    let options = Cfg::nixos_options();
+    assert_eq!(
+        option_type(&options, "hosts"),
+        "types.either types.str (types.listOf types.str)"
+    );
+    assert_eq!(
+        option_type(&options, "ports"),
+        "types.either types.str (types.listOf types.str)"
+    );
+    assert_eq!(
+        option_type(&options, "ids"),
+        "types.either (types.nullOr types.int) (types.listOf (types.nullOr types.int))"
+    );
+}
+
+#[test]
+fn test_serde_as_string_with_separator_and_collections() {
+    #[derive(NixosType)]
+    #[allow(dead_code)]
+    struct Cfg {
+        #[serde_as(as = "StringWithSeparator::<CommaSeparator, String>")]
+        tags: Vec<String>,
+        #[serde_as(as = "StringWithSeparator::<SpaceSeparator, String>")]
+        words: Vec<String>,
+        #[serde_as(as = "Vec<DisplayFromStr>")]
+        ports: Vec<u16>,
+        #[serde_as(as = "HashMap<_, DurationSeconds>")]
+        timeouts: HashMap<String, Duration>,
+    }
+
+    let options = Cfg::nixos_options();
+    assert_eq!(option_type(&options, "tags"), "types.commas");
+    assert_eq!(option_type(&options, "words"), "types.str");
+    assert_eq!(option_type(&options, "ports"), "types.listOf types.str");
+    assert_eq!(
+        option_type(&options, "timeouts"),
+        "types.attrsOf types.ints.unsigned"
+    );
+}
+
+#[test]
+fn test_expanded_serde_as_with_path() {
+    // `#[serde_as]` rewrites `as = "T"` into this `with` form
+    #[derive(Debug, PartialEq, Serialize, Deserialize, NixosType)]
+    struct Cfg {
+        #[serde(with = "serde_with::As::<serde_with::DisplayFromStr>")]
+        port: u16,
+    }
+
+    let options = Cfg::nixos_options();
+    assert_eq!(option_type(&options, "port"), "types.str");
+
+    let json = serde_json::to_string(&Cfg { port: 8080 }).unwrap();
+    assert_eq!(json, r#"{"port":"8080"}"#);
+    let parsed: Cfg = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, Cfg { port: 8080 });
+}
+
+#[test]
+fn test_nixos_type_override() {
+    mod hex_u32 {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(value: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&format!("{:x}", value))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+            let s = String::deserialize(deserializer)?;
+            u32::from_str_radix(&s, 16).map_err(serde::de::Error::custom)
+        }
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Cfg {
+        #[serde(with = "hex_u32")]
+        #[nixos(type = "types.strMatching \"[0-9a-f]+\"")]
+        mask: u32,
+        #[nixos(type = "types.port")]
+        port: u16,
+        #[nixos(type = "types.nullOr types.port")]
+        fallback_port: Option<u16>,
+    }
+
+    let options = Cfg::nixos_options();
+    assert_eq!(
+        option_type(&options, "mask"),
+        "types.strMatching \"[0-9a-f]+\""
+    );
+    assert_eq!(option_type(&options, "port"), "types.port");
+    assert_eq!(
+        option_type(&options, "fallback_port"),
+        "types.nullOr types.port"
+    );
+}
@@ -0,0 +1,278 @@
+//! Tests for `ServiceModule`
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::{NixosType, ServiceModule};
+
+#[derive(Serialize, Deserialize, NixosType)]
+enum LogLevel {
+    Info,
+    Debug,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct Listener {
+    /// Port to listen on
+    port: u16,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(rename_all = "camelCase")]
+#[nixos(assert(expr = "self.workers > 0", message = "workers must be positive"))]
+struct AppSettings {
+    /// Listeners, the first one serves [`log_level`](Self::log_level)
+    listeners: Vec<Listener>,
+    /// Logging verbosity
+    log_level: LogLevel,
+    workers: u32,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct Tree {
+    value: String,
+    children: Vec<Tree>,
+}
+
+#[test]
+fn test_flat_service_module() {
+    let module = ServiceModule::<AppSettings>::new("services.my-app").generate();
+
+    assert!(module.starts_with("{ config, lib, pkgs, utils, ... }:\n"));
+    assert!(module.contains("  cfg = config.services.my-app;\n"));
+    // Dependencies are bound in the `let` block, the settings type itself
+    // is declared inline
+    assert!(module.contains("  listenerType = types.submodule {\n"));
+    assert!(module.contains("  logLevelType = types.enum [ \"Info\" \"Debug\" ];\n"));
+    assert!(!module.contains("appSettingsType"));
+    assert!(module.contains(
+        "  appSettingsToSettings = cfg: {\n\
+         \x20   listeners = map (v: listenerToSettings v) cfg.listeners;\n"
+    ));
+    assert!(module.contains(
+        "  settings = appSettingsToSettings cfg;\n\
+         \x20 configFile = pkgs.writeText \"my-app.json\" (builtins.toJSON settings);\n"
+    ));
+
+    assert!(
+        module.contains("  options.services.my-app = {\n    enable = mkEnableOption \"my-app\";\n")
+    );
+    assert!(module.contains("      default = pkgs.my-app;\n"));
+    assert!(module
+        .contains("    listeners = lib.mkOption {\n      type = types.listOf listenerType;\n"));
+    // Doc comment links use the full option path
+    assert!(module.contains("{option}`services.my-app.logLevel`"));
+
+    assert!(module.contains(
+        "    assertions = [\n      {\n        assertion = let self = config.services.my-app; in self.workers > 0;\n"
+    ));
+    assert!(module.contains("    systemd.services.my-app = {\n"));
+    assert!(module.contains(
+        "        ExecStart = \"${cfg.package}/bin/my-app ${utils.escapeSystemdExecArgs [ \"${configFile}\" ]}\";\n"
+    ));
+}
+
+#[test]
+fn test_service_module_options() {
+    let module = ServiceModule::<AppSettings>::new("services.my-app")
+        .header("Generated, do not edit.")
+        .description("My application")
+        .package("pkgs.my-app-server")
+        .binary("my-app-server")
+        .args([
+            "--config",
+            "${configFile}",
+            "--workers=${toString cfg.workers}",
+        ])
+        .service_config("Restart", "\"on-failure\"")
+        .generate();
+
+    assert!(module.starts_with("# Generated, do not edit.\n\n{ config, lib, pkgs, utils, ... }:\n"));
+    assert!(module.contains("    enable = mkEnableOption \"My application\";\n"));
+    assert!(module.contains("      description = \"My application\";\n"));
+    assert!(module.contains("      default = pkgs.my-app-server;\n"));
+    assert!(module.contains(
+        "        ExecStart = \"${cfg.package}/bin/my-app-server ${utils.escapeSystemdExecArgs [ \"--config\" \"${configFile}\" \"--workers=${toString cfg.workers}\" ]}\";\n\
+         \x20       Restart = \"on-failure\";\n"
+    ));
+}
+
+#[test]
+fn test_service_module_config_path() {
+    let etc = ServiceModule::<AppSettings>::new("services.my-app")
+        .config_path("/etc/my-app/config.json")
+        .generate();
+    assert!(etc.contains("    environment.etc.\"my-app/config.json\".source = configFile;\n"));
+    assert!(etc.contains("${utils.escapeSystemdExecArgs [ \"/etc/my-app/config.json\" ]}"));
+
+    let other = ServiceModule::<AppSettings>::new("services.my-app")
+        .config_path("/var/lib/my-app/config.json")
+        .generate();
+    assert!(other.contains(
+        "    systemd.tmpfiles.rules = [ \"L+ /var/lib/my-app/config.json - - - - ${configFile}\" ];\n"
+    ));
+}
+
+#[test]
+fn test_service_module_settings_option() {
+    let module = ServiceModule::<AppSettings>::new("services.my-app")
+        .settings_option("settings")
+        .generate();
+
+    assert!(module.contains("  appSettingsType = types.submodule {\n"));
+    assert!(module.contains("    settings = mkOption {\n      type = appSettingsType;\n"));
+    assert!(module.contains("  settings = appSettingsToSettings cfg.settings;\n"));
+    assert!(module.contains("let self = config.services.my-app.settings; in self.workers > 0"));
+}
+
+#[test]
+fn test_service_module_recursive_type() {
+    let module = ServiceModule::<Tree>::new("services.tree")
+        .settings_option("settings")
+        .generate();
+
+    assert_eq!(
+        module.matches("  treeType = types.submodule {\n").count(),
+        1
+    );
+    assert!(module.contains("type = types.listOf treeType;"));
+    assert!(module.contains("    children = map (v: treeToSettings v) cfg.children;\n"));
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[nixos(assert(expr = "self.port > 0", message = "port must be set"))]
+struct Worker {
+    /// Name reported by the worker
+    #[nixos(default_from_name)]
+    name: String,
+    port: u16,
+    #[nixos(secret)]
+    token: Option<String>,
+}
+
+#[test]
+fn test_default_from_name() {
+    let options = Worker::nixos_options();
+    assert!(options.contains("    default = name;\n"));
+
+    let definition = Worker::nixos_type_definition();
+    assert!(definition.contains("workerType = types.submodule ({ name, ... }: {\n  options = {\n"));
+    assert!(definition.ends_with("  };\n});\n"));
+    assert!(Listener::nixos_type_definition().contains("listenerType = types.submodule {\n"));
+
+    // Outside a submodule, `name` is the service name
+    let module = ServiceModule::<Worker>::new("services.worker").generate();
+    assert!(module.contains("  name = \"worker\";\n"));
+}
+
+#[test]
+fn test_service_module_instances() {
+    let module = ServiceModule::<Worker>::new("services.worker")
+        .instances()
+        .args([
+            "--config",
+            "${configFile}",
+            "--port=${toString settings.port}",
+        ])
+        .generate();
+
+    assert!(module.contains("  workerType = types.submodule ({ name, ... }: {\n"));
+    assert!(module.contains(
+        "  instanceConfigFile = instance: settings: pkgs.writeText \"worker-${instance}.json\" (builtins.toJSON (workerToSettings settings));\n"
+    ));
+    assert!(!module.contains("  name = \"worker\";"));
+    assert!(module.contains(
+        "    instances = mkOption {\n      type = types.attrsOf workerType;\n      default = { };\n"
+    ));
+
+    assert!(module
+        .contains("    assertions = lib.concatLists (lib.mapAttrsToList (instance: settings: [\n"));
+    assert!(module
+        .contains("let self = config.services.worker.instances.${instance}; in self.port > 0;"));
+    assert!(
+        module.contains("message = \"services.worker.instances.${instance}: port must be set\";")
+    );
+
+    assert!(module.contains(
+        "    systemd.services = lib.mapAttrs' (instance: settings:\n\
+         \x20     let\n\
+         \x20       configFile = instanceConfigFile instance settings;\n\
+         \x20     in\n\
+         \x20     lib.nameValuePair \"worker-${instance}\" {\n\
+         \x20       description = \"worker (${instance})\";\n"
+    ));
+    assert!(module.contains(
+        "          ExecStart = \"${cfg.package}/bin/worker ${utils.escapeSystemdExecArgs [ \"--config\" \"${configFile}\" \"--port=${toString settings.port}\" ]}\";\n\
+         \x20         LoadCredential =\n\
+         \x20           lib.optional (settings.tokenFile != null) \"token:${settings.tokenFile}\";\n"
+    ));
+    assert!(module.contains("      }) cfg.instances;\n"));
+}
+
+#[test]
+fn test_service_module_instances_config_path() {
+    let etc = ServiceModule::<Worker>::new("services.worker")
+        .instances()
+        .config_path("/etc/worker/${instance}.json")
+        .generate();
+    assert!(etc.contains(
+        "lib.nameValuePair \"worker/${instance}.json\" { source = instanceConfigFile instance settings; }"
+    ));
+    assert!(etc.contains("${utils.escapeSystemdExecArgs [ \"/etc/worker/${instance}.json\" ]}"));
+
+    let other = ServiceModule::<Worker>::new("services.worker")
+        .instances()
+        .config_path("/var/lib/worker/${instance}.json")
+        .generate();
+    assert!(other.contains(
+        "\"L+ /var/lib/worker/${instance}.json - - - - ${instanceConfigFile instance settings}\""
+    ));
+}
+
+#[test]
+fn test_service_module_timer() {
+    let module = ServiceModule::<AppSettings>::new("services.my-app")
+        .timer("daily")
+        .generate();
+
+    assert!(module.contains(
+        "    startAt = mkOption {\n\
+         \x20     type = types.either types.str (types.listOf types.str);\n\
+         \x20     default = \"daily\";\n"
+    ));
+    assert!(module.contains("    persistent = mkOption {\n      type = types.bool;\n"));
+    assert!(module.contains("    randomizedDelaySec = mkOption {\n      type = types.str;\n"));
+    // The job is started by the timer, not at boot
+    assert!(!module.contains("multi-user.target"));
+    assert!(module.contains("        Type = \"oneshot\";\n"));
+    assert!(module.contains(
+        "    systemd.timers.my-app = {\n\
+         \x20     description = \"Timer for my-app\";\n\
+         \x20     wantedBy = [ \"timers.target\" ];\n\
+         \x20     timerConfig = {\n\
+         \x20       OnCalendar = cfg.startAt;\n\
+         \x20       Persistent = cfg.persistent;\n\
+         \x20       RandomizedDelaySec = cfg.randomizedDelaySec;\n\
+         \x20     };\n\
+         \x20   };\n"
+    ));
+
+    let module = ServiceModule::<AppSettings>::new("services.my-app")
+        .timer("hourly")
+        .service_config("Type", "\"exec\"")
+        .generate();
+    assert_eq!(module.matches(" Type = ").count(), 1);
+    assert!(module.contains("        Type = \"exec\";\n"));
+}
+
+#[test]
+fn test_service_module_instances_timer() {
+    let module = ServiceModule::<Worker>::new("services.worker")
+        .instances()
+        .timer("daily")
+        .generate();
+
+    assert!(module.contains(
+        "    systemd.timers = lib.mapAttrs' (instance: settings:\n\
+         \x20     lib.nameValuePair \"worker-${instance}\" {\n\
+         \x20       description = \"Timer for worker (${instance})\";\n"
+    ));
+}
@@ -0,0 +1,174 @@
+//! Tests for the generated settings functions
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::NixosType;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Serialize, Deserialize, NixosType, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Low,
+    High,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(tag = "kind")]
+enum Mode {
+    Fast,
+    Slow,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(untagged)]
+enum Marker {
+    Unit,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct Limits {
+    max: u32,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(tag = "type")]
+struct Tagged {
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(tag = "type", rename = "renamed-tagged")]
+struct RenamedTagged {
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+#[serde(rename_all = "camelCase")]
+struct Settings {
+    listen_port: u16,
+    #[serde(rename = "log-level")]
+    log_level: Level,
+    #[serde(skip)]
+    #[allow(dead_code)]
+    cache: Option<u32>,
+    #[serde(flatten)]
+    limits: Limits,
+    #[serde(default)]
+    retries: u32,
+    timeout: Option<u32>,
+    mode: Mode,
+    marker: Marker,
+    backups: Vec<Limits>,
+    named: HashMap<String, Limits>,
+    fallback: Option<Limits>,
+}
+
+#[test]
+fn test_struct_settings_function() {
+    let function = Settings::nixos_to_settings_function();
+
+    assert!(function.starts_with("cfg: {\n"));
+    assert!(function.contains("  listenPort = cfg.listenPort;\n"));
+    assert!(function.contains("  log-level = levelToSettings cfg.log-level;\n"));
+    assert!(function.contains("  timeout = cfg.timeout;\n"));
+    assert!(function.contains("  mode = modeToSettings cfg.mode;\n"));
+    assert!(function.contains("  marker = markerToSettings cfg.marker;\n"));
+    assert!(!function.contains("cache"));
+}
+
+#[test]
+fn test_collection_conversions() {
+    let function = Settings::nixos_to_settings_function();
+
+    assert!(function.contains("  backups = map (v: limitsToSettings v) cfg.backups;\n"));
+    assert!(function.contains("  named = lib.mapAttrs (_: v: limitsToSettings v) cfg.named;\n"));
+    assert!(
+        function.contains("  fallback = lib.mapNullable (v: limitsToSettings v) cfg.fallback;\n")
+    );
+}
+
+#[derive(Serialize, Deserialize, NixosType)]
+struct PerLevel {
+    limits: BTreeMap<Level, Limits>,
+    weights: BTreeMap<Level, u32>,
+    overrides: BTreeMap<Level, Option<u32>>,
+}
+
+#[test]
+fn test_enum_keyed_maps_drop_unset_variants() {
+    // Enum-keyed maps are submodules whose unset variants are null
+    assert!(PerLevel::nixos_options_named_at("")
+        .contains("\"Low\" = lib.mkOption { type = types.nullOr limitsType; default = null; };"));
+
+    let function = PerLevel::nixos_to_settings_function();
+    assert!(function.contains(
+        "  limits = lib.mapAttrs (_: v: limitsToSettings v) (lib.filterAttrs (_: v: v != null) cfg.limits);\n"
+    ));
+    assert!(function.contains("  weights = lib.filterAttrs (_: v: v != null) cfg.weights;\n"));
+    // Null is a valid value of an `Option`
+    assert!(function.contains("  overrides = cfg.overrides;\n"));
+}
+
+#[test]
+fn test_flatten_and_default_fields_are_merged() {
+    let function = Settings::nixos_to_settings_function();
+
+    assert!(function.ends_with(
+        "}\n\
+         // limitsToSettings cfg.limits\n\
+         // lib.optionalAttrs (cfg.retries != null) { retries = cfg.retries; }"
+    ));
+    assert!(!function.contains("  retries ="));
+}
+
+#[test]
+fn test_enum_settings_functions() {
+    assert_eq!(Level::nixos_to_settings_function(), "v: v");
+    assert_eq!(Mode::nixos_to_settings_function(), "v: { kind = v; }");
+    assert_eq!(Marker::nixos_to_settings_function(), "v: null");
+}
+
+#[test]
+fn test_tagged_struct_settings_function() {
+    assert_eq!(
+        Tagged::nixos_to_settings_function(),
+        "cfg: {\n  type = \"Tagged\";\n  name = cfg.name;\n}"
+    );
+    assert_eq!(
+        RenamedTagged::nixos_to_settings_function(),
+        "cfg: {\n  type = \"renamed-tagged\";\n  name = cfg.name;\n}"
+    );
+}
+
+#[test]
+fn test_to_settings_binds_dependencies() {
+    let to_settings = Settings::nixos_to_settings();
+
+    assert!(to_settings.starts_with("let\n"));
+    assert!(to_settings.ends_with("in\nsettingsToSettings"));
+    assert!(to_settings.contains("  limitsToSettings = cfg: {\n    max = cfg.max;\n  };\n"));
+    assert!(to_settings.contains("  modeToSettings = v: { kind = v; };\n"));
+    assert_eq!(to_settings.matches("  limitsToSettings =").count(), 1);
+}
+
+#[test]
+fn test_settings_function_names_match_references() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct PoolType {
+        size: u32,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Outer {
+        pool: PoolType,
+    }
+
+    // The binding and the reference to it come from the same name
+    assert_eq!(PoolType::nixos_to_settings_name(), "poolTypeToSettings");
+    assert_eq!(
+        serde_nixos::type_registration!(PoolType).to_settings_name,
+        "poolTypeToSettings"
+    );
+    let to_settings = Outer::nixos_to_settings();
+    assert!(to_settings.contains("  poolTypeToSettings = cfg: {\n"));
+    assert!(to_settings.contains("  pool = poolTypeToSettings cfg.pool;\n"));
+    assert!(to_settings.ends_with("in\nouterToSettings"));
+}
@@ -0,0 +1,92 @@
+//! Tests for `#[nixos(default_value = "...", example_value = "...")]`
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::NixosType;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+fn option_block<'a>(options: &'a str, name: &str) -> &'a str {
+    let start = options
+        .find(&format!("{} = lib.mkOption", name))
+        .unwrap_or_else(|| panic!("missing option {}:\n{}", name, options));
+    let rest = &options[start..];
+    let end = rest.find("\n  };").unwrap_or(rest.len());
+    &rest[..end]
+}
+
+#[derive(Default, Serialize, Deserialize, NixosType)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+    #[default]
+    Safe,
+    Fast,
+}
+
+#[test]
+fn test_default_values() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Config {
+        #[nixos(default_value = "\"127.0.0.1\".to_string()")]
+        host: String,
+        #[nixos(default_value = "8080")]
+        port: u16,
+        #[nixos(default_value = "Duration::from_secs(30)")]
+        timeout: Duration,
+        #[nixos(default_value = "vec![\"a\".into(), \"${b}\".into()]")]
+        tags: Vec<String>,
+        #[nixos(default_value = "Mode::Fast")]
+        mode: Mode,
+        #[nixos(default_value = "Some(3)")]
+        retries: Option<u32>,
+        #[serde(default)]
+        #[nixos(default_value = "true")]
+        verbose: bool,
+    }
+
+    let options = Config::nixos_options();
+
+    assert!(option_block(&options, "host").contains("default = \"127.0.0.1\";"));
+    assert!(option_block(&options, "port").contains("default = 8080;"));
+    assert!(
+        option_block(&options, "timeout").contains("default = { nanos = 0; secs = 30; };"),
+        "{}",
+        options
+    );
+    assert!(option_block(&options, "tags").contains("default = [ \"a\" \"\\${b}\" ];"));
+    assert!(option_block(&options, "mode").contains("default = \"fast\";"));
+    assert!(option_block(&options, "retries").contains("default = 3;"));
+
+    // A Nix default keeps the field from becoming nullable
+    let verbose = option_block(&options, "verbose");
+    assert!(verbose.contains("type = types.bool;"), "{}", verbose);
+    assert!(verbose.contains("default = true;"), "{}", verbose);
+}
+
+#[test]
+fn test_example_values() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Config {
+        #[nixos(example_value = "\"db.example.com\".to_string()")]
+        host: String,
+        #[nixos(example_value = "vec![\"a\".into(), \"b\".into()]")]
+        peers: Vec<String>,
+        #[nixos(example_value = "BTreeMap::from([(\"alice\".to_string(), 1)])")]
+        quotas: BTreeMap<String, u32>,
+    }
+
+    let options = Config::nixos_options();
+
+    assert!(option_block(&options, "host").contains("example = \"db.example.com\";"));
+    assert!(
+        option_block(&options, "peers")
+            .contains("example = lib.literalExpression \"[ \\\"a\\\" \\\"b\\\" ]\";"),
+        "{}",
+        options
+    );
+    assert!(
+        option_block(&options, "quotas")
+            .contains("example = lib.literalExpression \"{ alice = 1; }\";"),
+        "{}",
+        options
+    );
+}
@@ -0,0 +1,170 @@
+//! Tests for the Rust-side `nixos_validate()` generated by `#[nixos(validate)]`
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::{NixosType, NixosValidationError};
+use std::collections::BTreeMap;
+
+fn messages(errors: Vec<NixosValidationError>) -> Vec<String> {
+    errors.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn test_valid_config_passes() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    #[nixos(validate)]
+    struct Config {
+        #[nixos(min = 1, max = 65535)]
+        port: u32,
+        #[nixos(non_empty, pattern = "[a-z]+")]
+        name: String,
+        limit: u64,
+    }
+
+    let config = Config {
+        port: 8080,
+        name: "web".to_string(),
+        limit: 100,
+    };
+    assert!(config.nixos_validate().is_ok());
+}
+
+#[test]
+fn test_scalar_violations_use_nix_names() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    #[nixos(validate)]
+    #[serde(rename_all = "camelCase")]
+    struct Config {
+        #[nixos(min = 1, max = 65535)]
+        listen_port: u32,
+        #[nixos(min = -1.5, max = 1.5)]
+        offset: f64,
+        #[serde(rename = "host-name")]
+        #[nixos(pattern = "[a-z][a-z0-9-]*")]
+        host_name: String,
+        #[nixos(non_empty)]
+        user: Option<String>,
+        max_bytes: u64,
+    }
+
+    let config = Config {
+        listen_port: 0,
+        offset: 2.0,
+        host_name: "Web01".to_string(),
+        user: Some(String::new()),
+        max_bytes: u64::MAX,
+    };
+    assert_eq!(
+        messages(config.nixos_validate().unwrap_err()),
+        vec![
+            "listenPort: must be at least 1, got 0",
+            "offset: must be at most 1.5, got 2",
+            "host-name: must match `[a-z][a-z0-9-]*`",
+            "user: must not be empty",
+            "maxBytes: 18446744073709551615 does not fit in a Nix integer (signed 64-bit)",
+        ]
+    );
+}
+
+#[test]
+fn test_collection_violations() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    #[nixos(validate)]
+    struct Config {
+        #[nixos(non_empty, unique, pattern = "[a-z]+")]
+        tags: Vec<String>,
+        #[nixos(non_empty)]
+        servers: Vec<String>,
+        #[nixos(max = 10)]
+        quotas: BTreeMap<String, u32>,
+    }
+
+    let config = Config {
+        tags: vec!["a".to_string(), "B".to_string(), "a".to_string()],
+        servers: Vec::new(),
+        quotas: BTreeMap::from([("ok".to_string(), 5), ("bad key".to_string(), 11)]),
+    };
+    assert_eq!(
+        messages(config.nixos_validate().unwrap_err()),
+        vec![
+            "tags: items must be unique",
+            "tags[1]: must match `[a-z]+`",
+            "servers: must not be empty",
+            "quotas.\"bad key\": must be at most 10, got 11",
+        ]
+    );
+}
+
+#[test]
+fn test_nested_types_are_validated() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Backend {
+        #[nixos(min = 1)]
+        weight: u8,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    #[nixos(validate)]
+    struct Config {
+        primary: Backend,
+        fallback: Option<Box<Backend>>,
+        pool: Vec<Backend>,
+    }
+
+    let config = Config {
+        primary: Backend { weight: 0 },
+        fallback: Some(Box::new(Backend { weight: 0 })),
+        pool: vec![Backend { weight: 1 }, Backend { weight: 0 }],
+    };
+    let errors = config.nixos_validate().unwrap_err();
+    assert_eq!(errors[0].path, "primary.weight");
+    assert_eq!(
+        messages(errors),
+        vec![
+            "primary.weight: must be at least 1, got 0",
+            "fallback.weight: must be at least 1, got 0",
+            "pool[1].weight: must be at least 1, got 0",
+        ]
+    );
+}
+
+#[test]
+fn test_flattened_fields_are_validated_under_their_option() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Inner {
+        #[nixos(min = 1)]
+        n: u8,
+    }
+
+    #[derive(Serialize, Deserialize, NixosType)]
+    #[nixos(validate)]
+    struct Outer {
+        #[serde(flatten)]
+        inner: Inner,
+    }
+
+    // Flattened fields are declared as a nested option under their name
+    let errors = Outer {
+        inner: Inner { n: 0 },
+    }
+    .nixos_validate()
+    .unwrap_err();
+    assert_eq!(messages(errors), vec!["inner.n: must be at least 1, got 0"]);
+}
+
+#[test]
+fn test_skipped_and_overridden_fields_are_not_checked() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    #[nixos(validate)]
+    struct Config {
+        #[nixos(skip)]
+        internal: u64,
+        #[nixos(type = "types.str")]
+        raw: u64,
+    }
+
+    let config = Config {
+        internal: u64::MAX,
+        raw: u64::MAX,
+    };
+    assert!(config.nixos_validate().is_ok());
+}
@@ -0,0 +1,241 @@
+//! Tests for translating `validator` and `garde` field rules into NixOS types
+
+use serde::{Deserialize, Serialize};
+use serde_nixos::NixosType;
+use validator::Validate;
+
+fn option_block<'a>(options: &'a str, name: &str) -> &'a str {
+    let start = options
+        .find(&format!("{} = lib.mkOption", name))
+        .unwrap_or_else(|| panic!("missing option {}:\n{}", name, options));
+    let rest = &options[start..];
+    let end = rest.find("\n  };").unwrap_or(rest.len());
+    &rest[..end]
+}
+
+fn check_name(name: &str) -> Result<(), validator::ValidationError> {
+    if name == "root" {
+        return Err(validator::ValidationError::new("reserved"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_validator_rules() {
+    #[derive(Serialize, Deserialize, Validate, NixosType)]
+    #[nixos(validate)]
+    struct Config {
+        #[validate(range(min = 1, max = 64))]
+        workers: u32,
+        #[validate(length(min = 1))]
+        name: String,
+        #[validate(length(min = 2, max = 8, message = "bad"))]
+        code: String,
+        #[validate(email)]
+        admin: Option<String>,
+        #[validate(length(max = 3))]
+        peers: Vec<String>,
+        #[validate(custom(function = "check_name"), length(min = 1))]
+        user: String,
+        #[validate(range(exclusive_min = 0.0))]
+        ratio: f64,
+    }
+
+    let options = Config::nixos_options();
+
+    let workers = option_block(&options, "workers");
+    assert!(
+        workers.contains("type = types.ints.between 1 64;"),
+        "{}",
+        workers
+    );
+    assert!(workers.contains("Constraints: value between 1 and 64."));
+
+    assert!(option_block(&options, "name").contains("type = types.nonEmptyStr;"));
+
+    // validator counts characters, Nix counts bytes
+    let code = option_block(&options, "code");
+    assert!(code.contains("type = types.str;"), "{}", code);
+    assert!(!code.contains("Constraints:"), "{}", code);
+    assert!(
+        code.contains(
+            "Further validated by the program: `length(min = 2, max = 8, message = \\\"bad\\\")`."
+        ),
+        "{}",
+        code
+    );
+
+    let admin = option_block(&options, "admin");
+    assert!(
+        admin
+            .contains("type = types.nullOr (types.strMatching \"[^@[:space:]]+@[^@[:space:]]+\");"),
+        "{}",
+        admin
+    );
+
+    let peers = option_block(&options, "peers");
+    assert!(
+        peers.contains(
+            "type = types.addCheck (types.listOf types.str) (l: builtins.length l <= 3);"
+        ),
+        "{}",
+        peers
+    );
+
+    let user = option_block(&options, "user");
+    assert!(user.contains("type = types.nonEmptyStr;"), "{}", user);
+    assert!(
+        user.contains("Further validated by the program: `custom(function = \"check_name\")`."),
+        "{}",
+        user
+    );
+
+    let ratio = option_block(&options, "ratio");
+    assert!(ratio.contains("type = types.float;"), "{}", ratio);
+    assert!(
+        ratio.contains("Further validated by the program: `range(exclusive_min = 0.0)`."),
+        "{}",
+        ratio
+    );
+
+    // The translated rules agree with validator's own checks; `code` is
+    // only checked by validator
+    let config = Config {
+        workers: 0,
+        name: "web".to_string(),
+        code: "x".to_string(),
+        admin: Some("not-an-email".to_string()),
+        peers: vec!["a".into(), "b".into(), "c".into(), "d".into()],
+        user: "nobody".to_string(),
+        ratio: 0.5,
+    };
+    let nixos_errors: Vec<String> = config
+        .nixos_validate()
+        .unwrap_err()
+        .into_iter()
+        .map(|error| error.path)
+        .collect();
+    assert_eq!(nixos_errors, vec!["workers", "admin", "peers"]);
+    let validator_errors = config.validate().unwrap_err();
+    let mut fields: Vec<_> = validator_errors.field_errors().into_keys().collect();
+    fields.sort();
+    assert_eq!(fields, vec!["admin", "code", "peers", "workers"]);
+}
+
+#[test]
+fn test_explicit_nixos_constraints_take_precedence() {
+    #[derive(Serialize, Deserialize, Validate, NixosType)]
+    struct Config {
+        #[validate(range(min = 1, max = 64))]
+        #[nixos(max = 32)]
+        workers: u32,
+    }
+
+    let options = Config::nixos_options();
+    assert!(
+        options.contains("type = types.ints.between 1 32;"),
+        "{}",
+        options
+    );
+}
+
+#[test]
+fn test_garde_rules() {
+    // `garde` isn't a dependency here; `NixosType` reads its attributes
+    // all the same.
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Config {
+        #[garde(range(min = -10, max = 10))]
+        offset: i32,
+        #[garde(length(bytes, min = 3), pattern(r"^[a-z]+$"))]
+        slug: String,
+        #[garde(prefix("https://"))]
+        endpoint: String,
+        #[garde(pattern("[0-9]"))]
+        tag: String,
+        // Lazy quantifiers aren't POSIX ERE
+        #[garde(pattern("^[a-z]+?$"))]
+        word: String,
+        #[garde(dive)]
+        inner: Vec<String>,
+        #[garde(ipv4)]
+        address: String,
+        #[garde(custom(check_name), length(graphemes, max = 5))]
+        label: String,
+    }
+
+    let options = Config::nixos_options();
+
+    assert!(options.contains("type = types.ints.between (-10) 10;"));
+
+    let slug = option_block(&options, "slug");
+    assert!(
+        slug.contains(
+            "type = types.addCheck (types.strMatching \"[a-z]+\") \
+             (s: builtins.stringLength s >= 3);"
+        ),
+        "{}",
+        slug
+    );
+
+    let endpoint = option_block(&options, "endpoint");
+    assert!(
+        endpoint.contains("type = types.strMatching \"https://.*\";"),
+        "{}",
+        endpoint
+    );
+
+    // garde patterns search anywhere in the string
+    let tag = option_block(&options, "tag");
+    assert!(
+        tag.contains("type = types.strMatching \".*([0-9]).*\";"),
+        "{}",
+        tag
+    );
+
+    let word = option_block(&options, "word");
+    assert!(word.contains("type = types.str;"), "{}", word);
+    assert!(
+        word.contains("Further validated by the program: `pattern(\\\"^[a-z]+?$\\\")`."),
+        "{}",
+        word
+    );
+
+    let inner = option_block(&options, "inner");
+    assert!(
+        inner.contains("type = types.listOf types.str;"),
+        "{}",
+        inner
+    );
+    assert!(!inner.contains("description"), "{}", inner);
+
+    assert!(option_block(&options, "address").contains("types.strMatching \"((25[0-5]"));
+
+    let label = option_block(&options, "label");
+    assert!(label.contains("type = types.str;"), "{}", label);
+    assert!(
+        label.contains(
+            "Further validated by the program: `custom(check_name)`, `length(graphemes, max = 5)`."
+        ),
+        "{}",
+        label
+    );
+}
+
+#[test]
+fn test_rules_on_adapted_fields_are_described() {
+    #[derive(Serialize, Deserialize, NixosType)]
+    struct Config {
+        #[garde(length(min = 1))]
+        #[nixos(type = "types.lines")]
+        motd: String,
+    }
+
+    let options = Config::nixos_options();
+    assert!(options.contains("type = types.lines;"), "{}", options);
+    assert!(
+        options.contains("Further validated by the program: `length(min = 1)`."),
+        "{}",
+        options
+    );
+}